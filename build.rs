@@ -1,6 +1,28 @@
+use std::fmt::Write as _;
 use std::io;
+use std::path::Path;
 #[cfg(target_os = "windows")] use winres::WindowsResource;
 
+#[allow(dead_code)]
+#[path = "src/models/graphics_lib/shader_preprocessor.rs"]
+mod shader_preprocessor;
+
+use naga::proc::Layouter;
+use shader_preprocessor::ShaderPreprocessor;
+
+// graphics_model.rs 中手写的 #[repr(C)] struct 与对应的 WGSL struct：(Rust struct, WGSL 文件, WGSL struct)
+// Rust 字段名为 WGSL 字段名加上前缀 _，WGSL 中以 _ 开头的填充字段同名
+const STRUCT_MIRRORS: [(&str, &str, &str); 5] = [
+    ("Node", "common.wgsl", "Node"),
+    ("Bound", "common.wgsl", "Bound"),
+    ("BHTree", "solver.wgsl", "BHTree"),
+    ("BHTreeNode", "solver.wgsl", "BHTreeNode"),
+    ("ComputeUniforms", "solver.wgsl", "Uniforms"),
+];
+
+// ComputeUniforms 的字段没有前缀
+const UNPREFIXED_STRUCTS: [&str; 1] = ["ComputeUniforms"];
+
 fn main() -> io::Result<()> {
     #[cfg(target_os = "windows")] {
         WindowsResource::new()
@@ -8,5 +30,55 @@ fn main() -> io::Result<()> {
             .set_icon("resources/app_icon.ico")
             .compile()?;
     }
-    Ok(())
-}
\ No newline at end of file
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=resources/app_icon.ico");
+    println!("cargo:rerun-if-changed=src/assets/shaders");
+    println!("cargo:rerun-if-changed=src/models/graphics_lib/shader_preprocessor.rs");
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("wgsl_layout.rs"), wgsl_layout_assertions())
+}
+
+// 生成 graphics_model.rs 中 include! 的常量断言，Rust struct 的大小、对齐或字段偏移与 WGSL 不一致时编译失败
+fn wgsl_layout_assertions() -> String {
+
+    let mut output = String::from("// 由 build.rs 根据 WGSL struct 的内存布局生成\nconst _: () = {\n    use std::mem::{align_of, offset_of, size_of};\n");
+
+    for (rust_name, wgsl_file, wgsl_name) in STRUCT_MIRRORS {
+
+        let shader = ShaderPreprocessor::default().process(wgsl_file).unwrap_or_else(|error| panic!("{}", error));
+        let module = naga::front::wgsl::parse_str(&shader.source)
+            .unwrap_or_else(|error| panic!("{}", error.emit_to_string_with_path(&shader.source, wgsl_file)));
+        let mut layouter = Layouter::default();
+        layouter.update(&module.types, &module.constants).unwrap_or_else(|error| panic!("{}: {}", wgsl_file, error));
+
+        let (handle, members) = module.types.iter()
+            .find_map(|(handle, ty)| match &ty.inner {
+                naga::TypeInner::Struct { members, .. } if ty.name.as_deref() == Some(wgsl_name) => Some((handle, members)),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("WGSL struct {} not found in {}", wgsl_name, wgsl_file));
+        let layout = &layouter[handle];
+
+        let wgsl = format!("WGSL struct {} in {}", wgsl_name, wgsl_file);
+        writeln!(output).unwrap();
+        writeln!(output, "    assert!(size_of::<{}>() == {}, \"Size of {} differs from {} ({} bytes)\");",
+            rust_name, layout.size, rust_name, wgsl, layout.size).unwrap();
+        writeln!(output, "    assert!({} % align_of::<{}>() == 0, \"Alignment of {} exceeds {} ({} bytes)\");",
+            layout.alignment, rust_name, rust_name, wgsl, layout.alignment).unwrap();
+
+        for member in members {
+            let wgsl_field = member.name.as_deref().unwrap();
+            let rust_field = match wgsl_field.starts_with('_') || UNPREFIXED_STRUCTS.contains(&rust_name) {
+                true => String::from(wgsl_field),
+                false => format!("_{}", wgsl_field),
+            };
+            writeln!(output, "    assert!(offset_of!({}, {}) == {}, \"Offset of {}.{} differs from {}.{} in {} ({} bytes)\");",
+                rust_name, rust_field, member.offset, rust_name, rust_field, wgsl_name, wgsl_field, wgsl_file, member.offset).unwrap();
+        }
+    }
+
+    output += "};\n";
+    output
+}
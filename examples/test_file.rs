@@ -21,6 +21,7 @@ async fn main() {
                 test_edge_data.to_str().unwrap(),
                 0,
                 1,
+                1.0,
             ).unwrap()
         );
         Box::new(app)
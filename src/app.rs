@@ -14,6 +14,7 @@ use crate::{
         app_model::{AppModel, MainStage},
         data_model::DataModel,
         graphics_model::GraphicsModel,
+        timeline_model::TimelineModel,
         Models,
     },
 };
@@ -99,6 +100,7 @@ impl MainApp {
                 graphics_model: GraphicsModel::init(cc),
                 data_model: DataModel::default(),
                 app_model: AppModel::default(),
+                timeline_model: TimelineModel::default(),
             },
             inspector_view: Default::default(),
             drawer_view: Default::default(),
@@ -117,6 +119,11 @@ impl MainApp {
 impl eframe::App for MainApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         self.models.app_model.ui_frame_count += 1u32;
+        self.models.poll_edge_watcher();
+        self.models.update_timeline(ctx.input().stable_dt as f64);
+        if self.models.timeline_model.is_playing {
+            ctx.request_repaint();
+        }
 
         egui::CentralPanel::default()
             .frame(egui::Frame::none())
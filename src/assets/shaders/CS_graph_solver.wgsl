@@ -1,64 +1,27 @@
-struct Node {
-    position: vec3<f32>,
-    _empty_1: i32,
-    force: vec3<f32>,
-    _empty_2: i32,
-    prev_force: vec3<f32>,
-    mass: atomic<u32>,
-};
-
-struct Uniforms {
-    frame_num: u32,
-    node_count: u32,
-    edge_count: u32,
-    edge_sort_count: u32,
-    tree_node_count: u32,
-    bounding_count: u32,
-    kernel_status_count: u32,
-};
-
-struct Bound {
-    bound_min: vec3<f32>,
-    bound_max: vec3<f32>,
-}
-
-struct BHTree {
-    max_depth: atomic<u32>,
-    bottom: atomic<u32>,
-    radius: f32,
-    _empty: i32,
-}
-
-struct BHTreeNode {
-    position: vec3<f32>,
-    _empty: i32,
-    mass: atomic<i32>,
-    count: i32,
-    start: atomic<i32>,
-    sort: i32,
-}
+#include "common.wgsl"
+#include "solver.wgsl"
+#include "kernel_status.wgsl"
 
-struct Kvp {
-    sort_key: f32,
-    index: u32,
+struct GroupRange {
+    start: u32,
+    end: u32,
 }
 
-struct KvpParam {
-    dim: u32,
-    block_count: u32,
+struct NodeEdgeSortRange {
+    min: atomic<u32>,
+    max: atomic<u32>,
 }
 
-struct Transform {
-    view: mat4x4<f32>,
-    projection: mat4x4<f32>,
-    time: vec4<f32>,
-    screen: vec4<f32>,
-    camera: vec4<f32>,
+// 边排序 Buffer 的分块，offset 为分块在整个边排序数组中的起始位置
+struct EdgeChunk {
+    offset: u32,
+    count: u32,
 }
 
-struct NodeEdgeSortRange {
-    min: atomic<u32>,
-    max: atomic<u32>,
+// validate_nodes 隔离的节点，count 可能超过列表长度
+struct NodeQuarantine {
+    count: atomic<u32>,
+    nodes: array<u32>,
 }
 
 @group(0) @binding(0)  var<uniform>             uniforms:               Uniforms;
@@ -70,13 +33,46 @@ struct NodeEdgeSortRange {
 @group(0) @binding(6)  var<storage, read_write> tree_node_src:          array<BHTreeNode>;
 @group(0) @binding(7)  var<storage, read_write> tree_child_src:         array<atomic<i32>>;
 @group(0) @binding(8)  var<storage, read_write> kvps:                   array<Kvp>;
-@group(0) @binding(9)  var<uniform>             kvps_param:             KvpParam;
 @group(0) @binding(10) var<uniform>             transform:              Transform;
 @group(0) @binding(11) var<storage, read_write> kernel_status:          array<i32>;
 @group(0) @binding(12) var<storage, read_write> edge_sort_node:         array<vec2<u32>>;
 @group(0) @binding(13) var<storage, read_write> edge_sort_dir:          array<vec3<f32>>;
 @group(0) @binding(14) var<storage, read_write> node_edge_sort_range:   array<NodeEdgeSortRange>;
 @group(0) @binding(15) var<storage, read_write> node_copy_src:          array<f32>;
+@group(0) @binding(16) var<storage, read>       node_flag_src:          array<u32>;
+@group(0) @binding(17) var<storage, read>       node_group_src:         array<u32>;
+@group(0) @binding(18) var<storage, read>       group_member_src:       array<u32>;
+@group(0) @binding(19) var<storage, read>       group_range_src:        array<GroupRange>;
+@group(0) @binding(20) var<storage, read_write> group_centroid_src:     array<vec4<f32>>;
+@group(0) @binding(21) var<storage, read_write> group_force_src:        array<vec4<f32>>;
+@group(0) @binding(22) var<storage, read_write> sort_key_dst:           array<u32>;
+@group(0) @binding(23) var<storage, read_write> sort_value_dst:         array<u32>;
+@group(0) @binding(24) var<storage, read>       sorted_value_src:       array<u32>;
+@group(0) @binding(25) var<storage, read>       sorted_key_src:         array<u32>;
+@group(0) @binding(26) var<uniform>             edge_chunk:             EdgeChunk;
+@group(0) @binding(27) var<storage, read_write> last_valid_position:    array<vec4<f32>>;
+@group(0) @binding(28) var<storage, read_write> node_quarantine:        NodeQuarantine;
+
+// NODE_FLAG_*、MAX_QUARANTINED_NODES、QUARANTINE_NO_PREVIOUS 与 WORKGROUP_SIZE 由 graphics_model.rs 中的常量定义
+
+// 浮点数映射为保持大小顺序的 u32，须同步修改 radix_sort.rs 中的 float_sort_key
+fn float_sort_key(value: f32) -> u32 {
+    let bits = bitcast<u32>(value);
+    if ((bits & 0x80000000u) != 0u) {
+        return ~bits;
+    }
+    return bits | 0x80000000u;
+}
+
+// 固定与删除的节点都不移动
+fn is_pinned(index: u32) -> bool {
+    return (node_flag_src[index] & (NODE_FLAG_PINNED | NODE_FLAG_HIDDEN)) != 0u;
+}
+
+// 删除与时间轴之外的节点不插入八叉树，不参与包围盒、引力与分组质心，因此不产生也不受到斥力
+fn is_hidden(index: u32) -> bool {
+    return (node_flag_src[index] & NODE_FLAG_HIDDEN) != 0u;
+}
 
 fn hash(s: u32) -> u32 {
     var t : u32 = s;
@@ -97,9 +93,14 @@ fn random_xy(seed_x: u32, seed_y: u32) -> f32 {
     return f32(hash(hash(seed_x) + seed_y)) / 4294967295.0; // 2^32-1
 }
 
+// 由用户设置的 seed 和帧号得到随机数种子，axis 为 0 / 1 / 2
+fn frame_seed(axis: u32) -> u32 {
+    return hash(uniforms.seed) + axis + 3u * uniforms.frame_num;
+}
+
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn init_kernel_status(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
     let total = uniforms.kernel_status_count;
@@ -110,11 +111,14 @@ fn init_kernel_status(@builtin(global_invocation_id) global_invocation_id: vec3<
 
     kernel_status[index] = 0;
 
+    if (index == 0u) {
+        atomicStore(&node_quarantine.count, 0u);
+    }
 }
 
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn gen_node(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
     let total = uniforms.node_count;
@@ -125,9 +129,9 @@ fn gen_node(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
     var vPos : vec3<f32> = node_src[index].position;
 
-    vPos.x = random_xy(index, 0u + 3u * uniforms.frame_num) * 2.0 - 1.0;
-    vPos.y = random_xy(index, 1u + 3u * uniforms.frame_num) * 2.0 - 1.0;
-    vPos.z = random_xy(index, 2u + 3u * uniforms.frame_num) * 2.0 - 1.0;
+    vPos.x = random_xy(index, frame_seed(0u)) * 2.0 - 1.0;
+    vPos.y = random_xy(index, frame_seed(1u)) * 2.0 - 1.0;
+    vPos.z = random_xy(index, frame_seed(2u)) * 2.0 - 1.0;
 //    vPos.x = 0.0;
 //    vPos.y = 0.0;
 //    vPos.z = 0.0;
@@ -144,9 +148,27 @@ fn gen_node(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 }
 
+// 增量修改边之后重置与边相关的节点状态，保留坐标，之后重新运行 cal_mass 与边排序
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn reset_edge_state(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+
+    let total = uniforms.node_count;
+    let index = global_invocation_id.x;
+    if (index >= total) {
+        return;
+    }
+
+    atomicStore(&node_src[index].mass, 1u);
+    spring_force_src[index] = vec3<f32>(0.0);
+
+    atomicStore(&node_edge_sort_range[index].min, 0u);
+    atomicStore(&node_edge_sort_range[index].max, 0u);
+}
+
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn cal_mass(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.edge_count;
     let index = global_invocation_id.x;
@@ -164,11 +186,11 @@ fn cal_mass(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn cal_gravity_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.node_count;
     let index = global_invocation_id.x;
-    if (index >= total) {
+    if (index >= total || is_hidden(index)) {
         return;
     }
 
@@ -196,7 +218,7 @@ fn cal_gravity_force(@builtin(global_invocation_id) global_invocation_id: vec3<u
 }
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn prepare_edge_sort(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.edge_count;
     let index = global_invocation_id.x;
@@ -206,61 +228,52 @@ fn prepare_edge_sort(@builtin(global_invocation_id) global_invocation_id: vec3<u
 
     var edge = edge_src[index];
 
-    edge_sort_node[index * 2u] = edge;
-    edge_sort_node[index * 2u + 1u] = vec2<u32>(edge[1], edge[0]);
+    // 每条边正反两个方向各一项，以起点为键，终点为值
+    sort_key_dst[index * 2u] = edge[0];
+    sort_value_dst[index * 2u] = edge[1];
+    sort_key_dst[index * 2u + 1u] = edge[1];
+    sort_value_dst[index * 2u + 1u] = edge[0];
 }
 
+// 以下边排序相关的 Kernel 按分块 dispatch，edge_sort_node / edge_sort_dir 只包含当前分块
+// 线程序号为分块内的位置，加上 edge_chunk.offset 为全局位置
 @compute
-@workgroup_size(256)
-fn sort_edge(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let i = global_invocation_id.x;
-    var j = i ^ kvps_param.block_count;
-
-    if (kvps_param.block_count == kvps_param.dim >> 1u) {
-        j = i ^ (kvps_param.block_count * 2u - 1u);
-    }
-
-    let total = uniforms.edge_sort_count;
-    if (j < i || i >= total || j >= total) {
+@workgroup_size(WORKGROUP_SIZE)
+fn apply_edge_sort(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+    let local = global_invocation_id.x;
+    if (local >= edge_chunk.count) {
         return;
     }
 
-    let edge_i = edge_sort_node[i];
-    let edge_j = edge_sort_node[j];
-
-    if (edge_j[0] < edge_i[0]) {
-        edge_sort_node[i] = edge_j;
-        edge_sort_node[j] = edge_i;
-    }
+    let i = edge_chunk.offset + local;
+    edge_sort_node[local] = vec2<u32>(sorted_key_src[i], sorted_value_src[i]);
 }
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn compute_node_edge_sort_range(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let i = global_invocation_id.x;
-
-    let total = uniforms.edge_sort_count;
-    if (i >= total) {
+    let local = global_invocation_id.x;
+    if (local >= edge_chunk.count) {
         return;
     }
 
-    let node_index = edge_sort_node[i][0];
+    let i = edge_chunk.offset + local;
+    let node_index = edge_sort_node[local][0];
 
     atomicStore(&node_edge_sort_range[node_index].min, i);
     atomicStore(&node_edge_sort_range[node_index].max, i + 1u);
 
 }
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn compute_node_edge_sort_range_2(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let i = global_invocation_id.x;
-
-    let total = uniforms.edge_sort_count;
-    if (i >= total) {
+    let local = global_invocation_id.x;
+    if (local >= edge_chunk.count) {
         return;
     }
 
-    let node_index = edge_sort_node[i][0];
+    let i = edge_chunk.offset + local;
+    let node_index = edge_sort_node[local][0];
 
     atomicMin(&node_edge_sort_range[node_index].min, i);
     atomicMax(&node_edge_sort_range[node_index].max, i + 1u);
@@ -268,10 +281,10 @@ fn compute_node_edge_sort_range_2(@builtin(global_invocation_id) global_invocati
 }
 
 
-var<workgroup> local_sum: array<vec3<f32>, 256>;
+var<workgroup> local_sum: array<vec3<f32>, WORKGROUP_SIZE>;
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn spring_force_reduction(
     @builtin(local_invocation_index) local_index: u32,
     @builtin(global_invocation_id) global_id: vec3<u32>,
@@ -280,19 +293,21 @@ fn spring_force_reduction(
 
     var skip = false;
 
-    var index = global_id.x;
-    let total = uniforms.edge_sort_count;
-    if (index >= total) {
-        index = total - 1u;
+    // 分块大小为 256 的整数倍，工作组与全局的 256 对齐分段一致
+    var local = global_id.x;
+    let total = edge_chunk.count;
+    if (local >= total) {
+        local = total - 1u;
     }
+    let index = edge_chunk.offset + local;
 
-    var edge = edge_sort_node[index];
+    var edge = edge_sort_node[local];
     let source_node: u32 = edge[0];
     let target_node: u32 = edge[1];
     var dir = node_src[target_node].position - node_src[source_node].position;
     local_sum[local_index] = dir;
 
-    if (index >= total) {
+    if (local >= total) {
         skip = true;
         local_sum[local_index] = vec3<f32>(0.0);
     }
@@ -311,9 +326,9 @@ fn spring_force_reduction(
     workgroupBarrier();
 
     var start = u32(max(min_relative_index, 0));
-    var end = u32(min(max_relative_index, 256));
+    var end = u32(min(max_relative_index, i32(WORKGROUP_SIZE)));
 
-    for (var s = 256u / 2u; s > 0u; s >>= 1u) {
+    for (var s = WORKGROUP_SIZE / 2u; s > 0u; s >>= 1u) {
 
         if (!skip && local_index < start + s) {
             let k = local_index + s;
@@ -334,11 +349,11 @@ fn spring_force_reduction(
 //        edge_sort_src[index].dir = dir_sum;
     }
         let dir_sum = local_sum[local_index];
-        edge_sort_dir[index] = dir_sum;
+        edge_sort_dir[local] = dir_sum;
 }
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn spring_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.node_count;
     let index = global_invocation_id.x;
@@ -353,19 +368,30 @@ fn spring_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
         return;
     }
 
-    spring_force_src[index] += edge_sort_dir[range_min];
-    for (var i = u32(range_min) - (u32(range_min) % 256u) + 256u; i < u32(range_max); i += 256u) {
-        spring_force_src[index] += edge_sort_dir[i];
+    // 只累加区间中落在当前分块内的部分
+    let chunk_min = edge_chunk.offset;
+    let chunk_max = edge_chunk.offset + edge_chunk.count;
+
+    if (range_min >= chunk_min && range_min < chunk_max) {
+        spring_force_src[index] += edge_sort_dir[range_min - chunk_min];
+    }
+    let start = max(range_min - (range_min % WORKGROUP_SIZE) + WORKGROUP_SIZE, chunk_min);
+    let end = min(range_max, chunk_max);
+    for (var i = start; i < end; i += WORKGROUP_SIZE) {
+        spring_force_src[index] += edge_sort_dir[i - chunk_min];
     }
 
 }
 
-var<workgroup> smin: array<vec3<f32>, 256>;
-var<workgroup> smax: array<vec3<f32>, 256>;
+// 隐藏节点在包围盒归约中的取值
+let HIDDEN_BOUND: f32 = 3.0e38;
+
+var<workgroup> smin: array<vec3<f32>, WORKGROUP_SIZE>;
+var<workgroup> smax: array<vec3<f32>, WORKGROUP_SIZE>;
 
 
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn reduction_bounding(
     @builtin(local_invocation_index) local_index: u32,
     @builtin(global_invocation_id) global_id: vec3<u32>,
@@ -378,11 +404,17 @@ fn reduction_bounding(
         index = total - 1u;
     }
 
-    smin[local_index] = node_src[index].position;
-    smax[local_index] = node_src[index].position;
+    // 隐藏的节点取不影响归约结果的值
+    if (is_hidden(index)) {
+        smin[local_index] = vec3<f32>(HIDDEN_BOUND);
+        smax[local_index] = vec3<f32>(-HIDDEN_BOUND);
+    } else {
+        smin[local_index] = node_src[index].position;
+        smax[local_index] = node_src[index].position;
+    }
     workgroupBarrier();
 
-    for (var s = 256u / 2u; s > 0u; s >>= 1u) {
+    for (var s = WORKGROUP_SIZE / 2u; s > 0u; s >>= 1u) {
         if (local_index < s) {
             let k = local_index + s;
             smin[local_index] = min(smin[local_index], smin[k]);
@@ -399,7 +431,7 @@ fn reduction_bounding(
 
 // 5
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn reduction_bounding_2(
     @builtin(local_invocation_index) local_index: u32,
     @builtin(global_invocation_id) global_id: vec3<u32>,
@@ -416,7 +448,7 @@ fn reduction_bounding_2(
     smax[local_index] = bounding[index].bound_max;
     workgroupBarrier();
 
-    for (var s = 256u / 2u; s > 0u; s >>= 1u) {
+    for (var s = WORKGROUP_SIZE / 2u; s > 0u; s >>= 1u) {
         if (local_index < s) {
             let k = local_index + s;
             smin[local_index] = min(smin[local_index], smin[k]);
@@ -437,6 +469,11 @@ fn reduction_bounding_2(
 fn bounding_box() {
     var bound_min_min = bounding[0].bound_min;
     var bound_max_max = bounding[0].bound_max;
+    // 所有节点都隐藏时包围盒为空
+    if (any(bound_min_min > bound_max_max)) {
+        bound_min_min = vec3<f32>(0.0);
+        bound_max_max = vec3<f32>(0.0);
+    }
 
     let box = bound_max_max - bound_min_min;
     let tree_node_count = uniforms.tree_node_count - 1u;
@@ -452,7 +489,7 @@ fn bounding_box() {
 
 // 7
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn clear_1(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.tree_node_count;
     let index = global_invocation_id.x;
@@ -467,7 +504,7 @@ fn clear_1(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 // 8
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     var index = global_invocation_id.x;
     let node_count = uniforms.node_count;
@@ -486,19 +523,33 @@ fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
     var root_r = bhTree.radius;
     var r = root_r * 0.5;
 
-    var loop_limit_count = 1000;
+    // 当前插入的节点，确定性模式下可能换成与之重合、编号更大的节点
+    var body = index;
+    let is_deterministic = uniforms.is_deterministic != 0u;
+
+    // 确定性模式下放宽上限，超出时报错，避免部分节点未插入却继续计算
+    var loop_limit_count = select(1000, 100000, is_deterministic);
 
     while (index < node_count) {
 
         if (loop_limit_count < 0) {
-            kernel_status[1] = -101;
+            // 八叉树耗尽时其他线程也会超出上限，保留更具体的状态码
+            if (kernel_status[KERNEL_TREE_BUILDING] != STATUS_TREE_NODE_EXHAUSTED) {
+                kernel_status[KERNEL_TREE_BUILDING] = select(STATUS_LOOP_LIMIT_WARNING, STATUS_LOOP_LIMIT, is_deterministic);
+            }
             break;
         }
         loop_limit_count--;
 
         if (skip != 0) {
+            if (is_hidden(body)) {
+                index += inc;
+                body = index;
+                skip = 1;
+                continue;
+            }
             skip = 0;
-            pos = node_src[index].position;
+            pos = node_src[body].position;
 
             n = tree_node_count;
             r = root_r * 0.5;
@@ -534,10 +585,11 @@ fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
         if (ch != -2) {
             if (ch == -1) {
                 var v = -1;
-                let origin = atomicCompareExchangeWeak(&tree_child_src[locked], v, i32(index));
+                let origin = atomicCompareExchangeWeak(&tree_child_src[locked], v, i32(body));
                 if (origin == -1) {
                     local_max_depth = max(depth, local_max_depth);
                     index += inc;
+                    body = index;
                     skip = 1;
                 } else {
                      skip = 0;
@@ -549,10 +601,23 @@ fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
                 if (ch == origin) {
                     // lock 成功，如果两个点的位置相同，做一点微小偏移就行了
                     if (all(node_src[ch].position == pos)) {
-                        node_src[index].position += vec3<f32>(random_xy(index, 0u + 3u * uniforms.frame_num), random_xy(index, 1u + 3u * uniforms.frame_num), random_xy(index, 2u + 3u * uniforms.frame_num)) * 0.2 - 0.1;
+                        if (is_deterministic) {
+                            // 总是偏移编号较大的节点并重新插入，结果与线程到达顺序无关
+                            if (u32(ch) > body) {
+                                atomicStore(&tree_child_src[locked], i32(body));
+                                body = u32(ch);
+                            } else {
+                                atomicStore(&tree_child_src[locked], ch);
+                            }
+                            node_src[body].position += vec3<f32>(random_xy(body, frame_seed(0u)), random_xy(body, frame_seed(1u)), random_xy(body, frame_seed(2u))) * 0.2 - 0.1;
+                            skip = 1;
+                            kernel_status[KERNEL_ATTRACTIVE_FORCE] = STATUS_COINCIDENT_NODES;
+                            continue;
+                        }
+                        node_src[body].position += vec3<f32>(random_xy(body, frame_seed(0u)), random_xy(body, frame_seed(1u)), random_xy(body, frame_seed(2u))) * 0.2 - 0.1;
                         skip = 0;
                         atomicStore(&tree_child_src[locked], ch);
-                        kernel_status[0] = -3;
+                        kernel_status[KERNEL_ATTRACTIVE_FORCE] = STATUS_COINCIDENT_NODES;
                         break;
                     }
 
@@ -563,6 +628,7 @@ fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
                         // 1. create new cell
                         let cell = atomicSub(&bhTree.bottom, 1u) - 1u;
                         if (cell <= node_count) {
+                            kernel_status[KERNEL_TREE_BUILDING] = STATUS_TREE_NODE_EXHAUSTED;
                             return;
                         }
 
@@ -597,9 +663,10 @@ fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
                         }
 
                     };
-                    atomicStore(&tree_child_src[n * 8u + j], i32(index));
+                    atomicStore(&tree_child_src[n * 8u + j], i32(body));
                     local_max_depth = max(depth, local_max_depth);
                     index += inc;
+                    body = index;
                     skip = 2;
                 }
             }
@@ -614,7 +681,7 @@ fn tree_building(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
 
 // 9
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn clear_2(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.tree_node_count - 1u;
     let index = global_invocation_id.x;
@@ -630,7 +697,7 @@ fn clear_2(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 // 10
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn summarization(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let bottom = atomicLoad(&bhTree.bottom);
     let tree_node_count = uniforms.tree_node_count - 1u;
@@ -646,14 +713,14 @@ fn summarization(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
     var smass: array<i32, 8>;
     let restart = index;
 
-    var loop_limit_count = 10000;
+    var loop_limit_count = select(10000, 1000000, uniforms.is_deterministic != 0u);
 
     for (var j = 0; j < 5; j++) {
 
         while (index <= tree_node_count) {
 
             if (loop_limit_count < 0) {
-                kernel_status[2] = 500 + j;
+                kernel_status[KERNEL_SUMMARIZATION] = STATUS_SUMMARIZATION_STALL + j;
                 break;
             }
             loop_limit_count--;
@@ -704,7 +771,7 @@ fn summarization(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
     while (index <= tree_node_count) {
 
         if (loop_limit_count < 0) {
-            kernel_status[2] = 101;
+            kernel_status[KERNEL_SUMMARIZATION] = STATUS_LOOP_LIMIT;
             break;
         }
         loop_limit_count--;
@@ -775,7 +842,7 @@ fn summarization(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
 
 // 11
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn sort(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let tree_node_count = uniforms.tree_node_count - 1u;
     let bottom = atomicLoad(&bhTree.bottom);
@@ -783,12 +850,13 @@ fn sort(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let inc = min(node_count, 16384u);
     var index = tree_node_count + 1u - inc + global_invocation_id.x;
 
-    var loop_limit_count = 1000;
+    let is_deterministic = uniforms.is_deterministic != 0u;
+    var loop_limit_count = select(1000, 100000, is_deterministic);
 
     while (index >= bottom) {
 
         if (loop_limit_count < 0) {
-            kernel_status[3] = -101;
+            kernel_status[KERNEL_SORT] = select(STATUS_LOOP_LIMIT_WARNING, STATUS_LOOP_LIMIT, is_deterministic);
             break;
         }
         loop_limit_count--;
@@ -830,7 +898,7 @@ fn sort(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 // 12
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn electron_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let tree_node_count = uniforms.tree_node_count - 1u;
     let node_count = uniforms.node_count;
@@ -871,7 +939,7 @@ fn electron_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>
             loop {
 
                 if (loop_limit_count < 0) {
-                    kernel_status[4] = 101;
+                    kernel_status[KERNEL_ELECTRON_FORCE] = STATUS_LOOP_LIMIT;
                     break;
                 }
                 loop_limit_count--;
@@ -881,7 +949,7 @@ fn electron_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>
                 while (pd < 8u) {
 
                     if (loop_limit_count < 0) {
-                        kernel_status[4] = 101;
+                        kernel_status[KERNEL_ELECTRON_FORCE] = STATUS_LOOP_LIMIT;
                         break;
                     }
                     loop_limit_count--;
@@ -927,12 +995,15 @@ fn electron_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>
             }
             node_src[order].force += af * 0.25;
         }
+    } else if (global_invocation_id.x == 0u) {
+        // 遍历栈放不下整棵树，本帧没有斥力
+        kernel_status[KERNEL_ELECTRON_FORCE] = STATUS_TREE_DEPTH_OVERFLOW;
     }
 }
 
 // 13
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.node_count;
     let index = global_invocation_id.x;
@@ -955,7 +1026,7 @@ fn main(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 // 14
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn displacement(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.node_count;
     let index = global_invocation_id.x;
@@ -974,28 +1045,74 @@ fn displacement(@builtin(global_invocation_id) global_invocation_id: vec3<u32>)
     node_src[index].force = vec3<f32>(0.0);
     node_src[index].prev_force = force;
 
-//    if (index == 0u) {
-//        return;
-//    }
+    // 固定的节点仍清空受力，但不移动
+    if (is_pinned(index)) {
+        return;
+    }
 
     node_src[index].position += force * factor * 0.01;
 }
 
+fn is_finite(v: vec3<f32>) -> bool {
+    return all(v == v) && all(abs(v) <= vec3<f32>(3.4e38));
+}
+
+// 检查 displacement 之后的坐标与受力，出现 NaN / 无穷的节点恢复到上一步的有效坐标并记入隔离列表
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn validate_nodes(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+    let total = uniforms.node_count;
+    let index = global_invocation_id.x;
+    if (index >= total || is_hidden(index)) {
+        return;
+    }
+
+    let position = node_src[index].position;
+    if (is_finite(position) && is_finite(node_src[index].prev_force)) {
+        last_valid_position[index] = vec4<f32>(position, 1.0);
+        return;
+    }
+
+    // 恢复时加上微小的偏移，避免下一步在同样的位置再次出现 NaN
+    let last_valid = last_valid_position[index];
+    let jitter = vec3<f32>(
+        random_xy(index, frame_seed(0u)),
+        random_xy(index, frame_seed(1u)),
+        random_xy(index, frame_seed(2u)),
+    ) * 2.0 - 1.0;
+    var flag = 0u;
+    if (last_valid.w > 0.0) {
+        node_src[index].position = last_valid.xyz + jitter * (abs(last_valid.xyz) + 1.0) * 1e-4;
+    } else {
+        // 没有有效坐标时先与 gen_node 一样放到 [-1, 1] 内，由 CPU 按邻居重新放置
+        node_src[index].position = jitter;
+        flag = QUARANTINE_NO_PREVIOUS;
+    }
+    node_src[index].force = vec3<f32>(0.0);
+    node_src[index].prev_force = vec3<f32>(0.0);
+    kernel_status[KERNEL_VALIDATE_NODES] = STATUS_NODES_QUARANTINED;
+
+    let slot = atomicAdd(&node_quarantine.count, 1u);
+    if (slot < MAX_QUARANTINED_NODES) {
+        node_quarantine.nodes[slot] = index | flag;
+    }
+}
+
 // 15
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn randomize(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.node_count;
     let index = global_invocation_id.x;
-    if (index >= total) {
+    if (index >= total || is_pinned(index)) {
         return;
     }
 
     var vPos : vec3<f32> = node_src[index].position;
 
-    vPos.x = random_xy(index, 0u + 3u * uniforms.frame_num) * 2.0 - 1.0;
-    vPos.y = random_xy(index, 1u + 3u * uniforms.frame_num) * 2.0 - 1.0;
-    vPos.z = random_xy(index, 2u + 3u * uniforms.frame_num) * 2.0 - 1.0;
+    vPos.x = random_xy(index, frame_seed(0u)) * 2.0 - 1.0;
+    vPos.y = random_xy(index, frame_seed(1u)) * 2.0 - 1.0;
+    vPos.z = random_xy(index, frame_seed(2u)) * 2.0 - 1.0;
 
     // Write back
     node_src[index].position = vPos;
@@ -1005,7 +1122,7 @@ fn randomize(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 // 16
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
 fn copy(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     let total = uniforms.node_count;
     let index = global_invocation_id.x;
@@ -1022,7 +1139,34 @@ fn copy(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
 // 17
 @compute
-@workgroup_size(256)
+@workgroup_size(WORKGROUP_SIZE)
+fn set_position(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+    let total = uniforms.node_count;
+    let index = global_invocation_id.x;
+    if (index >= total) {
+        return;
+    }
+
+    let position = vec3<f32>(
+        node_copy_src[3u * index     ],
+        node_copy_src[3u * index + 1u],
+        node_copy_src[3u * index + 2u],
+    );
+    node_src[index].position = position;
+    node_src[index].force = vec3<f32>(0.0);
+    node_src[index].prev_force = vec3<f32>(0.0);
+
+    // 设置的坐标作为 validate_nodes 恢复时使用的有效坐标
+    if (is_finite(position)) {
+        last_valid_position[index] = vec4<f32>(position, 1.0);
+    } else {
+        last_valid_position[index] = vec4<f32>(0.0);
+    }
+}
+
+// 18
+@compute
+@workgroup_size(WORKGROUP_SIZE)
 fn cal_depth(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
     let total = uniforms.node_count;
@@ -1035,37 +1179,113 @@ fn cal_depth(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
 
     var clip_pos = transform.projection * transform.view * vec4<f32>(vPos, 1.0);
 
-    kvps[index].index = index;
-    kvps[index].sort_key = clip_pos.z;
+    // 升序排序，深度取反使远处的节点先绘制
+    sort_key_dst[index] = ~float_sort_key(clip_pos.z);
+    sort_value_dst[index] = index;
 
 }
 
-// 18
+// 19
 @compute
-@workgroup_size(256)
-fn sort_by_depth(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
-    let i = global_invocation_id.x;
-    var j = i ^ kvps_param.block_count;
+@workgroup_size(WORKGROUP_SIZE)
+fn apply_depth_sort(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+    let total = uniforms.node_count;
+    let index = global_invocation_id.x;
+    if (index >= total) {
+        return;
+    }
 
-    if (kvps_param.block_count == kvps_param.dim >> 1u) {
-        j = i ^ (kvps_param.block_count * 2u - 1u);
+    kvps[index].index = sorted_value_src[index];
+}
+
+var<workgroup> local_count: array<f32, WORKGROUP_SIZE>;
+
+// 20
+// 每个工作组归约一个分组的质心，w 为分组中未隐藏的节点数
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn cluster_centroid(
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) group_id: vec3<u32>,
+) {
+    let group = group_id.x;
+    let range = group_range_src[group];
+
+    var sum = vec3<f32>(0.0);
+    var count = 0.0;
+    for (var i = range.start + local_index; i < range.end; i += WORKGROUP_SIZE) {
+        let member = group_member_src[i];
+        if (!is_hidden(member)) {
+            sum += node_src[member].position;
+            count += 1.0;
+        }
     }
-    
-    let total = uniforms.node_count;
-    if (j < i || i >= total || j >= total) {
+    local_sum[local_index] = sum;
+    local_count[local_index] = count;
+    workgroupBarrier();
+
+    for (var s = WORKGROUP_SIZE / 2u; s > 0u; s >>= 1u) {
+        if (local_index < s) {
+            local_sum[local_index] += local_sum[local_index + s];
+            local_count[local_index] += local_count[local_index + s];
+        }
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u) {
+        let count = local_count[0];
+        group_centroid_src[group] = vec4<f32>(local_sum[0] / max(count, 1.0), count);
+    }
+}
+
+// 21
+// 分组之间的斥力，把其他分组视为位于质心、质量为节点数的点
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn cluster_repulsion(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+    let total = uniforms.cluster_group_count;
+    let group = global_invocation_id.x;
+    if (group >= total) {
         return;
     }
 
+    // 与 electron_force 中节点之间斥力的系数一致
+    let scale = 0.0003 * 0.25;
+    let epssq = 0.05 * 0.05;
 
-    let index_i= kvps[i].index;
-    let index_j = kvps[j].index;
-    let key_i = kvps[index_i].sort_key;
-    let key_j = kvps[index_j].sort_key;
-    
-    var diff = key_j - key_i;
+    let centroid = group_centroid_src[group];
+    var force = vec3<f32>(0.0);
+    for (var other = 0u; other < total; other++) {
+        if (other == group) {
+            continue;
+        }
+        let other_centroid = group_centroid_src[other];
+        let dp = centroid.xyz - other_centroid.xyz;
+        force += dp * other_centroid.w / max(dot(dp, dp), epssq);
+    }
+
+    group_force_src[group] = vec4<f32>(force * scale * uniforms.cluster_repulsion, 0.0);
+}
 
-    if (diff > 0.0) {
-        kvps[i].index = index_j;
-        kvps[j].index = index_i;
+// 22
+// 节点受到指向所在分组质心的引力，以及分组之间的斥力
+@compute
+@workgroup_size(WORKGROUP_SIZE)
+fn cluster_force(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+    let total = uniforms.node_count;
+    let index = global_invocation_id.x;
+    if (index >= total) {
+        return;
+    }
+
+    let group = node_group_src[index];
+    if (group >= uniforms.cluster_group_count || is_hidden(index)) {
+        return;
     }
-}
\ No newline at end of file
+
+    let mass = f32(atomicLoad(&node_src[index].mass));
+    let centroid = group_centroid_src[group].xyz;
+    let attraction = (centroid - node_src[index].position) * uniforms.cluster_attraction;
+
+    node_src[index].force += (attraction + group_force_src[group].xyz) * mass;
+}
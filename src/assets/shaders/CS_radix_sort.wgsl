@@ -0,0 +1,132 @@
+// LSD 基数排序，键为 u32，值随键移动，排序稳定
+// 每趟处理 4 位，依次执行 radix_histogram、radix_scan、radix_scatter
+// 块数可能超过单个维度的工作组上限，块序号按二维 dispatch 展开
+
+struct RadixParam {
+    count: u32,
+    shift: u32,
+    block_count: u32,
+    _empty: u32,
+}
+
+// RADIX_SIZE、RADIX_MASK 与 BLOCK_SIZE 由 radix_sort.rs 中的常量定义
+
+@group(0) @binding(0) var<uniform>             param:       RadixParam;
+@group(0) @binding(1) var<storage, read>       key_src:     array<u32>;
+@group(0) @binding(2) var<storage, read>       value_src:   array<u32>;
+@group(0) @binding(3) var<storage, read_write> key_dst:     array<u32>;
+@group(0) @binding(4) var<storage, read_write> value_dst:   array<u32>;
+@group(0) @binding(5) var<storage, read_write> histogram:   array<u32>;
+
+var<workgroup> local_count: array<atomic<u32>, RADIX_SIZE>;
+var<workgroup> local_sum: array<u32, BLOCK_SIZE>;
+var<workgroup> local_digit: array<u32, BLOCK_SIZE>;
+
+fn block_index(workgroup_id: vec3<u32>, num_workgroups: vec3<u32>) -> u32 {
+    return workgroup_id.y * num_workgroups.x + workgroup_id.x;
+}
+
+// 每个工作组统计一个块中各位值的个数
+// 按 位值 * 块数 + 块序号 排列，其前缀和即为每个块中各位值的全局起始位置
+@compute
+@workgroup_size(BLOCK_SIZE)
+fn radix_histogram(
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let block = block_index(workgroup_id, num_workgroups);
+    if (block >= param.block_count) {
+        return;
+    }
+
+    if (local_index < RADIX_SIZE) {
+        atomicStore(&local_count[local_index], 0u);
+    }
+    workgroupBarrier();
+
+    let index = block * BLOCK_SIZE + local_index;
+    if (index < param.count) {
+        let digit = (key_src[index] >> param.shift) & RADIX_MASK;
+        atomicAdd(&local_count[digit], 1u);
+    }
+    workgroupBarrier();
+
+    if (local_index < RADIX_SIZE) {
+        histogram[local_index * param.block_count + block] = atomicLoad(&local_count[local_index]);
+    }
+}
+
+// 单个工作组对整个 histogram 做排他前缀和
+// 每个线程先顺序累加一段，再对 BLOCK_SIZE 个段和做扫描
+@compute
+@workgroup_size(BLOCK_SIZE)
+fn radix_scan(@builtin(local_invocation_index) local_index: u32) {
+    let total = RADIX_SIZE * param.block_count;
+    let chunk = (total + BLOCK_SIZE - 1u) / BLOCK_SIZE;
+    let start = min(local_index * chunk, total);
+    let end = min(start + chunk, total);
+
+    var sum = 0u;
+    for (var i = start; i < end; i++) {
+        sum += histogram[i];
+    }
+    local_sum[local_index] = sum;
+    workgroupBarrier();
+
+    for (var offset = 1u; offset < BLOCK_SIZE; offset = offset * 2u) {
+        var value = local_sum[local_index];
+        if (local_index >= offset) {
+            value += local_sum[local_index - offset];
+        }
+        workgroupBarrier();
+        local_sum[local_index] = value;
+        workgroupBarrier();
+    }
+
+    var prefix = local_sum[local_index] - sum;
+    for (var i = start; i < end; i++) {
+        let count = histogram[i];
+        histogram[i] = prefix;
+        prefix += count;
+    }
+}
+
+// 元素的目标位置为所在块中该位值的全局起始位置，加上块内排在它之前的相同位值的个数，保证稳定
+@compute
+@workgroup_size(BLOCK_SIZE)
+fn radix_scatter(
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let block = block_index(workgroup_id, num_workgroups);
+    if (block >= param.block_count) {
+        return;
+    }
+
+    let index = block * BLOCK_SIZE + local_index;
+
+    // 越界的线程写入 RADIX_SIZE，不与任何位值相同
+    var digit = RADIX_SIZE;
+    if (index < param.count) {
+        digit = (key_src[index] >> param.shift) & RADIX_MASK;
+    }
+    local_digit[local_index] = digit;
+    workgroupBarrier();
+
+    if (index >= param.count) {
+        return;
+    }
+
+    var rank = 0u;
+    for (var i = 0u; i < local_index; i++) {
+        if (local_digit[i] == digit) {
+            rank++;
+        }
+    }
+
+    let position = histogram[digit * param.block_count + block] + rank;
+    key_dst[position] = key_src[index];
+    value_dst[position] = value_src[index];
+}
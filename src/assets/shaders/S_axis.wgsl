@@ -1,3 +1,5 @@
+#include "common.wgsl"
+
 struct Input {
     @builtin(vertex_index) vertex_index: u32,
     @builtin(instance_index) instance_index: u32,
@@ -9,14 +11,6 @@ struct Varing {
     @location(1) color: vec3<f32>,
 };
 
-struct Transform {
-    view: mat4x4<f32>,
-    projection: mat4x4<f32>,
-    time: vec4<f32>,
-    screen: vec4<f32>,
-    camera: vec4<f32>,
-}
-
 @group(0) @binding(0) var<uniform> transform: Transform;
 
 @vertex
@@ -71,3 +65,77 @@ fn main_fs(v: Varing) -> @location(0) vec4<f32> {
 
     return vec4<f32>(color, alpha);
 }
+
+
+// 经纬网，Geographic 布局下在 Axis Pass 中绘制
+// 须同步修改 graphics_model.rs 中的 GRATICULE_SEGMENT_COUNT
+let PROJECTION_GLOBE: u32 = 1u;
+let PROJECTION_MERCATOR: u32 = 2u;
+
+let PI: f32 = 3.14159265;
+let MERCATOR_MAX_LATITUDE: f32 = 1.48442223; // 85.05113°
+
+// 经线每 15°，每条 36 段；纬线 -75° 到 75° 每 15°，每条 72 段
+let MERIDIAN_COUNT: u32 = 24u;
+let MERIDIAN_SEGMENTS: u32 = 36u;
+let PARALLEL_SEGMENTS: u32 = 72u;
+
+fn geo_project(phi: f32, lambda: f32) -> vec3<f32> {
+    let projection = u32(transform.geo.x);
+    if (projection == PROJECTION_GLOBE) {
+        return vec3<f32>(cos(phi) * sin(lambda), sin(phi), cos(phi) * cos(lambda));
+    }
+    if (projection == PROJECTION_MERCATOR) {
+        let phi_m = clamp(phi, -MERCATOR_MAX_LATITUDE, MERCATOR_MAX_LATITUDE);
+        return vec3<f32>(lambda / PI, log(tan(PI * 0.25 + phi_m * 0.5)) / PI, 0.0);
+    }
+    return vec3<f32>(lambda / PI, phi / PI, 0.0);
+}
+
+@vertex
+fn graticule_vs(
+    @location(0) quad_pos: vec2<f32>,
+    i: Input
+) -> Varing {
+
+    let step = PI / 36.0;
+    var a: vec3<f32>;
+    var b: vec3<f32>;
+
+    let meridian_instances = MERIDIAN_COUNT * MERIDIAN_SEGMENTS;
+    if (i.instance_index < meridian_instances) {
+        let lambda = -PI + f32(i.instance_index / MERIDIAN_SEGMENTS) * PI / 12.0;
+        let phi = -PI * 0.5 + f32(i.instance_index % MERIDIAN_SEGMENTS) * step;
+        a = geo_project(phi, lambda);
+        b = geo_project(phi + step, lambda);
+    } else {
+        let index = i.instance_index - meridian_instances;
+        let phi = -PI * 5.0 / 12.0 + f32(index / PARALLEL_SEGMENTS) * PI / 12.0;
+        let lambda = -PI + f32(index % PARALLEL_SEGMENTS) * step;
+        a = geo_project(phi, lambda);
+        b = geo_project(phi, lambda + step);
+    }
+
+    let clip_a = transform.projection * transform.view * vec4<f32>(a, 1.0);
+    let clip_b = transform.projection * transform.view * vec4<f32>(b, 1.0);
+    var dir = clip_a / clip_a.w - clip_b / clip_b.w;
+    dir.y = dir.y / transform.camera.x;
+    let quad_dir = normalize(vec2<f32>(dir.y, -dir.x));
+
+    var v: Varing;
+
+    v.position = select(clip_b, clip_a, i.vertex_index % 2u == 0u);
+    var quad_pos_ratio = quad_pos.y * quad_dir;
+    quad_pos_ratio.x /= transform.camera.x;
+    v.position += vec4<f32>(quad_pos_ratio * (1.0 / transform.screen.y) * v.position.w, 0.0, 0.0);
+
+    v.tex_coords = quad_pos;
+    v.color = vec3<f32>(0.5, 0.55, 0.6);
+
+    return v;
+}
+
+@fragment
+fn graticule_fs(v: Varing) -> @location(0) vec4<f32> {
+    return vec4<f32>(v.color, 0.35);
+}
@@ -1,3 +1,5 @@
+#include "common.wgsl"
+
 struct Input {
     @builtin(vertex_index) vertex_index: u32,
     @builtin(instance_index) instance_index: u32,
@@ -7,19 +9,6 @@ struct Varing {
     @builtin(position) position: vec4<f32>,
 };
 
-struct Transform {
-    view: mat4x4<f32>,
-    projection: mat4x4<f32>,
-    time: vec4<f32>,
-    screen: vec4<f32>,
-    camera: vec4<f32>,
-}
-
-struct Bound {
-    bound_min: vec3<f32>,
-    bound_max: vec3<f32>,
-}
-
 @group(0) @binding(0) var<uniform> transform: Transform;
 
 @group(1) @binding(0) var<storage, read> boundSrc : array<Bound>;
@@ -1,4 +1,5 @@
-
+#include "common.wgsl"
+#include "oit.wgsl"
 
 struct Input {
     @builtin(vertex_index) vertex_index: u32,
@@ -10,26 +11,101 @@ struct Varing {
     @builtin(position) position: vec4<f32>,
 };
 
-struct Node {
-    position: vec3<f32>,
-    force: vec3<f32>,
-    prev_force: vec3<f32>,
-    mass: u32,
-};
-
-struct Transform {
-    view: mat4x4<f32>,
-    projection: mat4x4<f32>,
-    time: vec4<f32>,
-    screen: vec4<f32>,
-    camera: vec4<f32>,
-}
-
 @group(0) @binding(0) var<uniform> transform: Transform;
 
 @group(1) @binding(0) var<storage, read> node_src : array<Node>;
 @group(1) @binding(1) var<storage, read> edge_src : array<vec2<u32>>;
 
+// transform.geo.x 为投影方式，transform.geo.y 为每条边的分段数
+// 须同步修改 layout_lib/geo.rs 中的 GeoProjection
+let PROJECTION_GLOBE: u32 = 1u;
+let PROJECTION_MERCATOR: u32 = 2u;
+let PROJECTION_EQUIRECTANGULAR: u32 = 3u;
+
+let PI: f32 = 3.14159265;
+let MERCATOR_MAX_LATITUDE: f32 = 1.48442223; // 85.05113°
+
+// 平面投影坐标 → 单位球面
+fn unproject(p: vec3<f32>, projection: u32) -> vec3<f32> {
+    let lambda = p.x * PI;
+    var phi = p.y * PI;
+    if (projection == PROJECTION_MERCATOR) {
+        phi = 2.0 * atan(exp(p.y * PI)) - PI * 0.5;
+    }
+    return vec3<f32>(cos(phi) * sin(lambda), sin(phi), cos(phi) * cos(lambda));
+}
+
+// 单位球面 → 平面投影坐标
+fn project(d: vec3<f32>, projection: u32) -> vec3<f32> {
+    let lambda = atan2(d.x, d.z);
+    let phi = asin(clamp(d.y, -1.0, 1.0));
+    if (projection == PROJECTION_MERCATOR) {
+        let phi_m = clamp(phi, -MERCATOR_MAX_LATITUDE, MERCATOR_MAX_LATITUDE);
+        return vec3<f32>(lambda / PI, log(tan(PI * 0.25 + phi_m * 0.5)) / PI, 0.0);
+    }
+    return vec3<f32>(lambda / PI, phi / PI, 0.0);
+}
+
+fn slerp(a: vec3<f32>, b: vec3<f32>, t: f32) -> vec3<f32> {
+    let theta = acos(clamp(dot(a, b), -1.0, 1.0));
+    let sin_theta = sin(theta);
+    // 重合或对跖时大圆不唯一，退化为直线
+    if (sin_theta < 0.0001) {
+        return mix(a, b, t);
+    }
+    return (sin((1.0 - t) * theta) * a + sin(t * theta) * b) / sin_theta;
+}
+
+// 边上 t 处的点，t ∈ [0, 1]，Geographic 布局下为大圆弧
+fn edge_point(a: vec3<f32>, b: vec3<f32>, t: f32) -> vec3<f32> {
+    let projection = u32(transform.geo.x);
+    if (t <= 0.0 || t >= 1.0 || transform.geo.y <= 1.0) {
+        return mix(a, b, t);
+    }
+    if (projection == PROJECTION_GLOBE) {
+        let ra = length(a);
+        let rb = length(b);
+        if (ra < 0.000001 || rb < 0.000001) {
+            return mix(a, b, t);
+        }
+        return slerp(a / ra, b / rb, t) * mix(ra, rb, t);
+    }
+    if (projection == PROJECTION_MERCATOR || projection == PROJECTION_EQUIRECTANGULAR) {
+        return project(slerp(unproject(a, projection), unproject(b, projection), t), projection);
+    }
+    return mix(a, b, t);
+}
+
+struct EdgeSegment {
+    edge: vec2<u32>,
+    edge_index: u32,
+    a: vec3<f32>,
+    b: vec3<f32>,
+    // 平面投影中跨越 ±180° 经线的分段不绘制
+    is_visible: bool,
+}
+
+// 每个实例绘制一条边的一段
+fn edge_segment(instance_index: u32) -> EdgeSegment {
+    let segment_count = max(u32(transform.geo.y), 1u);
+    let edge_index = instance_index / segment_count;
+    let segment = instance_index % segment_count;
+    let edge = edge_src[edge_index];
+
+    let node_a = node_src[edge[0]].position;
+    let node_b = node_src[edge[1]].position;
+
+    var s: EdgeSegment;
+    s.edge = edge;
+    s.edge_index = edge_index;
+    s.a = edge_point(node_a, node_b, f32(segment) / f32(segment_count));
+    s.b = edge_point(node_a, node_b, f32(segment + 1u) / f32(segment_count));
+
+    let projection = u32(transform.geo.x);
+    s.is_visible = segment_count == 1u || projection == PROJECTION_GLOBE || abs(s.a.x - s.b.x) < 1.0;
+    return s;
+}
+
 fn vs_transform(
     node_pos_a: vec3<f32>,
     node_pos_b: vec3<f32>,
@@ -58,16 +134,19 @@ fn main_vs(
     @location(0) quad_pos: vec2<f32>,
     i: Input
 ) -> Varing {
-    let edge = edge_src[i.instance_index];
+    let s = edge_segment(i.instance_index);
 
     var v: Varing;
 
     v.position = vs_transform(
-        node_src[edge[0]].position,
-        node_src[edge[1]].position,
-        node_src[edge[i.vertex_index % 2u]].position,
+        s.a,
+        s.b,
+        select(s.b, s.a, i.vertex_index % 2u == 0u),
         quad_pos
     );
+    if (!s.is_visible) {
+        v.position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+    }
 
     v.tex_coords = vec2<f32>(0.0);
 
@@ -82,6 +161,10 @@ fn main_fs(v: Varing) -> @location(0) vec4<f32> {
     return out_color;
 }
 
+@fragment
+fn oit_fs(v: Varing) -> OitOutput {
+    return oit_output(vec4<f32>(1.0, 1.0, 1.0, 0.02), v.position.z);
+}
 
 
 struct CastVaring {
@@ -95,23 +178,26 @@ fn cast_vs(
     @location(0) quad_pos: vec2<f32>,
     i: Input
 ) -> CastVaring {
-    let edge = edge_src[i.instance_index];
+    let s = edge_segment(i.instance_index);
 
     var v: CastVaring;
 
     v.position = vs_transform(
-        node_src[edge[0]].position,
-        node_src[edge[1]].position,
-        node_src[edge[i.vertex_index % 2u]].position,
+        s.a,
+        s.b,
+        select(s.b, s.a, i.vertex_index % 2u == 0u),
         quad_pos
     );
 
     v.position /= abs(v.position.w);
     v.position.x = (v.position.x + 1.0) / 2.0 * transform.screen.x - transform.screen.z;
     v.position.y = (-v.position.y + 1.0) / 2.0 * transform.screen.y - transform.screen.w;
+    if (!s.is_visible) {
+        v.position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+    }
 
-    v.id = i.instance_index;
-    v.nodes = edge;
+    v.id = s.edge_index;
+    v.nodes = s.edge;
 
     return v;
 }
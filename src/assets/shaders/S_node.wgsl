@@ -1,3 +1,6 @@
+#include "common.wgsl"
+#include "oit.wgsl"
+
 struct Input {
     @builtin(vertex_index) vertex_index: u32,
     @builtin(instance_index) instance_index: u32,
@@ -9,30 +12,19 @@ struct Varing {
     @location(1) color: vec3<f32>,
 };
 
-struct Node {
-    position: vec3<f32>,
-    force: vec3<f32>,
-    prev_force: vec3<f32>,
-    mass: u32,
-};
-
-struct Transform {
-    view: mat4x4<f32>,
-    projection: mat4x4<f32>,
-    time: vec4<f32>,
-    screen: vec4<f32>,
-    camera: vec4<f32>,
-}
-
-struct Kvp {
-    sort_key: f32,
-    index: u32,
-}
-
 @group(0) @binding(0) var<uniform> transform: Transform;
 
 @group(1) @binding(0) var<storage, read> node_src : array<Node>;
 @group(1) @binding(1) var<storage, read> kvps: array<Kvp>;
+@group(1) @binding(2) var<storage, read> node_flag_src: array<u32>;
+
+// NODE_FLAG_HIDDEN 由 graphics_model.rs 中的常量定义
+// 删除的节点移到裁剪范围之外，不产生片元
+fn is_hidden(index: u32) -> bool {
+    return (node_flag_src[index] & NODE_FLAG_HIDDEN) != 0u;
+}
+
+let HIDDEN_POSITION: vec4<f32> = vec4<f32>(0.0, 0.0, 2.0, 1.0);
 
 fn vs_transform(
     node_position: vec3<f32>,
@@ -50,23 +42,40 @@ fn vs_transform(
     return position;
 }
 
-@vertex
-fn main_vs(
-    @location(0) quad_pos: vec2<f32>,
-    i: Input
+fn node_varing(
+    node_index: u32,
+    instance_index: u32,
+    quad_pos: vec2<f32>
 ) -> Varing {
-    var node = node_src[kvps[i.instance_index].index];
-    var kvp = kvps[i.instance_index];
+    var node = node_src[node_index];
 
     var v: Varing;
     v.position = vs_transform(node.position, quad_pos);
     v.tex_coords = quad_pos;
-    v.color = mix(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), f32(i.instance_index) / f32(arrayLength(&node_src)));
-    if (kvp.index == 0u) { v.color = vec3<f32>(1.0); }
+    v.color = mix(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), f32(instance_index) / f32(arrayLength(&node_src)));
+    if (node_index == 0u) { v.color = vec3<f32>(1.0); }
+    if (is_hidden(node_index)) { v.position = HIDDEN_POSITION; }
 
     return v;
 }
 
+@vertex
+fn main_vs(
+    @location(0) quad_pos: vec2<f32>,
+    i: Input
+) -> Varing {
+    return node_varing(kvps[i.instance_index].index, i.instance_index, quad_pos);
+}
+
+// 顺序无关透明不需要深度排序，实例序号即节点序号
+@vertex
+fn oit_vs(
+    @location(0) quad_pos: vec2<f32>,
+    i: Input
+) -> Varing {
+    return node_varing(i.instance_index, i.instance_index, quad_pos);
+}
+
 @fragment
 fn main_fs(v: Varing) -> @location(0) vec4<f32> {
 
@@ -82,7 +91,18 @@ fn main_fs(v: Varing) -> @location(0) vec4<f32> {
     return out_color;
 }
 
+@fragment
+fn oit_fs(v: Varing) -> OitOutput {
 
+    let sdf = dot(v.tex_coords, v.tex_coords);
+    let clip = step(sdf, 1.0);
+
+    if clip < 0.5 {
+        discard;
+    }
+
+    return oit_output(vec4<f32>(v.color, 1.0), v.position.z);
+}
 
 
 struct CastVaring {
@@ -96,8 +116,8 @@ fn cast_vs(
     @location(0) quad_pos: vec2<f32>,
     i: Input
 ) -> CastVaring {
-    var node = node_src[kvps[i.instance_index].index];
-    var kvp = kvps[i.instance_index];
+    // 拾取有深度测试，不依赖深度排序
+    var node = node_src[i.instance_index];
 
     var v: CastVaring;
     v.position = vs_transform(node.position, quad_pos);
@@ -106,7 +126,8 @@ fn cast_vs(
     v.position.y = (-v.position.y + 1.0) / 2.0 * transform.screen.y - transform.screen.w;
 
     v.tex_coords = quad_pos;
-    v.id = kvp.index;
+    v.id = i.instance_index;
+    if (is_hidden(i.instance_index)) { v.position = HIDDEN_POSITION; }
 
     return v;
 }
@@ -0,0 +1,33 @@
+// 加权混合顺序无关透明的合成 Pass
+// 平均颜色 = 累积颜色 / 累积权重，覆盖率 = 1 - 透射率
+
+@group(0) @binding(0) var accum_texture: texture_2d<f32>;
+@group(0) @binding(1) var reveal_texture: texture_2d<f32>;
+
+struct Varing {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn main_vs(
+    @location(0) quad_pos: vec2<f32>,
+) -> Varing {
+    var v: Varing;
+    v.position = vec4<f32>(quad_pos, 0.0, 1.0);
+    return v;
+}
+
+@fragment
+fn main_fs(v: Varing) -> @location(0) vec4<f32> {
+    let coord = vec2<i32>(v.position.xy);
+
+    let reveal = textureLoad(reveal_texture, coord, 0).r;
+    if (reveal >= 0.9999) {
+        discard;
+    }
+
+    let accum = textureLoad(accum_texture, coord, 0);
+    let color = accum.rgb / max(accum.a, 1e-5);
+
+    return vec4<f32>(color, 1.0 - reveal);
+}
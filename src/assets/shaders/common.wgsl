@@ -0,0 +1,35 @@
+// 多个 Shader 共用的结构体，由 ShaderPreprocessor 通过 #include "common.wgsl" 引入
+// graphics_model.rs 中的 Node 与 Bound 由 build.rs 检查内存布局
+
+// Compute Shader 中 mass 为原子类型，由 Rust 定义 NODE_ATOMIC_MASS
+struct Node {
+    position: vec3<f32>,
+    _empty_1: i32,
+    force: vec3<f32>,
+    _empty_2: i32,
+    prev_force: vec3<f32>,
+#ifdef NODE_ATOMIC_MASS
+    mass: atomic<u32>,
+#else
+    mass: u32,
+#endif
+};
+
+struct Transform {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    time: vec4<f32>,
+    screen: vec4<f32>,
+    camera: vec4<f32>,
+    geo: vec4<f32>,
+}
+
+struct Kvp {
+    sort_key: f32,
+    index: u32,
+}
+
+struct Bound {
+    bound_min: vec3<f32>,
+    bound_max: vec3<f32>,
+}
@@ -0,0 +1,14 @@
+// 加权混合顺序无关透明的输出，权重随深度递减，使近处的片元占主导
+struct OitOutput {
+    @location(0) accum: vec4<f32>,
+    @location(1) reveal: f32,
+};
+
+fn oit_output(color: vec4<f32>, depth: f32) -> OitOutput {
+    let weight = color.a * clamp(3e3 * pow(1.0 - depth, 3.0), 1e-2, 3e3);
+
+    var out: OitOutput;
+    out.accum = vec4<f32>(color.rgb * color.a, color.a) * weight;
+    out.reveal = color.a;
+    return out;
+}
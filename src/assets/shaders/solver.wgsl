@@ -0,0 +1,33 @@
+// Graph Solver 中由 Rust 写入或读取的结构体，由 CS_graph_solver.wgsl 包含
+// graphics_model.rs 中的同名 struct 由 build.rs 检查内存布局，Uniforms 对应 ComputeUniforms
+
+struct Uniforms {
+    frame_num: u32,
+    node_count: u32,
+    edge_count: u32,
+    edge_sort_count: u32,
+    tree_node_count: u32,
+    bounding_count: u32,
+    kernel_status_count: u32,
+    seed: u32,
+    is_deterministic: u32,
+    cluster_group_count: u32,
+    cluster_attraction: f32,
+    cluster_repulsion: f32,
+};
+
+struct BHTree {
+    max_depth: atomic<u32>,
+    bottom: atomic<u32>,
+    radius: f32,
+    _empty: i32,
+}
+
+struct BHTreeNode {
+    position: vec3<f32>,
+    _empty: i32,
+    mass: atomic<i32>,
+    count: i32,
+    start: atomic<i32>,
+    sort: i32,
+}
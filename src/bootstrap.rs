@@ -1,6 +1,7 @@
 use eframe::AppCreator;
 
 use crate::{MainApp, APP_VERSION, utils::file::get_resource_path};
+use crate::models::graphics_lib::kernel_timer::supported_timing_features;
 
 pub struct Config {
     native_options: eframe::NativeOptions,
@@ -16,6 +17,10 @@ pub struct ConfigBuilder {
 
 impl Default for ConfigBuilder {
     fn default() -> Self {
+        // Kernel 计时需要 Timestamp Query，仅在所有适配器都支持时请求
+        let mut wgpu_options = egui_wgpu::WgpuConfiguration::default();
+        wgpu_options.device_descriptor.features |= supported_timing_features(wgpu_options.backends);
+
         let native_options = eframe::NativeOptions {
             initial_window_size: Some(egui::Vec2::new(1200.0, 720.0)),
             min_window_size: Some(egui::Vec2::new(960.0, 640.0)),
@@ -24,6 +29,7 @@ impl Default for ConfigBuilder {
             follow_system_theme: false,
             default_theme: eframe::Theme::Dark,
             icon_data: Some(load_icon("app_bar_icon.ico")),
+            wgpu_options,
             // #[cfg(target_os = "macos")]
             // fullsize_content: true,
             ..Default::default()
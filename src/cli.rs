@@ -0,0 +1,253 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::models::data_model::DataModel;
+use crate::models::graphics_model::create_layout_engine;
+use crate::models::layout_lib::cluster::label_propagation;
+use crate::models::layout_lib::graph::connected_components;
+use crate::models::layout_lib::{layout_metrics, Adjacency, CpuLayoutEngine, LayoutEngine};
+use crate::utils::csv_loader::load_data;
+use crate::utils::export::{write_columns, ExportColumn, ExportFormat};
+use crate::utils::message::{message_warning, messenger};
+
+const DEFAULT_ITERATIONS: u32 = 1000;
+const DEFAULT_SEED: u32 = 1;
+const DEFAULT_OUT: &str = "positions.csv";
+// 每隔多少次迭代输出一次进度
+const PROGRESS_INTERVAL: u32 = 100;
+
+const USAGE: &str = "\
+Usage: graphpu layout --edges <edges.csv> [options]
+
+Run the force-directed layout without opening a window and export node positions.
+
+Options:
+  --edges <path>         Edge CSV file (required)
+  --nodes <path>         Node CSV file
+  --source <index>       Source column of the edge file [default: 0]
+  --target <index>       Target column of the edge file [default: 1]
+  --iterations <count>   Layout iterations [default: 1000]
+  --seed <seed>          Seed of the initial positions [default: 1]
+  --out <path>           Output file [default: positions.csv]
+  --format <format>      csv or pcache [default: by the extension of --out]
+  --cpu                  Use the CPU solver even if a GPU is available
+  -h, --help             Print this help
+";
+
+struct LayoutArgs {
+    edges:      PathBuf,
+    nodes:      Option<PathBuf>,
+    source:     usize,
+    target:     usize,
+    iterations: u32,
+    seed:       u32,
+    out:        PathBuf,
+    format:     ExportFormat,
+    is_cpu:     bool,
+}
+
+// 参数为空，或只有 macOS 从 Finder 启动时传入的 -psn_ 参数时，没有子命令
+pub fn has_command(args: &[String]) -> bool {
+    args.first().is_some_and(|arg| !arg.starts_with("-psn_"))
+}
+
+// 命令行参数中有子命令时在此运行，返回进程的退出码；没有子命令时返回 None，启动窗口
+pub fn run(args: &[String]) -> Option<i32> {
+    if !has_command(args) { return None; }
+
+    let result = match args.first().map(String::as_str).unwrap_or_default() {
+        "layout" => parse_layout_args(&args[1..]).and_then(|layout_args| match layout_args {
+            Some(layout_args) => run_layout(&layout_args),
+            None => {
+                print!("{}", USAGE);
+                Ok(())
+            }
+        }),
+        "-h" | "--help" | "help" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("Unknown command '{}'\n\n{}", command, USAGE)),
+    };
+
+    print_messages();
+    match result {
+        Ok(()) => Some(0),
+        Err(error) => {
+            eprintln!("[ERROR]  {}", error);
+            Some(1)
+        }
+    }
+}
+
+// 有 --help 时返回 None
+fn parse_layout_args(args: &[String]) -> Result<Option<LayoutArgs>, String> {
+
+    let mut edges = None;
+    let mut nodes = None;
+    let mut source = 0;
+    let mut target = 1;
+    let mut iterations = DEFAULT_ITERATIONS;
+    let mut seed = DEFAULT_SEED;
+    let mut out = PathBuf::from(DEFAULT_OUT);
+    let mut format = None;
+    let mut is_cpu = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        let parse_error = |value: &str| format!("Invalid value '{}' for {}", value, arg);
+        match arg.as_str() {
+            "--edges" => edges = Some(PathBuf::from(value()?)),
+            "--nodes" => nodes = Some(PathBuf::from(value()?)),
+            "--source" => { let value = value()?; source = value.parse().map_err(|_| parse_error(value))?; }
+            "--target" => { let value = value()?; target = value.parse().map_err(|_| parse_error(value))?; }
+            "--iterations" => { let value = value()?; iterations = value.parse().map_err(|_| parse_error(value))?; }
+            "--seed" => { let value = value()?; seed = value.parse().map_err(|_| parse_error(value))?; }
+            "--out" => out = PathBuf::from(value()?),
+            "--format" => {
+                let value = value()?;
+                format = Some(match value.to_lowercase().as_str() {
+                    "csv" => ExportFormat::Csv,
+                    "pcache" => ExportFormat::Pcache,
+                    _ => return Err(parse_error(value)),
+                });
+            }
+            "--cpu" => is_cpu = true,
+            "-h" | "--help" => return Ok(None),
+            _ => return Err(format!("Unknown option '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let edges = edges.ok_or_else(|| format!("--edges is required\n\n{}", USAGE))?;
+    let format = format.or_else(|| ExportFormat::from_path(&out)).unwrap_or(ExportFormat::Csv);
+
+    Ok(Some(LayoutArgs { edges, nodes, source, target, iterations, seed, out, format, is_cpu }))
+}
+
+fn run_layout(args: &LayoutArgs) -> Result<(), String> {
+
+    let path_string = |path: &PathBuf| path.to_string_lossy().to_string();
+    let node_file_path = args.nodes.as_ref().map(path_string).unwrap_or_default();
+    let data = load_data(&node_file_path, &path_string(&args.edges), args.source, args.target, 1.0)?;
+
+    let mut model = DataModel {
+        node_data: data.node_data,
+        edge_data: data.edge_data,
+        edge_source: Some(data.source_index),
+        edge_target: Some(data.target_index),
+        source_target_list: Some(data.source_target_list),
+        max_id: data.max_id,
+        ..Default::default()
+    };
+    model.set_status();
+    eprintln!("Loaded {} nodes and {} edges", model.status.node_count, model.status.edge_count);
+
+    let mut engine: Box<dyn LayoutEngine> = if args.is_cpu {
+        Box::new(CpuLayoutEngine::new(model.status.node_count, model.source_target_list.as_ref().unwrap(), args.seed))
+    } else {
+        create_layout_engine(&mut model, args.seed)?
+    };
+    print_messages();
+    eprintln!("Running {} iterations on {}", args.iterations, engine.name());
+
+    let start = Instant::now();
+    let mut iteration = 0;
+    while iteration < args.iterations {
+        let count = PROGRESS_INTERVAL.min(args.iterations - iteration);
+        engine.run(count);
+        iteration += count;
+
+        // GPU 出错时改用 CPU 从头计算，两者的初始坐标相同
+        if let Err(error) = engine.poll(&model) {
+            message_warning("Using CPU Solver", error.as_str());
+            print_messages();
+            engine = Box::new(CpuLayoutEngine::new(model.status.node_count, model.source_target_list.as_ref().unwrap(), args.seed));
+            eprintln!("Restarting {} iterations on {}", args.iterations, engine.name());
+            iteration = 0;
+            continue;
+        }
+        print_messages();
+        eprintln!("  {} / {} iterations, {:.1}s", iteration, args.iterations, start.elapsed().as_secs_f32());
+    }
+    let positions = engine.read_positions();
+    print_messages();
+
+    let node_count = model.status.node_count;
+    let source_target_list = model.source_target_list.as_ref().unwrap();
+    let adjacency = Adjacency::from_edges(node_count, source_target_list);
+    let (components, component_count) = connected_components(node_count, source_target_list);
+    let (clusters, cluster_count) = label_propagation(node_count, source_target_list);
+
+    let mut columns = vec![ExportColumn::uint("id", (0..node_count as u32).collect())];
+    columns.extend(ExportColumn::positions(&positions));
+    columns.push(ExportColumn::uint("degree", (0..node_count).map(|index| adjacency.degree(index) as u32).collect()));
+    columns.push(ExportColumn::uint("component", components));
+    columns.push(ExportColumn::uint("cluster", clusters));
+    write_columns(&args.out, args.format, "Node PCACHE file Exported from GraphPU", &columns)
+        .map_err(|error| format!("Failed to write {}: {}", args.out.display(), error))?;
+
+    let metrics = layout_metrics(node_count, source_target_list, &positions);
+    eprintln!("Stress: {:.4}  Neighborhood preservation: {:.4}  Components: {}  Clusters: {}",
+        metrics.stress, metrics.neighborhood_preservation, component_count, cluster_count);
+    eprintln!("Wrote {} ({})", args.out.display(), args.format);
+    Ok(())
+}
+
+// 命令行模式下没有消息面板，将消息输出到 stderr
+fn print_messages() {
+    for message in messenger().drain(..) {
+        eprintln!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<LayoutArgs>, String> {
+        parse_layout_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn parses_layout_args() {
+        let args = parse(&["--edges", "edges.csv", "--iterations", "20", "--seed", "7", "--cpu", "--source", "2", "--target", "3"]).unwrap().unwrap();
+        assert_eq!(args.edges, PathBuf::from("edges.csv"));
+        assert_eq!((args.source, args.target, args.iterations, args.seed, args.is_cpu), (2, 3, 20, 7, true));
+        assert_eq!(args.out, PathBuf::from(DEFAULT_OUT));
+        assert_eq!(args.format, ExportFormat::Csv);
+
+        // 未指定 --format 时按 --out 的扩展名判断，无法判断时为 CSV
+        for (out, format) in [("out.pcache", ExportFormat::Pcache), ("OUT.PCACHE", ExportFormat::Pcache), ("out.txt", ExportFormat::Csv), ("out", ExportFormat::Csv)] {
+            assert_eq!(parse(&["--edges", "e.csv", "--out", out]).unwrap().unwrap().format, format, "{}", out);
+        }
+        let args = parse(&["--edges", "e.csv", "--out", "out.pcache", "--format", "CSV"]).unwrap().unwrap();
+        assert_eq!(args.format, ExportFormat::Csv);
+    }
+
+    #[test]
+    fn rejects_invalid_layout_args() {
+        for (args, error) in [
+            (vec!["--out", "out.csv"], "--edges is required"),
+            (vec!["--edges", "e.csv", "--format", "json"], "Invalid value 'json' for --format"),
+            (vec!["--edges"], "Missing value for --edges"),
+            (vec!["--edges", "e.csv", "--iterations"], "Missing value for --iterations"),
+            (vec!["--edges", "e.csv", "--iterations", "-1"], "Invalid value '-1' for --iterations"),
+            (vec!["--edges", "e.csv", "--verbose"], "Unknown option '--verbose'"),
+        ] {
+            match parse(&args) {
+                Err(message) => assert!(message.starts_with(error), "{:?}: {}", args, message),
+                Ok(_) => panic!("{:?} should be rejected", args),
+            }
+        }
+    }
+
+    #[test]
+    fn shows_help() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--edges", "e.csv", "-h"]).unwrap().is_none());
+        assert!(!has_command(&[]));
+        assert!(!has_command(&[String::from("-psn_0_12345")]));
+        assert!(has_command(&[String::from("layout")]));
+    }
+}
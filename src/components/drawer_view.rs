@@ -1,5 +1,5 @@
 use egui::{Response, Ui, Vec2};
-use crate::components::drawers::{KernelView, MessageView};
+use crate::components::drawers::{KernelView, MessageView, TimelineView};
 use crate::constant::FONT_SIZE_TITLE;
 use crate::models::app_model::DockStage;
 
@@ -67,6 +67,9 @@ impl AppView for DrawerView {
                     DockStage::Kernel => {
                         self.kernel_view.show(models, ui, frame);
                     },
+                    DockStage::Timeline => {
+                        TimelineView::default().show(models, ui, frame);
+                    },
                     _ => {
                         ui.centered_and_justified(|ui| {
                             ui.set_min_height(100.0);
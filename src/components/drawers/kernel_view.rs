@@ -1,8 +1,9 @@
 
-use egui::{Color32, Ui};
+use egui::{Color32, Sense, Ui};
 use crate::components::AppView;
-use crate::constant::FONT_SIZE_BODY;
-use crate::models::graphics_model::KERNEL_NAMES;
+use crate::constant::{ACCENT_COLOR, FONT_SIZE_BODY};
+use crate::models::graphics_lib::kernel_timer::{KernelTimer, TimingSeries, TimingSource};
+use crate::models::graphics_lib::kernel_status::{describe_kernel_status, KernelRecovery, KERNEL_NAMES, STATUS_NODES_QUARANTINED};
 
 use crate::models::Models;
 use crate::widgets::frames::{drawer_kernel_content_frame};
@@ -13,11 +14,20 @@ pub struct KernelView {
     pub selected_kernel: usize,
 }
 
-fn get_kernel_status_desc(code: i32) -> &'static str {
-    match code {
-        -1  => "Uninitialized",
-        0   => "Running",
-        _   => ""
+fn get_kernel_status_desc(code: i32) -> String {
+    match describe_kernel_status(code) {
+        Some(status) => format!("{}: {}", status.title, status.description),
+        None => String::from("Unknown status code"),
+    }
+}
+
+fn get_kernel_recovery_desc(code: i32) -> &'static str {
+    match describe_kernel_status(code).map(|status| status.recovery) {
+        _ if code == STATUS_NODES_QUARANTINED => "Re-seed repeatedly quarantined nodes near their neighbors",
+        _ if code <= 0                      => "None needed",
+        Some(KernelRecovery::SpreadNodes)   => "Spread nearly coincident nodes and retry",
+        Some(KernelRecovery::GrowTree)      => "Double the tree buffer and retry",
+        _                                   => "Computing stops",
     }
 }
 
@@ -69,9 +79,58 @@ impl AppView for KernelView {
                             ui.end_row();
 
                             grid_label(ui, "Status code description");
-                            grid_content(ui, get_kernel_status_desc(code));
+                            grid_content(ui, get_kernel_status_desc(code).as_str());
+
+                            ui.end_row();
+
+                            grid_label(ui, "Automatic recovery");
+                            grid_content(ui, get_kernel_recovery_desc(code));
                         });
 
+                    // Multilevel 布局的进度
+                    if let Some(multilevel) = &_models.graphics_model.graphics_resources.multilevel {
+
+                        ui.separator();
+
+                        let level = &multilevel.levels[multilevel.current_level];
+                        let finished_levels = multilevel.level_count() - 1 - multilevel.current_level;
+                        let progress = if multilevel.current_level == 0 {
+                            1.0
+                        } else {
+                            (finished_levels as f32 + multilevel.iteration as f32 / multilevel.iterations_per_level as f32)
+                                / (multilevel.level_count() - 1) as f32
+                        };
+
+                        inspector_grid("multilevel_grid")
+                            .show(ui, |ui| {
+                                grid_label(ui, "Multilevel level");
+                                grid_content(ui, format!("{} / {}", multilevel.current_level, multilevel.level_count() - 1).as_str());
+
+                                ui.end_row();
+
+                                grid_label(ui, "Level nodes / edges");
+                                grid_content(ui, format!("{} / {}", level.node_count, level.edge_count).as_str());
+
+                                ui.end_row();
+
+                                grid_label(ui, "Level iteration");
+                                if multilevel.current_level == 0 {
+                                    grid_content(ui, "Original graph");
+                                } else {
+                                    grid_content(ui, format!("{} / {}", multilevel.iteration, multilevel.iterations_per_level).as_str());
+                                }
+
+                                ui.end_row();
+
+                                grid_label(ui, "Multilevel progress");
+                                ui.add(egui::ProgressBar::new(progress).show_percentage());
+                            });
+                    }
+
+                    ui.separator();
+
+                    kernel_timings(ui, &mut _models.graphics_model.graphics_resources.kernel_timer);
+
                 });
 
             // ui.centered_and_justified(|ui| {
@@ -83,6 +142,75 @@ impl AppView for KernelView {
     }
 }
 
+// 每个 Kernel 最近若干次的耗时，Compute 按每次迭代统计，Render 按每帧统计
+fn kernel_timings(ui: &mut egui::Ui, kernel_timer: &mut KernelTimer) {
+
+    ui.horizontal(|ui| {
+        let mut is_enabled = kernel_timer.is_enabled;
+        if ui.checkbox(&mut is_enabled, "Kernel timing").changed() {
+            kernel_timer.set_enabled(is_enabled);
+        }
+        ui.label(egui::RichText::new(match kernel_timer.source {
+            TimingSource::Timestamp => "GPU timestamp queries",
+            TimingSource::WallClock => "Wall clock, each compute kernel is submitted and waited on separately",
+        }).weak());
+    });
+
+    let timings = &kernel_timer.timings;
+    if timings.groups.is_empty() { return; }
+
+    egui::Grid::new("kernel_timing_grid")
+        .num_columns(6)
+        .spacing([10.0, 4.0])
+        .min_col_width(65.)
+        .min_row_height(10.)
+        .show(ui, |ui| {
+            for title in ["Kernel", "Min", "Avg", "Max", "Share", "History"] {
+                ui.label(egui::RichText::new(title).weak());
+            }
+            ui.end_row();
+
+            // 每次迭代与每帧的总耗时
+            for (group, series) in &timings.groups {
+                ui.label(egui::RichText::new(group.name()).strong());
+                timing_row(ui, series, None);
+                ui.end_row();
+            }
+
+            for (name, group, series) in &timings.kernels {
+                ui.label(name.as_str());
+                let share = timings.group(*group).map(|total| series.average() / total.average().max(f32::EPSILON));
+                timing_row(ui, series, share);
+                ui.end_row();
+            }
+        });
+}
+
+fn timing_row(ui: &mut egui::Ui, series: &TimingSeries, share: Option<f32>) {
+    ui.label(format!("{:.3} ms", series.min()));
+    ui.label(format!("{:.3} ms", series.average()));
+    ui.label(format!("{:.3} ms", series.max()));
+    ui.label(share.map_or(String::new(), |share| format!("{:.1} %", share * 100.0)));
+    sparkline(ui, series);
+}
+
+// 按最大值归一化的折线
+fn sparkline(ui: &mut egui::Ui, series: &TimingSeries) {
+
+    let (rect, _response) = ui.allocate_exact_size(egui::vec2(120.0, 14.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_gray(24));
+
+    let count = series.samples.len();
+    if count < 2 { return; }
+    let max = series.max().max(f32::EPSILON);
+    let points = series.samples.iter().enumerate().map(|(index, &value)| egui::pos2(
+        rect.left() + index as f32 / (count - 1) as f32 * rect.width(),
+        rect.bottom() - value / max * (rect.height() - 2.0) - 1.0,
+    )).collect();
+    painter.add(egui::Shape::line(points, (1.0, ACCENT_COLOR)));
+}
+
 fn inspector_grid(id: &str) -> egui::Grid {
     egui::Grid::new(id)
         .num_columns(2)
@@ -1,7 +1,9 @@
 mod message_view;
 mod kernel_view;
+mod timeline_view;
 
 pub use {
     message_view::MessageView,
-    kernel_view::KernelView
+    kernel_view::KernelView,
+    timeline_view::TimelineView,
 };
\ No newline at end of file
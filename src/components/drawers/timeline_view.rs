@@ -0,0 +1,179 @@
+use egui::{Color32, Sense, Ui, Vec2};
+use crate::components::AppView;
+use crate::constant::ACCENT_COLOR;
+
+use crate::models::graphics_lib::memory_planner::format_bytes;
+use crate::models::Models;
+use crate::widgets::frames::drawer_kernel_content_frame;
+
+// 直方图的柱数
+const HISTOGRAM_BIN_COUNT: usize = 120;
+
+const MEGABYTE: usize = 1024 * 1024;
+
+#[derive(Default)]
+pub struct TimelineView;
+
+impl AppView for TimelineView {
+    fn show(&mut self, models: &mut Models, ui: &mut Ui, _frame: &mut eframe::Frame) {
+        drawer_kernel_content_frame(ui.style()).show(ui, |ui| {
+
+            ui.set_style(ui.ctx().style());
+            ui.spacing_mut().item_spacing = egui::vec2(8.0, 4.0);
+
+            show_layout_history(models, ui);
+            ui.separator();
+
+            let timeline = &mut models.timeline_model;
+            let (min, max) = match timeline.range {
+                Some(range) => range,
+                None => {
+                    ui.centered_and_justified(|ui| {
+                        ui.label(egui::RichText::new("Choose a time column when importing data to use the timeline").weak());
+                    });
+                    return;
+                }
+            };
+
+            ui.horizontal(|ui| {
+                if ui.button(if timeline.is_playing { "⏸" } else { "▶" }).clicked() {
+                    if timeline.is_playing { timeline.is_playing = false } else { timeline.play() }
+                }
+
+                ui.label("Window");
+                ui.add(egui::DragValue::new(&mut timeline.window)
+                    .clamp_range(0.0..=(max - min))
+                    .speed((max - min) / 200.0));
+
+                ui.label("Duration");
+                ui.add(egui::DragValue::new(&mut timeline.duration)
+                    .clamp_range(1.0..=600.0)
+                    .suffix(" s"));
+
+                let (start, end) = timeline.window_range();
+                ui.label(egui::RichText::new(format!(
+                    "{}  –  {}",
+                    timeline.format_time(start.max(min)),
+                    timeline.format_time(end),
+                )).weak());
+            });
+
+            // 直方图兼作拖动条，窗口以高亮显示
+            let histogram = timeline.histogram(HISTOGRAM_BIN_COUNT);
+            let size = Vec2::new(ui.available_width(), ui.available_height().max(40.0));
+            let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let ratio = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0) as f64;
+                timeline.current = min + (max - min) * ratio;
+                timeline.is_playing = false;
+            }
+
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 0.0, Color32::from_gray(24));
+
+            let to_x = |time: f64| rect.left() + ((time - min) / (max - min).max(f64::EPSILON)) as f32 * rect.width();
+            let (start, end) = timeline.window_range();
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(to_x(start.max(min))..=to_x(end), rect.y_range()),
+                0.0,
+                Color32::from_white_alpha(16),
+            );
+
+            let max_count = histogram.iter().copied().max().unwrap_or(0).max(1) as f32;
+            let bin_width = rect.width() / HISTOGRAM_BIN_COUNT as f32;
+            for (bin, &count) in histogram.iter().enumerate() {
+                if count == 0 { continue; }
+                let left = rect.left() + bin as f32 * bin_width;
+                let height = count as f32 / max_count * (rect.height() - 4.0);
+                painter.rect_filled(
+                    egui::Rect::from_min_max(egui::pos2(left + 0.5, rect.bottom() - height), egui::pos2(left + bin_width - 0.5, rect.bottom())),
+                    0.0,
+                    Color32::from_gray(120),
+                );
+            }
+
+            let x = to_x(end);
+            painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], (1.5, ACCENT_COLOR));
+        });
+    }
+}
+
+// 布局历史：记录设置、已保存的计算帧，点击标记回到该帧
+fn show_layout_history(models: &mut Models, ui: &mut Ui) {
+
+    let history = &mut models.graphics_model.graphics_resources.layout_history;
+    let mut restore = None;
+    let mut is_resuming = false;
+    let mut is_exporting = false;
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut history.is_recording, "Record Layout");
+
+        ui.label("Every");
+        ui.add(egui::DragValue::new(&mut history.interval)
+            .clamp_range(1..=10000)
+            .suffix(" frames"));
+
+        ui.label("Memory");
+        let mut memory_cap = history.memory_cap / MEGABYTE;
+        if ui.add(egui::DragValue::new(&mut memory_cap).clamp_range(1..=16384).suffix(" MB")).changed() {
+            history.memory_cap = memory_cap * MEGABYTE;
+            history.enforce_memory_cap();
+        }
+
+        let is_selected = history.selected.is_some();
+        is_resuming = ui.add_enabled(is_selected, egui::Button::new("Resume")).clicked();
+        is_exporting = ui.add_enabled(is_selected, egui::Button::new("Export Frame")).clicked();
+        if ui.add_enabled(!history.snapshots.is_empty(), egui::Button::new("Clear")).clicked() {
+            history.clear();
+        }
+
+        let text = match (history.snapshots.front(), history.snapshots.back()) {
+            (Some(first), Some(last)) => format!(
+                "{} snapshots, frames {} – {}, {} / {}",
+                history.snapshots.len(),
+                first.frame,
+                last.frame,
+                format_bytes(history.size() as u64),
+                format_bytes(history.memory_cap as u64),
+            ),
+            _ => String::from("No snapshots"),
+        };
+        ui.label(egui::RichText::new(text).weak());
+    });
+
+    // 每条记录一个标记，按帧号排列
+    let size = Vec2::new(ui.available_width(), 18.0);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::from_gray(24));
+
+    if let (Some(first), Some(last)) = (history.snapshots.front(), history.snapshots.back()) {
+        let (first, last) = (first.frame as f32, last.frame as f32);
+        let span = (last - first).max(1.0);
+        let to_x = |frame: u32| rect.left() + 4.0 + (frame as f32 - first) / span * (rect.width() - 8.0);
+
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let frame = first + ((pointer.x - rect.left() - 4.0) / (rect.width() - 8.0)).clamp(0.0, 1.0) * span;
+            let index = history.nearest(frame.round() as u32);
+            if index != history.selected { restore = index; }
+        }
+
+        for (index, snapshot) in history.snapshots.iter().enumerate() {
+            let x = to_x(snapshot.frame);
+            let (width, color) = if history.selected == Some(index) { (2.0, ACCENT_COLOR) } else { (1.0, Color32::from_gray(120)) };
+            painter.line_segment([egui::pos2(x, rect.top() + 3.0), egui::pos2(x, rect.bottom() - 3.0)], (width, color));
+        }
+    }
+
+    if let Some(index) = restore {
+        models.restore_layout_snapshot(index);
+    }
+    if is_resuming {
+        models.resume_from_snapshot();
+    }
+    if is_exporting {
+        models.render_output();
+    }
+}
@@ -1,8 +1,7 @@
-use std::{fs::File, io::Write};
 use std::default::Default;
+use std::path::PathBuf;
 
 use egui::{Context, Widget};
-use rayon::prelude::*;
 
 use crate::{
     models::Models,
@@ -13,11 +12,13 @@ use crate::{
     },
 };
 use crate::constant::ACCENT_COLOR;
+use crate::utils::export::{write_columns, ExportColumn, ExportFormat};
 use crate::utils::message::message_info;
 
 pub struct ExportModal {
     directory_path: String,
     file_name: String,
+    format: ExportFormat,
     is_cast_to_float: bool,
 }
 
@@ -28,6 +29,7 @@ impl Default for ExportModal {
         Self {
             directory_path: "".to_string(),
             file_name: "".to_string(),
+            format: ExportFormat::Pcache,
             is_cast_to_float: false,
         }
     }
@@ -49,8 +51,8 @@ impl ExportModal {
                     ui.heading("Export Data");
 
                     ui.horizontal(|ui| {
-                        ui.selectable_value(&mut 0, 0, "PCACHE");
-                        ui.selectable_value(&mut 0, 1, "CSV");
+                        ui.selectable_value(&mut self.format, ExportFormat::Pcache, "PCACHE");
+                        ui.selectable_value(&mut self.format, ExportFormat::Csv, "CSV");
                     });
 
                     ui.separator();
@@ -97,7 +99,7 @@ impl ExportModal {
                                         .desired_width(150.)
                                 );
                                 let file_name = if self.file_name.len() != 0 { &self.file_name } else { DEFAULT_FILE_NAME };
-                                text.on_hover_text(egui::RichText::new(format!("{0}_node.pcache \n{0}_edge.pcache", file_name)).weak());
+                                text.on_hover_text(egui::RichText::new(format!("{0}_node.{1} \n{0}_edge.{1}", file_name, self.format.extension())).weak());
                             });
 
                             ui.end_row();
@@ -133,50 +135,27 @@ impl ExportModal {
     fn on_click_done(&mut self, models: &mut Models) -> std::io::Result<()> {
         let file_name = if self.file_name.len() != 0 { &self.file_name } else { DEFAULT_FILE_NAME };
         let path_prefix = self.directory_path.clone() + "/" + file_name;
-        let node_path = path_prefix.clone() + "_node.pcache";
-        let edge_path = path_prefix + "_edge.pcache";
+        let node_path = format!("{}_node.{}", path_prefix, self.format.extension());
+        let edge_path = format!("{}_edge.{}", path_prefix, self.format.extension());
 
         let graphics_resource = &mut models.graphics_model.graphics_resources;
-        graphics_resource.debug();
-        if let Some(graph_resources) = &graphics_resource.graph_resources {
-
-            let mut file = File::create(&node_path)?;
-            file.write_all(b"pcache\n")?;
-            file.write_all(b"comment Node PCACHE file Exported from GraphPU\n")?;
-            file.write_all(b"format binary 1.0\n")?;
-            file.write_fmt(format_args!("elements {}\n", graph_resources.status.node_count))?;
-
-            file.write_fmt(format_args!("property {} {}\n", "float", "position.x"))?;
-            file.write_fmt(format_args!("property {} {}\n", "float", "position.y"))?;
-            file.write_fmt(format_args!("property {} {}\n", "float", "position.z"))?;
-
-            file.write_all(b"end_header\n")?;
-            file.write_all(bytemuck::cast_slice(graph_resources.buffer_bytes.as_ref().unwrap()))?;
+        if graphics_resource.graph_resources.is_some() {
+            let positions = graphics_resource.read_positions();
+            write_columns(&PathBuf::from(&node_path), self.format, "Node PCACHE file Exported from GraphPU", &ExportColumn::positions(&positions))?;
         }
 
-
         if let Some(source_target_list) = &models.data_model.source_target_list {
 
-            let result: Vec<u8> = if self.is_cast_to_float {
-                let par_iter = source_target_list.par_iter().map(|x| *x as f32);
-                let doubles: Vec<f32> = par_iter.collect();
-                bytemuck::cast_slice(&doubles).to_vec()
-            } else {
-                bytemuck::cast_slice(&source_target_list).to_vec()
+            let column = |name: &str, offset: usize| {
+                let values = source_target_list.iter().skip(offset).step_by(2).copied();
+                if self.is_cast_to_float {
+                    ExportColumn::float(name, values.map(|x| x as f32).collect())
+                } else {
+                    ExportColumn::uint(name, values.collect())
+                }
             };
-
-            let mut file = File::create(&edge_path)?;
-            file.write_all(b"pcache\n")?;
-            file.write_all(b"comment Edge PCACHE file Exported from GraphPU\n")?;
-            file.write_all(b"format binary 1.0\n")?;
-            file.write_fmt(format_args!("elements {}\n", models.data_model.status.edge_count))?;
-
-            let type_string = if self.is_cast_to_float { "float" } else { "uint" };
-            file.write_fmt(format_args!("property {} {}\n", type_string, "source"))?;
-            file.write_fmt(format_args!("property {} {}\n", type_string, "target"))?;
-
-            file.write_all(b"end_header\n")?;
-            file.write_all(bytemuck::cast_slice(&result))?;
+            let columns = [column("source", 0), column("target", 1)];
+            write_columns(&PathBuf::from(&edge_path), self.format, "Edge PCACHE file Exported from GraphPU", &columns)?;
         }
 
         let text = format!(
@@ -184,7 +163,7 @@ impl ExportModal {
             node_path,
             edge_path
         );
-        message_info("Export PCACHE Data Succeeded", text.as_str());
+        message_info(&format!("Export {} Data Succeeded", self.format), text.as_str());
         Ok(())
     }
 }
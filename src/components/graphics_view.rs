@@ -1,8 +1,11 @@
 use std::borrow::BorrowMut;
 use std::ops::Mul;
 use egui::{InnerResponse, PointerButton, Response, Ui, Vec2, Widget, WidgetText};
+use egui::plot::{Legend, Line, Plot, PlotPoints};
 use crate::models::app_model::Tool;
+use crate::models::data_model::DataModel;
 use crate::models::graphics_model::{CastType, GraphicsResources};
+use crate::models::layout_lib::LayoutMetrics;
 
 use crate::models::Models;
 use crate::widgets::frames::{button_group_style, DEFAULT_BUTTON_PADDING, graphics_frame, graphics_hover_frame, TOOL_BUTTON_PADDING, tool_item_group_style, toolbar_inner_frame, toolbar_inner_frame_bottom, toolbar_inner_frame_top};
@@ -52,14 +55,25 @@ impl AppView for GraphicsView {
 
                                 // 如果正在持续计算，则计算一次
                                 if is_computing {
-                                    compute_resources.compute();
+                                    compute_resources.iterate(&models.data_model);
                                 }
 
                                 // 如果当前帧需要 Dispatch，则 Dispatch 一次
                                 if is_dispatching {
-                                    compute_resources.randomize();
+                                    compute_resources.dispatch(&models.data_model);
                                 }
 
+                                if is_computing || is_dispatching {
+                                    compute_resources.update_metrics();
+                                }
+
+                                if is_computing {
+                                    compute_resources.update_layout_history();
+                                }
+
+                                // 取回之前计算帧的 Kernel 状态码
+                                compute_resources.poll_kernel_status(&models.data_model);
+
                                 // 更新 Viewport，用于处理窗口 resize
                                 // update_viewport 方法会判断传入的 Viewport 大小和之前的是否一致
                                 // 若发生变化，则更新材质视图，注册 egui 材质 ID，并返回 true
@@ -70,6 +84,10 @@ impl AppView for GraphicsView {
                                     max_rect.size().mul(Vec2::from([models.app_model.pixels_per_point; 2]))
                                 );
 
+                                if models.app_model.current_tool == Tool::Handle {
+                                    compute_resources.update_node_drag(ui, models.graphics_model.is_hover_graphics_view, models.app_model.pixels_per_point);
+                                }
+
                                 compute_resources.update_control(ui, models.graphics_model.is_hover_graphics_view, models.app_model.pixels_per_point);
                                 if compute_resources.control.pointer_pos.is_some() {
                                     if ui.input().pointer.button_double_clicked(PointerButton::Primary) {
@@ -92,7 +110,10 @@ impl AppView for GraphicsView {
                                     ui.ctx().request_repaint();
                                 }
 
-                                if models.app_model.current_tool == Tool::Select && compute_resources.control.is_pointer_update {
+                                // Select 与 Handle 工具都需要知道指针下的节点
+                                let is_casting = models.app_model.current_tool == Tool::Select
+                                    || (models.app_model.current_tool == Tool::Handle && compute_resources.node_drag.is_none());
+                                if is_casting && compute_resources.control.is_pointer_update {
                                     compute_resources.render_cast();
                                     compute_resources.control.is_pointer_update = false;
                                 }
@@ -133,6 +154,11 @@ impl AppView for GraphicsView {
                                             }
                                         );
                                         ui.label(egui::RichText::new(format!("{}", compute_resources.cast_value)).weak());
+                                        if let CastType::Node = cast_type {
+                                            if compute_resources.pinned_nodes.contains(&compute_resources.cast_value) {
+                                                ui.label(egui::RichText::new("📌 Pinned").weak());
+                                            }
+                                        }
                                     });
                                 });
                         });
@@ -216,6 +242,10 @@ impl AppView for GraphicsView {
                                     });
 
 
+                                    toggle_button(ui, &mut graphics_resources.render_options.is_order_independent, "◐")
+                                        .on_hover_text("Toggle Order-Independent Transparency")
+                                        .clicked().then(|| { need_update(ui, graphics_resources) });
+
                                     toggle_button(ui, &mut graphics_resources.render_options.is_rendering_bounding_box, "⛶")
                                         .on_hover_text("Toggle Bounding Box")
                                         .clicked().then(|| { need_update(ui, graphics_resources) });
@@ -264,6 +294,8 @@ impl AppView for GraphicsView {
                                     ui.label(egui::RichText::new("Compute frames: ").weak());
                                 });
 
+                                metrics_overlay(ui, graphics_resources, &models.data_model);
+
                                 ui.horizontal(|ui| {
                                     ui.label(format!("{:06}", graphics_resources.render_frame_count));
                                     ui.label(egui::RichText::new("Render frames: ").weak());
@@ -300,6 +332,65 @@ fn toggle_button(ui: &mut egui::Ui, selected: &mut bool, text: impl Into<WidgetT
     }).inner
 }
 
+// 布局质量指标，显示最新一次的数值与随计算帧变化的曲线
+fn metrics_overlay(ui: &mut egui::Ui, graphics_resources: &mut GraphicsResources, data_model: &DataModel) {
+
+    let history = &mut graphics_resources.metrics_history;
+
+    if let Some(metrics) = history.latest() {
+        ui.horizontal(|ui| {
+            ui.label(format!("{:.4}", metrics.stress));
+            ui.label(egui::RichText::new("Stress: ").weak());
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{:.4}", metrics.edge_length_variance));
+            ui.label(egui::RichText::new("Edge length variance: ").weak());
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{:.4}", metrics.neighborhood_preservation));
+            ui.label(egui::RichText::new("Neighborhood preservation: ").weak());
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(metrics.edge_crossings.map_or(String::from("-"), |count| count.to_string()));
+            ui.label(egui::RichText::new("Edge crossings: ").weak());
+        });
+
+        let line = |value: fn(&LayoutMetrics) -> f32| {
+            Line::new(history.entries.iter()
+                .map(|(frame, metrics)| [*frame as f64, value(metrics) as f64])
+                .collect::<PlotPoints>())
+        };
+
+        Plot::new("metrics_history")
+            .width(240.0)
+            .height(80.0)
+            .show_axes([false, true])
+            .allow_drag(false)
+            .allow_zoom(false)
+            .legend(Legend::default())
+            .show(ui, |plot_ui| {
+                plot_ui.line(line(|metrics| metrics.stress).name("Stress"));
+                plot_ui.line(line(|metrics| metrics.neighborhood_preservation).name("Neighborhood"));
+            });
+    }
+
+    let mut is_evaluating = false;
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut history.is_auto, "");
+        ui.add(egui::DragValue::new(&mut history.interval).clamp_range(1..=100000).suffix(" frames"));
+        ui.label(egui::RichText::new("Auto every ").weak());
+        is_evaluating = ui.button("Evaluate").clicked();
+    });
+
+    if is_evaluating {
+        graphics_resources.evaluate_metrics(data_model);
+    }
+}
+
 fn need_update(ui: &mut egui::Ui, graphics_resources: &mut GraphicsResources) {
     graphics_resources.need_update = true;
     ui.ctx().request_repaint()
@@ -9,9 +9,10 @@ use tokio::task::JoinHandle;
 use crate::constant::ACCENT_COLOR;
 
 use crate::models::app_model::ImportState;
-use crate::models::data_model::ExternalData;
+use crate::models::data_model::{ExternalData, TimeColumns};
+use crate::models::graphics_lib::memory_planner::SUGGESTED_MEMORY_BUDGET;
 use crate::models::{Models, ImportedData};
-use crate::utils::csv_loader::{read_headers_from_csv, load_data};
+use crate::utils::csv_loader::{read_headers_from_csv, load_data, summarize_csv, CsvSummary};
 use crate::widgets::frames::inner_panel_frame;
 use crate::widgets::modal::Modal;
 
@@ -22,7 +23,6 @@ enum Page {
     Config,
 }
 
-#[derive(Default)]
 pub struct ImportModal {
     page_index: Page,
     node_file_path: String,
@@ -31,13 +31,42 @@ pub struct ImportModal {
     edge_target: usize,
     import_promise: Option<Receiver<Result<ImportedData, String>>>,
     import_join_handle: Option<JoinHandle<()>>,
+    // 节点与边文件的统计，用于在导入前估算显存
+    summary: Option<(CsvSummary, CsvSummary)>,
+    summary_promise: Option<Receiver<Result<(CsvSummary, CsvSummary), String>>>,
+    // 边的抽样比例（%）与显存预算（MB）
+    edge_sample_percent: u32,
+    memory_budget_mb: u64,
+    // 节点文件的列名与时间轴使用的时间列
+    node_headers: Vec<String>,
+    time_columns: TimeColumns,
+}
+
+impl Default for ImportModal {
+    fn default() -> Self {
+        Self {
+            page_index: Page::default(),
+            node_file_path: String::new(),
+            edge_file_path: String::new(),
+            edge_source: 0,
+            edge_target: 0,
+            import_promise: None,
+            import_join_handle: None,
+            summary: None,
+            summary_promise: None,
+            edge_sample_percent: 100,
+            memory_budget_mb: SUGGESTED_MEMORY_BUDGET / 1024 / 1024,
+            node_headers: vec![],
+            time_columns: TimeColumns::default(),
+        }
+    }
 }
 
 impl ImportModal {
     pub fn show(&mut self, ctx: &Context, models: &mut Models) {
         Modal::new(String::from("import_modal_view")).show(ctx, |ui| {
             ui.set_width(400.0);
-            ui.set_height(250.0);
+            ui.set_height(if self.page_index == Page::Config { 540.0 } else { 250.0 });
 
             egui::CentralPanel::default()
                 .frame(inner_panel_frame(ui.style()))
@@ -96,8 +125,23 @@ impl ImportModal {
                 let edge_data_headers = &models.data_model.edge_data.headers_index_str;
                 self.edge_source = edge_data_headers.iter().position(|s| s == "source").unwrap_or(0);
                 self.edge_target = edge_data_headers.iter().position(|s| s == "target").unwrap_or(1);
+                // 节点文件可以为空，此时只能按边的时间决定节点的活动区间
+                self.node_headers = match self.node_file_path.is_empty() {
+                    true => vec![],
+                    false => read_headers_from_csv(&Some(PathBuf::from(self.node_file_path.clone())))
+                        .map(|(_, headers_index_str)| headers_index_str)
+                        .unwrap_or_default(),
+                };
+                self.time_columns = TimeColumns {
+                    edge_start: ["timestamp", "time", "start"].iter()
+                        .find(|name| edge_data_headers.iter().any(|s| s == *name))
+                        .map(|name| name.to_string()),
+                    edge_end: edge_data_headers.iter().find(|s| *s == "end").cloned(),
+                    ..Default::default()
+                };
                 self.page_index = Page::Config;
                 models.app_model.import_state = ImportState::Initial;
+                self.summarize_files();
             }
             Err(s) => {
                 models.app_model.import_state =
@@ -106,15 +150,73 @@ impl ImportModal {
         }
     }
 
+    // 在后台统计行数与每列的最大值，节点文件可以为空
+    #[allow(unused_must_use)]
+    fn summarize_files(&mut self) {
+        let node_file_path = self.node_file_path.clone();
+        let edge_file_path = self.edge_file_path.clone();
+        let (sender, recv) = mpsc::channel();
+        tokio::task::spawn(async move {
+            let node_summary = summarize_csv(&Some(PathBuf::from(node_file_path))).unwrap_or_default();
+            sender.send(summarize_csv(&Some(PathBuf::from(edge_file_path))).map(|edge_summary| (node_summary, edge_summary)));
+        });
+        self.summary = None;
+        self.summary_promise = Some(recv);
+    }
+
+    fn check_summary_done(&mut self) {
+        if let Some(promise) = &self.summary_promise {
+            match promise.try_recv() {
+                Ok(result) => {
+                    self.summary = result.ok();
+                    self.summary_promise = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.summary_promise = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+    }
+
+    // 按当前的列与抽样比例估算节点数和边数
+    // 节点数与导入后相同，取节点文件行数与最大节点 ID + 1 的较大值
+    fn estimated_counts(&self) -> Option<(usize, usize)> {
+        let (node_summary, edge_summary) = self.summary.as_ref()?;
+        let max_id = [self.edge_source, self.edge_target].iter()
+            .filter_map(|&column| edge_summary.column_max.get(column).copied().flatten())
+            .max();
+        let node_count = node_summary.row_count.max(max_id.map_or(0, |max_id| max_id as usize + 1));
+        let edge_count = (edge_summary.row_count as u64 * self.edge_sample_percent as u64 / 100) as usize;
+        Some((node_count, edge_count))
+    }
+
+    fn is_within_budget(&self, models: &Models) -> bool {
+        let graphics_resources = &models.graphics_model.graphics_resources;
+        match self.estimated_counts() {
+            Some((node_count, edge_count)) => {
+                graphics_resources.estimate_memory(node_count, edge_count).total() <= self.memory_budget_mb * 1024 * 1024
+                    && graphics_resources.check_memory(node_count, edge_count).is_ok()
+            }
+            None => true,
+        }
+    }
+
+    // 逐级降低边的抽样比例，直到不超过预算与 GPU 的限制
+    fn fit_to_budget(&mut self, models: &Models) {
+        while self.edge_sample_percent > 1 && !self.is_within_budget(models) {
+            self.edge_sample_percent -= 1;
+        }
+    }
+
     #[allow(unused_must_use)]
     fn on_click_done(&mut self) {
         let node_file_path = self.node_file_path.clone();
         let edge_file_path = self.edge_file_path.clone();
         let edge_source = self.edge_source;
         let edge_target = self.edge_target;
+        let edge_sample_ratio = self.edge_sample_percent as f32 / 100.0;
         let (sender, recv) = mpsc::channel();
         let join_handle = tokio::task::spawn(async move {
-            sender.send(load_data(&node_file_path, &edge_file_path, edge_source, edge_target));
+            sender.send(load_data(&node_file_path, &edge_file_path, edge_source, edge_target, edge_sample_ratio));
         });
         self.import_promise = Some(recv);
         self.import_join_handle = Some(join_handle);
@@ -126,6 +228,7 @@ impl ImportModal {
                 Ok(result) => {
                     match result {
                         Ok(data) => {
+                            models.data_model.time_columns = self.time_columns.clone();
                             models.setup_data(data);
                             self.reset_import_promise();
                             self.page_index = Page::FilePicker;
@@ -169,5 +272,9 @@ impl ImportModal {
         }
         self.import_promise = None;
         self.import_join_handle = None;
+        self.summary = None;
+        self.summary_promise = None;
+        self.edge_sample_percent = 100;
+        self.time_columns = TimeColumns::default();
     }
 }
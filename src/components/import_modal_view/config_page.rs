@@ -1,5 +1,6 @@
-use egui::Ui;
+use egui::{Color32, Ui};
 
+use crate::models::graphics_lib::memory_planner::format_bytes;
 use crate::models::Models;
 
 use super::ImportModal;
@@ -42,5 +43,123 @@ pub fn show(parent: &mut ImportModal, models: &mut Models, ui: &mut Ui) {
             });
 
             ui.end_row();
+
+            let edge_headers = &models.data_model.edge_data.headers_index_str;
+            let time_columns = &mut parent.time_columns;
+
+            ui.add(egui::Label::new("Edge Time"));
+            time_column_combo(ui, "Edge Time", edge_headers, &mut time_columns.edge_start);
+            ui.end_row();
+
+            ui.add(egui::Label::new("Edge End Time"));
+            ui.add_enabled_ui(time_columns.edge_start.is_some(), |ui| {
+                time_column_combo(ui, "Edge End Time", edge_headers, &mut time_columns.edge_end);
+            });
+            ui.end_row();
+
+            ui.add(egui::Label::new("Node Time"));
+            time_column_combo(ui, "Node Time", &parent.node_headers, &mut time_columns.node_start);
+            ui.end_row();
+
+            ui.add(egui::Label::new("Node End Time"));
+            ui.add_enabled_ui(time_columns.node_start.is_some(), |ui| {
+                time_column_combo(ui, "Node End Time", &parent.node_headers, &mut time_columns.node_end);
+            });
+            ui.end_row();
+        });
+
+    ui.separator();
+
+    show_memory_estimate(parent, models, ui);
+}
+
+// 时间列可以不选，选择后在 Timeline 中按时间过滤
+fn time_column_combo(ui: &mut Ui, id: &str, headers: &[String], column: &mut Option<String>) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(column.as_deref().unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(column, None, "None");
+                for header in headers {
+                    ui.selectable_value(column, Some(header.clone()), header);
+                }
+            });
+    });
+}
+
+// 导入前的显存估算，超出预算或 GPU 的限制时提供抽样与关闭深度排序
+fn show_memory_estimate(parent: &mut ImportModal, models: &mut Models, ui: &mut Ui) {
+
+    parent.check_summary_done();
+
+    let (node_count, edge_count) = match parent.estimated_counts() {
+        Some(counts) => counts,
+        None => {
+            ui.horizontal(|ui| {
+                if parent.summary_promise.is_some() {
+                    ui.spinner();
+                    ui.label("Estimating GPU memory...");
+                } else {
+                    ui.label("GPU memory can't be estimated for this file");
+                }
+            });
+            return;
+        }
+    };
+
+    let graphics_resources = &models.graphics_model.graphics_resources;
+    let estimate = graphics_resources.estimate_memory(node_count, edge_count);
+    let check = graphics_resources.check_memory(node_count, edge_count);
+    let max_binding_size = graphics_resources.memory_planner().max_binding_size();
+    let is_within_budget = parent.is_within_budget(models);
+
+    egui::Grid::new("memory_grid")
+        .num_columns(2)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Nodes / Edges");
+            ui.label(format!("{} / {}", node_count, edge_count));
+            ui.end_row();
+
+            for item in &estimate.items {
+                ui.label(egui::RichText::new(item.label).weak());
+                ui.label(format_bytes(item.size));
+                ui.end_row();
+            }
+
+            ui.label("Total");
+            ui.label(format_bytes(estimate.total()));
+            ui.end_row();
+
+            ui.label("Budget");
+            ui.add(egui::DragValue::new(&mut parent.memory_budget_mb).clamp_range(64..=1024 * 1024).suffix(" MB"));
+            ui.end_row();
+
+            ui.label("Max Binding");
+            ui.label(format_bytes(max_binding_size));
+            ui.end_row();
+
+            ui.label("Edge Sample");
+            ui.add(egui::Slider::new(&mut parent.edge_sample_percent, 1..=100).suffix("%"));
+            ui.end_row();
+
+            ui.label("Depth Sorting");
+            ui.checkbox(&mut models.graphics_model.graphics_resources.render_options.is_depth_sort, "");
+            ui.end_row();
+        });
+
+    if let Err(error) = check {
+        ui.colored_label(Color32::RED, error.to_string());
+    } else if !is_within_budget {
+        ui.colored_label(Color32::YELLOW, "The estimate exceeds the budget");
+    }
+
+    if !is_within_budget {
+        ui.horizontal(|ui| {
+            ui.label("Try sampling edges or disabling depth sorting");
+            if ui.button("Fit to Budget").clicked() {
+                parent.fit_to_budget(models);
+            }
         });
+    }
 }
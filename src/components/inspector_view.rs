@@ -6,12 +6,18 @@ use crate::models::Models;
 use crate::models::app_model::{ImportState, InspectorTab};
 use crate::models::graphics_model::ComputeMethod;
 use crate::models::graphics_model::ComputeMethodType;
+use crate::models::graphics_model::ClusterSource;
+use crate::models::graphics_model::MAX_ITERATIONS_PER_FRAME;
+use crate::models::layout_lib::GeoProjection;
 use crate::models::data_model::{ColorType, ColorRamp, ColorPalette, SizeType};
 use crate::utils::file::{path_to_string, pick_folder, system_open_directory};
 use crate::widgets::frames::{button_group_style, DEFAULT_BUTTON_PADDING, inspector_frame, inspector_inner_frame};
 
 use super::AppView;
 
+// Pinned 区域最多列出的节点数
+const MAX_LISTED_PINNED_NODES: usize = 32;
+
 #[derive(Default)]
 pub struct InspectorView;
 
@@ -156,6 +162,9 @@ impl InspectorView {
             ui.end_row();
         });
 
+        let mut is_cluster_changed = false;
+        let mut is_packing = false;
+
         inspector_section(ui, true, "Layout", |ui| {
 
             grid_label(ui, "Method");
@@ -163,11 +172,115 @@ impl InspectorView {
                 .selected_text(node_settings.position_compute.0)
                 .show_ui(ui, |ui| {
                     ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::FORCE_ATLAS2, ComputeMethod::FORCE_ATLAS2.0);
+                    ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::MULTILEVEL, ComputeMethod::MULTILEVEL.0);
                     ui.separator();
                     ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::RANDOMIZE, ComputeMethod::RANDOMIZE.0);
+                    ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::SPECTRAL, ComputeMethod::SPECTRAL.0);
+                    ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::RADIAL_TREE, ComputeMethod::RADIAL_TREE.0);
+                    ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::TOP_DOWN_TREE, ComputeMethod::TOP_DOWN_TREE.0);
+                    ui.selectable_value(&mut node_settings.position_compute, ComputeMethod::GEOGRAPHIC, ComputeMethod::GEOGRAPHIC.0);
+                });
+            ui.end_row();
+
+            let compute_options = &mut models.graphics_model.graphics_resources.compute_options;
+
+            grid_label(ui, "Seed");
+            ui.add(egui::DragValue::new(&mut compute_options.seed));
+            ui.end_row();
+
+            grid_label(ui, "Deterministic");
+            ui.checkbox(&mut compute_options.is_deterministic, "");
+            ui.end_row();
+
+            if node_settings.position_compute.1 == ComputeMethodType::Continuous {
+                grid_label(ui, "Iterations / Frame");
+                ui.add(egui::DragValue::new(&mut compute_options.iterations_per_frame).clamp_range(1..=MAX_ITERATIONS_PER_FRAME));
+                ui.end_row();
+            }
+
+            if node_settings.position_compute == ComputeMethod::GEOGRAPHIC {
+                let (latitude, longitude, projection) = &mut node_settings.position_geo;
+                let headers = &models.data_model.node_data.headers_index_str;
+
+                grid_label(ui, "Latitude");
+                column_combox("Geo Latitude", headers, latitude, ui);
+                ui.end_row();
+
+                grid_label(ui, "Longitude");
+                column_combox("Geo Longitude", headers, longitude, ui);
+                ui.end_row();
+
+                grid_label(ui, "Projection");
+                egui::ComboBox::from_id_source("Geo Projection")
+                    .selected_text(projection.to_string())
+                    .show_ui(ui, |ui| {
+                        for value in [GeoProjection::Globe, GeoProjection::Mercator, GeoProjection::Equirectangular] {
+                            ui.selectable_value(projection, value, value.to_string());
+                        }
+                    });
+                ui.end_row();
+
+                let render_options = &mut models.graphics_model.graphics_resources.render_options;
+                let need_update = &mut models.graphics_model.graphics_resources.need_update;
+
+                grid_label(ui, "Graticule");
+                if ui.checkbox(&mut render_options.is_rendering_graticule, "").changed() {
+                    *need_update = true;
+                }
+                ui.end_row();
+
+                grid_label(ui, "Great Circle Edges");
+                if ui.checkbox(&mut render_options.is_great_circle_edge, "").changed() {
+                    *need_update = true;
+                }
+                ui.end_row();
+            }
+
+            if node_settings.position_compute == ComputeMethod::RADIAL_TREE || node_settings.position_compute == ComputeMethod::TOP_DOWN_TREE {
+                let max_index = models.data_model.status.node_count.saturating_sub(1) as u32;
+                let mut is_custom_root = compute_options.tree_root.is_some();
+
+                grid_label(ui, "Root");
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut is_custom_root, "").changed() {
+                        compute_options.tree_root = if is_custom_root { Some(0) } else { None };
+                    }
+                    match &mut compute_options.tree_root {
+                        Some(root) => { ui.add(egui::DragValue::new(root).clamp_range(0..=max_index)); },
+                        None => { ui.label("Auto"); },
+                    }
+                });
+                ui.end_row();
+            }
+
+            grid_label(ui, "Cluster");
+            egui::ComboBox::from_id_source("Cluster Source")
+                .selected_text(compute_options.cluster_source.to_string())
+                .show_ui(ui, |ui| {
+                    for source in [ClusterSource::None, ClusterSource::Partition, ClusterSource::Community] {
+                        if ui.selectable_value(&mut compute_options.cluster_source, source, source.to_string()).changed() {
+                            is_cluster_changed = true;
+                        }
+                    }
                 });
             ui.end_row();
 
+            if compute_options.cluster_source != ClusterSource::None {
+                grid_label(ui, "Attraction");
+                ui.add(egui::Slider::new(&mut compute_options.cluster_attraction, 0.0..=10.0));
+                ui.end_row();
+
+                grid_label(ui, "Repulsion");
+                ui.add(egui::Slider::new(&mut compute_options.cluster_repulsion, 0.0..=10.0));
+                ui.end_row();
+
+                grid_label(ui, "");
+                if ui.button("Update Groups").clicked() {
+                    is_cluster_changed = true;
+                }
+                ui.end_row();
+            }
+
             grid_label(ui, "");
 
             if node_settings.position_compute.1 == ComputeMethodType::Continuous {
@@ -183,8 +296,20 @@ impl InspectorView {
                 }
             }
             ui.end_row();
+
+            grid_label(ui, "");
+            if ui.button("Pack Components").on_hover_text("Arrange disconnected components compactly around the largest one").clicked() {
+                is_packing = true;
+            }
+            ui.end_row();
         });
 
+        if is_cluster_changed {
+            models.update_cluster_groups();
+        }
+        if is_packing {
+            models.graphics_model.graphics_resources.pack_components(&models.data_model);
+        }
     }
 
     fn node_inspector(&mut self, models: &mut Models, ui: &mut Ui) {
@@ -284,6 +409,36 @@ impl InspectorView {
             ui.end_row();
 
         });
+        inspector_section(ui, true, "Pinned", |ui| {
+            let graphics_resources = &mut models.graphics_model.graphics_resources;
+
+            grid_label(ui, "Count");
+            ui.label(format!("{}", graphics_resources.pinned_nodes.len()));
+            ui.end_row();
+
+            let mut unpinned = None;
+            for &index in graphics_resources.pinned_nodes.iter().take(MAX_LISTED_PINNED_NODES) {
+                grid_label(ui, "");
+                ui.horizontal(|ui| {
+                    ui.label(format!("Node {}", index));
+                    if ui.small_button("Unpin").clicked() {
+                        unpinned = Some(index);
+                    }
+                });
+                ui.end_row();
+            }
+            if let Some(index) = unpinned {
+                graphics_resources.set_node_pinned(index, false);
+            }
+
+            if !graphics_resources.pinned_nodes.is_empty() {
+                grid_label(ui, "");
+                if ui.button("Unpin All").clicked() {
+                    graphics_resources.clear_pinned_nodes();
+                }
+                ui.end_row();
+            }
+        });
     }
 
     fn edge_inspector(&mut self, _models: &mut Models, ui: &mut Ui) {
@@ -367,6 +522,11 @@ impl InspectorView {
 
 fn source_combox(id_source: impl Hash, data_hearders: &Vec<String>, current_value: &mut String, ui: &mut Ui) {
     grid_label(ui, "Source");
+    column_combox(id_source, data_hearders, current_value, ui);
+    ui.end_row();
+}
+
+fn column_combox(id_source: impl Hash, data_hearders: &Vec<String>, current_value: &mut String, ui: &mut Ui) {
     egui::ComboBox::from_id_source(id_source)
         .selected_text(current_value.to_string())
         .show_ui(ui, |ui| {
@@ -375,7 +535,6 @@ fn source_combox(id_source: impl Hash, data_hearders: &Vec<String>, current_valu
                 ui.selectable_value(current_value, value.clone(), value);
             }
         });
-    ui.end_row();
 }
 
 fn inspector_grid(id: &str) -> egui::Grid {
@@ -1,5 +1,6 @@
 use egui::Ui;
 
+use crate::utils::file::{pick_session, save_session_file};
 use crate::widgets::frames::menu_panel_style;
 use crate::{
     models::{
@@ -33,16 +34,32 @@ impl AppView for MenuBarView {
                     spacing_ui(ui);
                     ui.menu_button("File", |ui| {
                         spacing_ui_start(ui);
+                        let is_imported = models.app_model.import_state == ImportState::Success;
+
                         ui.add_enabled_ui(false, |ui| {
                             let _ = ui.button("New");
-                            let _ = ui.button("Open");
+                        });
+                        ui.add_enabled_ui(is_imported, |ui| {
+                            if ui.button("Open").on_hover_text("Open a layout session").clicked() {
+                                if let Some(path) = pick_session() {
+                                    models.open_session(path);
+                                }
+                                ui.close_menu();
+                            }
                         });
 
                         ui.separator();
 
                         ui.add_enabled_ui(false, |ui| {
                             let _ = ui.button("Close");
-                            let _ = ui.button("Save");
+                        });
+                        ui.add_enabled_ui(is_imported, |ui| {
+                            if ui.button("Save").on_hover_text("Save positions and pinned nodes as a session").clicked() {
+                                if let Some(path) = save_session_file() {
+                                    models.save_session(path);
+                                }
+                                ui.close_menu();
+                            }
                         });
 
                         ui.separator();
@@ -60,6 +77,14 @@ impl AppView for MenuBarView {
                                     models.app_model.is_import_visible = true;
                                     ui.close_menu();
                                 }
+                                let mut is_watching = models.app_model.edge_watcher.is_some();
+                                if ui.add_enabled(!models.app_model.is_edge_sampled, egui::Checkbox::new(&mut is_watching, "Watch Edge File"))
+                                    .on_hover_text("Apply changes of the edge file without reimporting")
+                                    .on_disabled_hover_text("The edges were sampled on import, reimport all edges to watch the file")
+                                    .changed() {
+                                    models.set_edge_watching(is_watching);
+                                    ui.close_menu();
+                                }
                             }
                             _ => {}
                         }
@@ -1,5 +1,5 @@
 use egui::{Modifiers, Ui};
-use crate::models::graphics_model::ComputeMethodType;
+use crate::models::graphics_model::{CastType, ComputeMethodType, GraphEdit};
 use crate::models::Models;
 
 #[derive(Default)]
@@ -32,6 +32,18 @@ impl Shortcut {
             models.render_output();
         }
 
+        // 删除鼠标所指的节点
+        let remove_node_shortcut =
+            egui::KeyboardShortcut::new(Modifiers::NONE, egui::Key::Delete);
+
+        if models.graphics_model.is_hover_graphics_view && ui.input_mut().consume_shortcut(&remove_node_shortcut) {
+            let graphics_resources = &models.graphics_model.graphics_resources;
+            if let Some(CastType::Node) = graphics_resources.cast_type {
+                let index = graphics_resources.cast_value;
+                models.apply_graph_edits(&[GraphEdit::RemoveNode(index)]);
+            }
+        }
+
         let organize_shortcut =
             egui::KeyboardShortcut::new(Modifiers::NONE, egui::Key::Escape);
 
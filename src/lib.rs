@@ -2,6 +2,7 @@
 extern crate lazy_static;
 
 mod app;
+pub mod cli;
 pub mod widgets;
 pub mod components;
 pub mod models;
@@ -1,8 +1,33 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use graphpu::bootstrap::{start_frame, ConfigBuilder};
+use graphpu::cli;
 
 #[tokio::main]
 async fn main() {
+    // 有子命令时不创建窗口，例如 graphpu layout --edges edges.csv --out positions.csv
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    #[cfg(windows)]
+    if cli::has_command(&args) {
+        attach_parent_console();
+    }
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
     start_frame(ConfigBuilder::default().build());
 }
+
+// 发布版本在 Windows 上为窗口程序，没有控制台
+// 运行子命令时连接到启动它的终端，使用法、进度与错误信息可见
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // 不是从终端启动时连接失败，输出仍被丢弃，只有退出码
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS); }
+}
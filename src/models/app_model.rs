@@ -1,4 +1,5 @@
 use crate::utils::file::desktop_dir_or_empty;
+use crate::utils::file_watcher::EdgeFileWatcher;
 use egui::style::{Selection, Spacing, WidgetVisuals, Widgets};
 use egui::{Color32, Rounding, Stroke, Style, Vec2, Visuals};
 use std::path::PathBuf;
@@ -62,6 +63,10 @@ pub struct AppModel {
     pub import_state: ImportState,
     pub node_file_path: Option<PathBuf>,
     pub edge_file_path: Option<PathBuf>,
+    // 导入时抽样了边，此时文件中的边与图不对应，不能监视
+    pub is_edge_sampled: bool,
+    // 监视边文件时，文件的变化以增量修改应用到图上
+    pub edge_watcher: Option<EdgeFileWatcher>,
     pub output_folder: String,
     pub main_stage: MainStage,
     pub dock_stage: DockStage,
@@ -125,6 +130,8 @@ impl Default for AppModel {
             import_state: Default::default(),
             node_file_path: None,
             edge_file_path: None,
+            is_edge_sampled: false,
+            edge_watcher: None,
             output_folder: desktop_dir_or_empty(),
             main_stage: Default::default(),
             dock_stage: Default::default(),
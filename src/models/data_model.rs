@@ -4,6 +4,7 @@ use eframe::epaint::Color32;
 use strum::Display;
 
 use super::graphics_model::ComputeMethod;
+use super::layout_lib::GeoProjection;
 
 #[derive(Debug, Default)]
 pub struct ExternalData {
@@ -20,6 +21,22 @@ pub struct GraphicsStatus {
     pub edge_data_length: usize,
 }
 
+// 导入时指定的时间列，按列名保存，均为 None 时不启用时间轴
+// 只有起始列时为时间点，同时有结束列时为区间
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TimeColumns {
+    pub edge_start: Option<String>,
+    pub edge_end: Option<String>,
+    pub node_start: Option<String>,
+    pub node_end: Option<String>,
+}
+
+impl TimeColumns {
+    pub fn is_empty(&self) -> bool {
+        self.edge_start.is_none() && self.node_start.is_none()
+    }
+}
+
 #[derive(Display, PartialEq)]
 pub enum PositionType {
     Compute,
@@ -55,6 +72,8 @@ pub struct NodeSettings {
     pub position_type: PositionType,
     pub position_compute: ComputeMethod,
     pub position_set: (f32, f32, f32),
+    // Geographic 布局的纬度列、经度列与投影方式
+    pub position_geo: (String, String, GeoProjection),
     
     pub color_type: ColorType,
     pub color_constant: Color32,
@@ -90,6 +109,7 @@ impl Default for NodeSettings {
             position_type: PositionType::Compute,
             position_compute: ComputeMethod::FORCE_ATLAS2,
             position_set: (0.0, 0.0, 0.0),
+            position_geo: (String::from("None"), String::from("None"), GeoProjection::Globe),
             color_type: ColorType::Constant,
             color_constant: Color32::WHITE,
             color_ramp: (String::from("None"), ColorRamp::Ramp1),
@@ -108,6 +128,7 @@ pub struct DataModel {
     pub edge_target: Option<usize>,
     pub source_target_list: Option<Vec<u32>>,
     pub max_id: u32,
+    pub time_columns: TimeColumns,
     pub status: GraphicsStatus,
     pub node_settings: NodeSettings,
     pub camera_settings: CameraSettings,
@@ -122,6 +143,7 @@ impl Default for DataModel {
             edge_target: None,
             source_target_list: None,
             max_id: 0,
+            time_columns: TimeColumns::default(),
             status: GraphicsStatus::default(),
             node_settings: NodeSettings::default(),
             camera_settings: Default::default()
@@ -26,6 +26,9 @@ pub struct Controls {
     pub is_update: bool,
     pub is_pointer_update: bool,
 
+    // 左键被其他交互（如拖动节点）占用时，不旋转相机
+    pub is_primary_captured: bool,
+
 }
 
 impl Controls {
@@ -42,6 +45,7 @@ impl Controls {
             viewport_size: Vec2::ZERO,
             is_update: true,
             is_pointer_update: false,
+            is_primary_captured: false,
         }
     }
 
@@ -178,7 +182,7 @@ impl Controls {
         // 左键旋转
         // 如果鼠标左键在绘图区域内按下
         // 用鼠标指针的 x、y delta 在绘图区域的占比计算旋转角度，旋转相机
-        if self.primary_down && (self.pointer_delta.x != 0.0 || self.pointer_delta.y != 0.0) {
+        if self.primary_down && !self.is_primary_captured && (self.pointer_delta.x != 0.0 || self.pointer_delta.y != 0.0) {
 
             let mut angles = glam::Vec2::new(self.pointer_delta.x, self.pointer_delta.y);
 
@@ -5,7 +5,8 @@ use wgpu::{Device, ShaderModule, BindGroupLayoutEntry};
 pub struct ComputeShader {
     pub shader: ShaderModule,
     pub device: Arc<Device>,
-    pub kernels: HashMap<String, ComputeKernel>
+    pub kernels: HashMap<String, ComputeKernel>,
+    pub pipelines: HashMap<String, CachedPipeline>,
 }
 
 pub struct ComputeBuffer<'a> {
@@ -33,6 +34,51 @@ impl ComputeShader {
     // }
 
     pub fn create_compute_kernel(&mut self, entry_point: &str, buffers: Vec<ComputeBuffer>) {
+        self.create_named_compute_kernel(entry_point, entry_point, buffers);
+    }
+
+    // 同一入口函数绑定不同 Buffer 时，以不同的名字注册
+    // 入口函数与绑定布局相同的 Kernel 共用缓存的管线，重建 Buffer 时只新建 Bind Group
+    pub fn create_named_compute_kernel(&mut self, name: &str, entry_point: &str, buffers: Vec<ComputeBuffer>) {
+
+        let pipeline_key = buffers.iter().fold(entry_point.to_owned(), |key, compute_buffer| {
+            format!("{} {}{}", key, compute_buffer.binding, match compute_buffer.buffer_type {
+                ComputeBufferType::Storage => "s",
+                ComputeBufferType::Uniform => "u",
+                ComputeBufferType::StorageReadOnly => "r",
+            })
+        });
+
+        if !self.pipelines.contains_key(&pipeline_key) {
+            let pipeline = self.create_pipeline(entry_point, &buffers);
+            self.pipelines.insert(pipeline_key.clone(), pipeline);
+        }
+        let pipeline = &self.pipelines[&pipeline_key];
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.bind_group_layout,
+            entries: buffers.iter().map(|compute_buffer| wgpu::BindGroupEntry {
+                binding: compute_buffer.binding,
+                resource: compute_buffer.buffer.clone(),
+            })
+            .collect::<Vec<_>>()
+            .as_slice(),
+            label: None,
+        });
+
+        let compute_kernel = ComputeKernel {
+            bind_group,
+            compute_pipeline: pipeline.compute_pipeline.clone(),
+        };
+
+        self.kernels.insert(
+            name.parse().unwrap(),
+            compute_kernel
+        );
+
+    }
+
+    fn create_pipeline(&self, entry_point: &str, buffers: &[ComputeBuffer]) -> CachedPipeline {
 
         let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: buffers.iter().map(|compute_buffer| BindGroupLayoutEntry {
@@ -54,17 +100,6 @@ impl ComputeShader {
             label: None,
         });
 
-        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: buffers.iter().map(|compute_buffer| wgpu::BindGroupEntry {
-                binding: compute_buffer.binding,
-                resource: compute_buffer.buffer.clone(),
-            })
-            .collect::<Vec<_>>()
-            .as_slice(),
-            label: None,
-        });
-
         let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some(&*(entry_point.to_owned() + " pipeline layout")),
             bind_group_layouts: &[&bind_group_layout],
@@ -78,20 +113,19 @@ impl ComputeShader {
             entry_point,
         });
 
-        let compute_kernel = ComputeKernel {
-            bind_group,
-            compute_pipeline
-        };
-
-        self.kernels.insert(
-            entry_point.parse().unwrap(),
-            compute_kernel
-        );
-
+        CachedPipeline {
+            bind_group_layout,
+            compute_pipeline: Arc::new(compute_pipeline),
+        }
     }
 }
 
+pub struct CachedPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    compute_pipeline: Arc<wgpu::ComputePipeline>,
+}
+
 pub struct ComputeKernel {
     pub bind_group: wgpu::BindGroup,
-    pub compute_pipeline: wgpu::ComputePipeline
+    pub compute_pipeline: Arc<wgpu::ComputePipeline>
 }
\ No newline at end of file
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use super::kernel_timer::TIMING_FEATURES;
+
+// 不创建窗口时的 target_format，只用于创建绘制管线
+const HEADLESS_TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8Unorm;
+
+// 不创建窗口时使用的 RenderState，没有可用的适配器时返回 None
+pub fn headless_render_state() -> Option<egui_wgpu::RenderState> {
+
+    // 与窗口模式使用相同的后端与电源偏好
+    let options = egui_wgpu::WgpuConfiguration::default();
+    let instance = wgpu::Instance::new(options.backends);
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: options.power_preference,
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }))?;
+
+    let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("Headless Device"),
+        features: adapter.features() & TIMING_FEATURES,
+        limits: adapter.limits(),
+    }, None)).ok()?;
+
+    let renderer = egui_wgpu::Renderer::new(&device, HEADLESS_TARGET_FORMAT, None, 1);
+
+    Some(egui_wgpu::RenderState {
+        device: Arc::new(device),
+        queue: Arc::new(queue),
+        target_format: HEADLESS_TARGET_FORMAT,
+        renderer: Arc::new(egui::mutex::RwLock::new(renderer)),
+    })
+}
@@ -0,0 +1,141 @@
+// Kernel 状态码表，CS_graph_solver.wgsl 中的状态码常量由 wgsl_constants 生成，作为 kernel_status.wgsl 被包含
+// 负数为警告，计算可以继续；正数为错误，由 recovery 决定如何自动恢复
+
+// 状态码在 Kernel Status Buffer 中的位置，对应写入该位置的 Kernel
+pub const KERNEL_STATUS_COUNT: usize = 6;
+pub const KERNEL_NAMES: [&str; KERNEL_STATUS_COUNT] = [
+    "attractive_force",
+    "tree_building",
+    "summarization",
+    "sort",
+    "electron_force",
+    "validate_nodes",
+];
+
+pub const STATUS_UNINITIALIZED: i32 = -1;
+pub const STATUS_RUNNING: i32 = 0;
+pub const STATUS_COINCIDENT_NODES: i32 = -3;
+pub const STATUS_LOOP_LIMIT_WARNING: i32 = -101;
+pub const STATUS_LOOP_LIMIT: i32 = 101;
+pub const STATUS_TREE_NODE_EXHAUSTED: i32 = 102;
+pub const STATUS_TREE_DEPTH_OVERFLOW: i32 = 103;
+pub const STATUS_NODES_QUARANTINED: i32 = -104;
+// 加上未完成的遍数 0 ~ 4
+pub const STATUS_SUMMARIZATION_STALL: i32 = 500;
+const SUMMARIZATION_PASS_COUNT: i32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KernelRecovery {
+    // 无需处理，Kernel 已自行处理或只影响当前帧
+    None,
+    // 将重合或过近的节点稍微分开
+    SpreadNodes,
+    // 扩大八叉树 Buffer 后重新创建资源
+    GrowTree,
+}
+
+pub struct KernelStatusCode {
+    pub code:           i32,
+    // WGSL 中的常量名
+    pub name:           &'static str,
+    pub title:          &'static str,
+    pub description:    &'static str,
+    pub recovery:       KernelRecovery,
+}
+
+pub const KERNEL_STATUS_CODES: [KernelStatusCode; 9] = [
+    KernelStatusCode {
+        code: STATUS_UNINITIALIZED,
+        name: "STATUS_UNINITIALIZED",
+        title: "Uninitialized",
+        description: "The kernel has not reported since the graph was created",
+        recovery: KernelRecovery::None,
+    },
+    KernelStatusCode {
+        code: STATUS_RUNNING,
+        name: "STATUS_RUNNING",
+        title: "Running",
+        description: "The kernel finished without problems",
+        recovery: KernelRecovery::None,
+    },
+    KernelStatusCode {
+        code: STATUS_COINCIDENT_NODES,
+        name: "STATUS_COINCIDENT_NODES",
+        title: "Coincident nodes",
+        description: "Some nodes had exactly the same position and were jittered apart while building the tree",
+        recovery: KernelRecovery::None,
+    },
+    KernelStatusCode {
+        code: STATUS_LOOP_LIMIT_WARNING,
+        name: "STATUS_LOOP_LIMIT_WARNING",
+        title: "Loop limit reached",
+        description: "The kernel stopped early, so a few nodes may be skipped in this frame",
+        recovery: KernelRecovery::None,
+    },
+    KernelStatusCode {
+        code: STATUS_LOOP_LIMIT,
+        name: "STATUS_LOOP_LIMIT",
+        title: "Loop limit exceeded",
+        description: "The kernel did not finish within its loop limit, usually because many nodes are too close to each other",
+        recovery: KernelRecovery::SpreadNodes,
+    },
+    KernelStatusCode {
+        code: STATUS_TREE_NODE_EXHAUSTED,
+        name: "STATUS_TREE_NODE_EXHAUSTED",
+        title: "Octree node exhaustion",
+        description: "Building the octree needed more cells than the tree buffer holds",
+        recovery: KernelRecovery::GrowTree,
+    },
+    KernelStatusCode {
+        code: STATUS_TREE_DEPTH_OVERFLOW,
+        name: "STATUS_TREE_DEPTH_OVERFLOW",
+        title: "Tree depth overflow",
+        description: "The octree is deeper than the traversal stack, so repulsion was skipped; nodes are nearly coincident",
+        recovery: KernelRecovery::SpreadNodes,
+    },
+    KernelStatusCode {
+        code: STATUS_NODES_QUARANTINED,
+        name: "STATUS_NODES_QUARANTINED",
+        title: "Nodes quarantined",
+        description: "Some node positions or forces became NaN or infinite and were restored from the previous step",
+        // 隔离的节点由隔离列表的回读处理，见 GraphicsResources::poll_node_quarantine
+        recovery: KernelRecovery::None,
+    },
+    KernelStatusCode {
+        code: STATUS_SUMMARIZATION_STALL,
+        name: "STATUS_SUMMARIZATION_STALL",
+        title: "Summarization stalled",
+        description: "Computing the centers of mass did not converge; the octree is likely incomplete",
+        recovery: KernelRecovery::SpreadNodes,
+    },
+];
+
+pub fn describe_kernel_status(code: i32) -> Option<&'static KernelStatusCode> {
+    let code = if (STATUS_SUMMARIZATION_STALL..STATUS_SUMMARIZATION_STALL + SUMMARIZATION_PASS_COUNT).contains(&code) {
+        STATUS_SUMMARIZATION_STALL
+    } else {
+        code
+    };
+    KERNEL_STATUS_CODES.iter().find(|status| status.code == code)
+}
+
+// 一行可读的说明，用于消息与 Kernel 面板
+pub fn format_kernel_status(kernel_index: usize, code: i32) -> String {
+    let kernel_name = KERNEL_NAMES.get(kernel_index).copied().unwrap_or("unknown");
+    match describe_kernel_status(code) {
+        Some(status) => format!("{} [{}] {}: {}", kernel_name, code, status.title, status.description),
+        None => format!("{} [{}] Unknown status code", kernel_name, code),
+    }
+}
+
+// 状态码与 Kernel 位置的 WGSL 常量
+pub fn wgsl_constants() -> String {
+    let mut source = String::from("// 由 kernel_status.rs 生成\n");
+    for status in &KERNEL_STATUS_CODES {
+        source += &format!("let {}: i32 = {};\n", status.name, status.code);
+    }
+    for (index, name) in KERNEL_NAMES.iter().enumerate() {
+        source += &format!("let KERNEL_{}: u32 = {}u;\n", name.to_uppercase(), index);
+    }
+    source
+}
@@ -0,0 +1,312 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use super::ReadbackRing;
+
+// 每个 Kernel 保留的计时样本数
+pub const TIMING_HISTORY_LENGTH: usize = 120;
+
+// 每批最多记录的 Timestamp 数，每次 dispatch 占两个
+const MAX_TIMESTAMP_COUNT: u32 = 512;
+const TIMING_READBACK_SLOT_COUNT: usize = 4;
+
+// Kernel 内部计时需要在 Pass 中写入 Timestamp
+pub const TIMING_FEATURES: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::WRITE_TIMESTAMP_INSIDE_PASSES);
+
+// 创建设备前检查所有适配器都支持的计时功能，避免请求不支持的 Feature 导致创建设备失败
+pub fn supported_timing_features(backends: wgpu::Backends) -> wgpu::Features {
+    let instance = wgpu::Instance::new(backends);
+    let mut adapters = instance.enumerate_adapters(backends).peekable();
+    if adapters.peek().is_none() { return wgpu::Features::empty(); }
+    let features = adapters.fold(TIMING_FEATURES, |features, adapter| features & adapter.features());
+    if features.contains(TIMING_FEATURES) { TIMING_FEATURES } else { wgpu::Features::empty() }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimingSource {
+    // GPU Timestamp Query，不影响计算
+    Timestamp,
+    // 适配器不支持 Timestamp 时，每个 Kernel 单独提交并等待完成，计时包含提交的开销
+    WallClock,
+}
+
+// 一批计时的分组，compute 每次迭代为一批，render 每帧为一批
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimingGroup {
+    Compute,
+    Render,
+}
+
+impl TimingGroup {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimingGroup::Compute => "Compute iteration",
+            TimingGroup::Render => "Render frame",
+        }
+    }
+}
+
+// 滚动的计时样本（毫秒）
+#[derive(Default)]
+pub struct TimingSeries {
+    pub samples: VecDeque<f32>,
+}
+
+impl TimingSeries {
+
+    fn push(&mut self, value: f32) {
+        if self.samples.len() >= TIMING_HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    pub fn min(&self) -> f32 {
+        self.samples.iter().copied().fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn max(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max)
+    }
+
+    pub fn average(&self) -> f32 {
+        if self.samples.is_empty() { return 0.0; }
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32
+    }
+
+    pub fn last(&self) -> f32 {
+        self.samples.back().copied().unwrap_or(0.0)
+    }
+}
+
+// 按首次出现的顺序保存每个 Kernel 与每个分组的计时
+#[derive(Default)]
+pub struct KernelTimings {
+    pub kernels:    Vec<(String, TimingGroup, TimingSeries)>,
+    pub groups:     Vec<(TimingGroup, TimingSeries)>,
+}
+
+impl KernelTimings {
+
+    // 同一批中多次 dispatch 的 Kernel 合计为一个样本
+    fn push_batch(&mut self, group: TimingGroup, durations: Vec<(String, f32)>) {
+
+        let mut totals: Vec<(String, f32)> = vec![];
+        for (label, duration) in durations {
+            match totals.iter_mut().find(|(name, _)| *name == label) {
+                Some((_, total)) => *total += duration,
+                None => totals.push((label, duration)),
+            }
+        }
+
+        let group_total = totals.iter().map(|(_, duration)| duration).sum();
+        for (label, duration) in totals {
+            match self.kernels.iter_mut().find(|(name, kernel_group, _)| *name == label && *kernel_group == group) {
+                Some((_, _, series)) => series.push(duration),
+                None => {
+                    let mut series = TimingSeries::default();
+                    series.push(duration);
+                    self.kernels.push((label, group, series));
+                }
+            }
+        }
+        self.push_group(group, group_total);
+    }
+
+    fn push_group(&mut self, group: TimingGroup, duration: f32) {
+        match self.groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, series)) => series.push(duration),
+            None => {
+                let mut series = TimingSeries::default();
+                series.push(duration);
+                self.groups.push((group, series));
+            }
+        }
+    }
+
+    pub fn group(&self, group: TimingGroup) -> Option<&TimingSeries> {
+        self.groups.iter().find(|(name, _)| *name == group).map(|(_, series)| series)
+    }
+
+    pub fn clear(&mut self) {
+        self.kernels.clear();
+        self.groups.clear();
+    }
+}
+
+// 正在记录的一批
+struct TimingBatch {
+    group:      TimingGroup,
+    labels:     Vec<String>,
+    // 按时钟计时时只记录 dispatch，由 replay 逐个提交
+    dispatches: Vec<(String, u32)>,
+}
+
+struct TimestampQueries {
+    query_set:      wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback:       ReadbackRing,
+    // 每个 Timestamp 刻度的纳秒数
+    period:         f32,
+    // 已提交、等待回读的批：（批号，分组，标签）
+    pending:        VecDeque<(u32, TimingGroup, Vec<String>)>,
+    batch_index:    u32,
+}
+
+// 记录 compute 与 render 中每次 dispatch 的耗时
+// dispatch_compute_kernel 只有 &self，正在记录的批放在 RefCell 中
+pub struct KernelTimer {
+    pub is_enabled:     bool,
+    pub source:         TimingSource,
+    queries:            Option<TimestampQueries>,
+    batch:              RefCell<Option<TimingBatch>>,
+    // 按时钟计时时，结束的 compute 批中待逐个提交的 dispatch
+    deferred:           Vec<(String, u32)>,
+    pub timings:        KernelTimings,
+}
+
+impl KernelTimer {
+
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+
+        let queries = device.features().contains(TIMING_FEATURES).then(|| {
+            let size = MAX_TIMESTAMP_COUNT as wgpu::BufferAddress * 8;
+            TimestampQueries {
+                query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("Kernel Timestamp Query Set"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: MAX_TIMESTAMP_COUNT,
+                }),
+                resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Kernel Timestamp Resolve Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                readback: ReadbackRing::new(device, size, TIMING_READBACK_SLOT_COUNT, "Kernel Timestamp Readback Buffer"),
+                period: queue.get_timestamp_period(),
+                pending: VecDeque::new(),
+                batch_index: 0,
+            }
+        });
+
+        Self {
+            is_enabled: false,
+            source: if queries.is_some() { TimingSource::Timestamp } else { TimingSource::WallClock },
+            queries,
+            batch: RefCell::new(None),
+            deferred: vec![],
+            timings: KernelTimings::default(),
+        }
+    }
+
+    pub fn begin_batch(&self, group: TimingGroup) {
+        if !self.is_enabled { return; }
+        *self.batch.borrow_mut() = Some(TimingBatch { group, labels: vec![], dispatches: vec![] });
+    }
+
+    // 按时钟计时且正在记录 compute 时，只记录 dispatch 而不写入 Pass，返回 true
+    pub fn defer_dispatch(&self, label: &str, work_group_count: u32) -> bool {
+        if self.source != TimingSource::WallClock { return false; }
+        match self.batch.borrow_mut().as_mut() {
+            Some(batch) if batch.group == TimingGroup::Compute => {
+                batch.dispatches.push((label.to_string(), work_group_count));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // 分配一对 Timestamp，返回第一个的序号，第二个为其后一个
+    pub fn begin_query(&self, label: &str) -> Option<(&wgpu::QuerySet, u32)> {
+        let queries = self.queries.as_ref()?;
+        let mut batch = self.batch.borrow_mut();
+        let batch = batch.as_mut()?;
+        let index = batch.labels.len() as u32 * 2;
+        if index + 2 > MAX_TIMESTAMP_COUNT { return None; }
+        batch.labels.push(label.to_string());
+        Some((&queries.query_set, index))
+    }
+
+    // 按时钟计时时取出上一批记录的 dispatch，由调用者逐个提交并计时
+    pub fn take_deferred(&mut self) -> Vec<(String, u32)> {
+        std::mem::take(&mut self.deferred)
+    }
+
+    pub fn push_wall_clock(&mut self, group: TimingGroup, durations: Vec<(String, f32)>) {
+        self.timings.push_batch(group, durations);
+    }
+
+    pub fn push_wall_clock_total(&mut self, group: TimingGroup, start: Instant) {
+        self.timings.push_group(group, start.elapsed().as_secs_f32() * 1000.0);
+    }
+
+    // 在提交前解析本批的 Timestamp 并复制到回读 Buffer，返回回读槽位，提交后须调用 request
+    pub fn end_batch(&mut self, command_encoder: &mut wgpu::CommandEncoder) -> Option<usize> {
+
+        let batch = self.batch.get_mut().take()?;
+        let queries = match self.queries.as_mut() {
+            Some(queries) => queries,
+            None => {
+                self.deferred = batch.dispatches;
+                return None;
+            }
+        };
+        if batch.labels.is_empty() { return None; }
+
+        let count = batch.labels.len() as u32 * 2;
+        command_encoder.resolve_query_set(&queries.query_set, 0..count, &queries.resolve_buffer, 0);
+
+        queries.batch_index = queries.batch_index.wrapping_add(1);
+        let slot = queries.readback.copy(command_encoder, &queries.resolve_buffer, queries.batch_index)?;
+        queries.pending.push_back((queries.batch_index, batch.group, batch.labels));
+        Some(slot)
+    }
+
+    pub fn request(&self, slot: Option<usize>) {
+        if let (Some(queries), Some(slot)) = (&self.queries, slot) {
+            queries.readback.request(slot);
+        }
+    }
+
+    // 取回已完成的 Timestamp，不阻塞
+    pub fn poll(&mut self, device: &wgpu::Device) {
+
+        let queries = match self.queries.as_mut() {
+            Some(queries) => queries,
+            None => return,
+        };
+
+        for (batch_index, data) in queries.readback.poll(device) {
+
+            // 回读失败的批不会返回，丢弃更早的批
+            while queries.pending.front().map_or(false, |(index, _, _)| *index != batch_index) {
+                queries.pending.pop_front();
+            }
+            let (_, group, labels) = match queries.pending.pop_front() {
+                Some(pending) => pending,
+                None => continue,
+            };
+
+            // 回读的字节不保证 8 字节对齐
+            let timestamps: Vec<u64> = data.chunks_exact(8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .collect();
+            let period = queries.period;
+            let durations = labels.into_iter().enumerate().map(|(index, label)| {
+                let ticks = timestamps[index * 2 + 1].saturating_sub(timestamps[index * 2]);
+                (label, ticks as f32 * period / 1_000_000.0)
+            }).collect();
+            self.timings.push_batch(group, durations);
+        }
+    }
+
+    pub fn set_enabled(&mut self, is_enabled: bool) {
+        self.is_enabled = is_enabled;
+        if !is_enabled {
+            *self.batch.get_mut() = None;
+            self.deferred.clear();
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+
+use bytemuck::{Pod, Zeroable};
+
+// 分块大小须为该值的整数倍，使分块内工作组的 256 分段与全局的 256 对齐分段一致
+pub const CHUNK_ALIGNMENT: u64 = 256;
+
+// 边排序 Buffer 的一个分块，须同步修改 CS_graph_solver.wgsl 中的 EdgeChunk
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Debug, PartialEq, Eq)]
+pub struct EdgeChunk {
+    pub offset: u32,
+    pub count:  u32,
+}
+
+// Buffer 的名称与大小，检查时为需要整体绑定的单个 Buffer，估算时为一类 Buffer 的合计
+pub struct BufferRequirement {
+    pub label: &'static str,
+    pub size:  u64,
+}
+
+// wgpu 不提供显存大小，默认预算取多数独立显卡与集成显卡都能满足的值
+pub const SUGGESTED_MEMORY_BUDGET: u64 = 2 * 1024 * 1024 * 1024;
+
+// 导入前估算的显存占用
+pub struct MemoryEstimate {
+    pub items: Vec<BufferRequirement>,
+}
+
+impl MemoryEstimate {
+    pub fn total(&self) -> u64 {
+        self.items.iter().map(|item| item.size).sum()
+    }
+}
+
+#[derive(Debug)]
+pub enum MemoryPlanError {
+    BufferTooLarge { label: &'static str, size: u64, limit: u64 },
+    TooManyWorkGroups { label: &'static str, count: u64, limit: u64 },
+    TooManyEdges { count: u64, limit: u64 },
+}
+
+impl fmt::Display for MemoryPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryPlanError::BufferTooLarge { label, size, limit } => write!(
+                f,
+                "{} needs {}, but the GPU allows at most {} per buffer binding",
+                label, format_bytes(*size), format_bytes(*limit),
+            ),
+            MemoryPlanError::TooManyWorkGroups { label, count, limit } => write!(
+                f,
+                "{} needs {} work groups, but the GPU allows at most {} per dispatch",
+                label, count, limit,
+            ),
+            MemoryPlanError::TooManyEdges { count, limit } => write!(
+                f,
+                "The graph has {} edges, but the GPU allows at most {}. \
+                Only the edge sort direction buffers are split into chunks, \
+                the edge buffer, the edge sort key and value buffers and the edge kernels are still bound as a whole",
+                count, limit,
+            ),
+        }
+    }
+}
+
+// 在分配 Buffer 之前按适配器的限制检查图数据能否放入 GPU
+// 边排序 Buffer 按绑定上限分块，其余 Buffer 须整体绑定，超出时拒绝导入
+pub struct MemoryPlanner {
+    max_binding_size:       u64,
+    max_work_group_count:   u64,
+}
+
+impl MemoryPlanner {
+
+    pub fn new(limits: &wgpu::Limits) -> Self {
+        Self {
+            max_binding_size: (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size),
+            max_work_group_count: limits.max_compute_workgroups_per_dimension as u64,
+        }
+    }
+
+    pub fn max_binding_size(&self) -> u64 {
+        self.max_binding_size
+    }
+
+    pub fn check_buffers(&self, buffers: &[BufferRequirement]) -> Result<(), MemoryPlanError> {
+        match buffers.iter().find(|buffer| buffer.size > self.max_binding_size) {
+            Some(buffer) => Err(MemoryPlanError::BufferTooLarge {
+                label: buffer.label,
+                size: buffer.size,
+                limit: self.max_binding_size,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // 整体绑定、每个线程处理一项时最多的项数
+    pub fn max_count(&self, element_size: u64, work_group_size: u64) -> u64 {
+        (self.max_binding_size / element_size).min(self.max_work_group_count * work_group_size)
+    }
+
+    // 一维 dispatch 的线程数
+    pub fn check_dispatch(&self, label: &'static str, thread_count: u64, work_group_size: u64) -> Result<(), MemoryPlanError> {
+        let count = thread_count.div_ceil(work_group_size);
+        if count > self.max_work_group_count {
+            return Err(MemoryPlanError::TooManyWorkGroups { label, count, limit: self.max_work_group_count });
+        }
+        Ok(())
+    }
+
+    // 按元素大小切分，每块不超过绑定上限和单次 dispatch 的线程数，且除最后一块外都是 CHUNK_ALIGNMENT 的整数倍
+    pub fn chunks(&self, label: &'static str, element_count: u64, element_size: u64) -> Result<Vec<EdgeChunk>, MemoryPlanError> {
+
+        let max_count = (self.max_binding_size / element_size)
+            .min(self.max_work_group_count * CHUNK_ALIGNMENT)
+            .min(u32::MAX as u64);
+        let chunk_size = max_count / CHUNK_ALIGNMENT * CHUNK_ALIGNMENT;
+
+        if chunk_size == 0 {
+            return Err(MemoryPlanError::BufferTooLarge {
+                label,
+                size: element_size * CHUNK_ALIGNMENT,
+                limit: self.max_binding_size,
+            });
+        }
+
+        // 空 Buffer 无法绑定，至少保留一块
+        let mut chunks = vec![];
+        let mut offset = 0;
+        loop {
+            let count = (element_count - offset).min(chunk_size);
+            chunks.push(EdgeChunk { offset: offset as u32, count: count as u32 });
+            offset += count;
+            if offset >= element_count { break; }
+        }
+
+        Ok(chunks)
+    }
+}
+
+pub fn format_bytes(size: u64) -> String {
+    let units = ["B", "KB", "MB", "GB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", size) } else { format!("{:.1} {}", value, units[unit]) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planner(max_binding_size: u32, max_work_group_count: u32) -> MemoryPlanner {
+        MemoryPlanner::new(&wgpu::Limits {
+            max_storage_buffer_binding_size: max_binding_size,
+            max_compute_workgroups_per_dimension: max_work_group_count,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn aligns_chunks_to_binding_limit() {
+        // 绑定上限可容纳 1000 个 16 byte 元素，向下对齐到 768
+        let chunks = planner(16_000, 65535).chunks("Edge Sort", 2000, 16).unwrap();
+        assert_eq!(chunks, vec![
+            EdgeChunk { offset: 0, count: 768 },
+            EdgeChunk { offset: 768, count: 768 },
+            EdgeChunk { offset: 1536, count: 464 },
+        ]);
+
+        // 恰好为对齐的整数倍时没有多余的空块
+        let chunks = planner(16 * 512, 65535).chunks("Edge Sort", 1024, 16).unwrap();
+        assert_eq!(chunks, vec![EdgeChunk { offset: 0, count: 512 }, EdgeChunk { offset: 512, count: 512 }]);
+
+        // 空 Buffer 也保留一块
+        assert_eq!(planner(16_000, 65535).chunks("Edge Sort", 0, 16).unwrap(), vec![EdgeChunk { offset: 0, count: 0 }]);
+    }
+
+    #[test]
+    fn limits_chunks_by_work_groups() {
+        // 每块的线程数不超过 2 个工作组
+        let chunks = planner(1 << 20, 2).chunks("Edge Sort", 1200, 16).unwrap();
+        assert_eq!(chunks.iter().map(|chunk| chunk.count).collect::<Vec<_>>(), vec![512, 512, 176]);
+        assert_eq!(chunks.iter().map(|chunk| chunk.offset).collect::<Vec<_>>(), vec![0, 512, 1024]);
+    }
+
+    #[test]
+    fn rejects_elements_larger_than_binding() {
+        // 一个对齐块都放不下时拒绝
+        let error = planner(255 * 16, 65535).chunks("Edge Sort", 10, 16).unwrap_err();
+        assert!(matches!(error, MemoryPlanError::BufferTooLarge { size: 4096, limit: 4080, .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn checks_buffers_and_dispatches() {
+        let planner = planner(1024, 4);
+        let buffers = [
+            BufferRequirement { label: "Node", size: 1024 },
+            BufferRequirement { label: "Tree", size: 1025 },
+        ];
+        assert!(matches!(planner.check_buffers(&buffers[..1]), Ok(())));
+        assert!(matches!(planner.check_buffers(&buffers), Err(MemoryPlanError::BufferTooLarge { label: "Tree", .. })));
+
+        assert!(planner.check_dispatch("Node", 1024, 256).is_ok());
+        assert!(matches!(planner.check_dispatch("Node", 1025, 256), Err(MemoryPlanError::TooManyWorkGroups { count: 5, limit: 4, .. })));
+
+        // 受绑定上限与工作组数中较小的一个限制
+        assert_eq!(planner.max_count(8, 256), 128);
+        assert_eq!(planner.max_count(1, 16), 64);
+    }
+}
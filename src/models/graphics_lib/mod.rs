@@ -6,6 +6,14 @@ pub mod buffer_dimensions;
 pub mod bind_group_layout;
 pub mod compute_shader;
 pub mod unifrom;
+pub mod readback;
+pub mod radix_sort;
+pub mod oit;
+pub mod memory_planner;
+pub mod kernel_timer;
+pub mod kernel_status;
+pub mod headless;
+pub mod shader_preprocessor;
 
 pub use {
     camera::Camera,
@@ -13,4 +21,9 @@ pub use {
     camera_controls::Controls,
     render_pipeline::RenderPipeline,
     buffer_dimensions::BufferDimensions,
+    readback::ReadbackRing,
+    oit::OitTargets,
+    memory_planner::MemoryPlanner,
+    kernel_timer::KernelTimer,
+    headless::headless_render_state,
 };
\ No newline at end of file
@@ -0,0 +1,71 @@
+use crate::constant::{OIT_ACCUM_FORMAT, OIT_REVEAL_FORMAT};
+
+use super::Texture;
+
+// 加权混合顺序无关透明（Weighted Blended OIT）的渲染目标
+// 累积与透射率先以多重采样绘制并 resolve，再由合成 Pass 读取
+pub struct OitTargets {
+    pub extent:             wgpu::Extent3d,
+    pub accum_msaa:         Texture,
+    pub reveal_msaa:        Texture,
+    pub accum:              Texture,
+    pub reveal:             Texture,
+    pub composite_bind_group: wgpu::BindGroup,
+}
+
+impl OitTargets {
+
+    pub fn new(device: &wgpu::Device, extent: wgpu::Extent3d, composite_bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+
+        let accum_msaa = Texture::create_texture_with_format(device, &extent, 4, OIT_ACCUM_FORMAT);
+        let reveal_msaa = Texture::create_texture_with_format(device, &extent, 4, OIT_REVEAL_FORMAT);
+        let accum = Texture::create_texture_with_format(device, &extent, 1, OIT_ACCUM_FORMAT);
+        let reveal = Texture::create_texture_with_format(device, &extent, 1, OIT_REVEAL_FORMAT);
+
+        let composite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: composite_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&accum.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&reveal.view),
+                },
+            ],
+            label: Some("OIT Composite Bind Group"),
+        });
+
+        Self {
+            extent,
+            accum_msaa,
+            reveal_msaa,
+            accum,
+            reveal,
+            composite_bind_group,
+        }
+    }
+
+    // 累积清为 0，透射率清为 1
+    pub fn color_attachments(&self) -> [Option<wgpu::RenderPassColorAttachment<'_>>; 2] {
+        [
+            Some(wgpu::RenderPassColorAttachment {
+                view: &self.accum_msaa.view,
+                resolve_target: Some(&self.accum.view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: false,
+                },
+            }),
+            Some(wgpu::RenderPassColorAttachment {
+                view: &self.reveal_msaa.view,
+                resolve_target: Some(&self.reveal.view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    store: false,
+                },
+            }),
+        ]
+    }
+}
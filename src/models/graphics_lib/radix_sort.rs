@@ -0,0 +1,390 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use super::compute_shader::{ComputeBuffer, ComputeBufferType, ComputeShader};
+use super::shader_preprocessor::{PreprocessedShader, ShaderPreprocessor};
+
+// RADIX_SIZE、RADIX_MASK 与 BLOCK_SIZE 由 ShaderPreprocessor 定义为 CS_radix_sort.wgsl 中的同名宏
+pub const RADIX_BITS: u32 = 4;
+const RADIX_SIZE: usize = 1 << RADIX_BITS;
+const RADIX_MASK: u32 = RADIX_SIZE as u32 - 1;
+const BLOCK_SIZE: usize = 256;
+
+// 第 i 趟从 Buffer i % 2 读取，写入 Buffer (i + 1) % 2
+const HISTOGRAM_KERNELS: [&str; 2] = ["radix_histogram", "radix_histogram_swapped"];
+const SCATTER_KERNELS: [&str; 2] = ["radix_scatter", "radix_scatter_swapped"];
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct RadixParam {
+    count:       u32,
+    shift:       u32,
+    block_count: u32,
+    _empty:      u32,
+}
+
+// GPU 基数排序，对 u32 键值对做稳定的升序排序
+// 由准备 Kernel 写入 key_buffer / value_buffer，encode 记录全部排序趟数后一次提交，结果在 sorted_*_buffer 中
+pub struct RadixSort {
+    compute_shader:     ComputeShader,
+    key_buffers:        [wgpu::Buffer; 2],
+    value_buffers:      [wgpu::Buffer; 2],
+    histogram_buffer:   wgpu::Buffer,
+    param_buffer:       wgpu::Buffer,
+    // 每一趟的参数，逐趟复制到 param_buffer，使所有趟可以在同一个 command encoder 中完成
+    pass_param_buffer:  wgpu::Buffer,
+    count:              u32,
+    // Buffer 可容纳的键值数，set_count 不能超过
+    capacity:           u32,
+    pass_count:         u32,
+    // 块数超过单个维度的工作组上限时按二维 dispatch
+    dispatch_size:      (u32, u32),
+    max_work_group_count: u32,
+}
+
+impl RadixSort {
+
+    // key_bits 为键的有效位数，决定排序的趟数
+    pub fn new(device: &Arc<wgpu::Device>, count: u32, key_bits: u32, label: &str) -> Self {
+
+        let shader_source = radix_sort_shader().unwrap_or_else(|error| panic!("{}", error));
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(shader_source.source)),
+        });
+
+        let block_count = (count as usize + BLOCK_SIZE - 1) / BLOCK_SIZE;
+        let pass_count = pass_count(key_bits);
+        let max_work_group_count = device.limits().max_compute_workgroups_per_dimension;
+        let dispatch_size = dispatch_size(block_count as u32, max_work_group_count);
+
+        // 空 Buffer 无法绑定，至少保留一个元素
+        let create_buffer = |name: &str, size: usize, usage: wgpu::BufferUsages| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} {}", label, name)),
+            size: (size.max(1) * 4) as wgpu::BufferAddress,
+            usage,
+            mapped_at_creation: false,
+        });
+
+        let key_value_usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+        let key_buffers = [
+            create_buffer("Key Buffer", count as usize, key_value_usage),
+            create_buffer("Key Buffer Swapped", count as usize, key_value_usage),
+        ];
+        let value_buffers = [
+            create_buffer("Value Buffer", count as usize, key_value_usage),
+            create_buffer("Value Buffer Swapped", count as usize, key_value_usage),
+        ];
+        let histogram_buffer = create_buffer("Histogram Buffer", RADIX_SIZE * block_count, wgpu::BufferUsages::STORAGE);
+        let param_buffer = create_buffer("Param Buffer", 4, wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+
+        let pass_param_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Pass Param Buffer", label)),
+            contents: bytemuck::cast_slice(&pass_params(count, pass_count)),
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let mut compute_shader = ComputeShader {
+            shader,
+            device: device.clone(),
+            kernels: Default::default(),
+            pipelines: Default::default(),
+        };
+
+        for direction in 0..2 {
+            let (src, dst) = (direction, 1 - direction);
+
+            compute_shader.create_named_compute_kernel(HISTOGRAM_KERNELS[direction], "radix_histogram", vec![
+                ComputeBuffer {
+                    binding: 0,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: param_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 1,
+                    buffer_type: ComputeBufferType::StorageReadOnly,
+                    buffer: key_buffers[src].as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 5,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: histogram_buffer.as_entire_binding(),
+                },
+            ]);
+
+            compute_shader.create_named_compute_kernel(SCATTER_KERNELS[direction], "radix_scatter", vec![
+                ComputeBuffer {
+                    binding: 0,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: param_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 1,
+                    buffer_type: ComputeBufferType::StorageReadOnly,
+                    buffer: key_buffers[src].as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 2,
+                    buffer_type: ComputeBufferType::StorageReadOnly,
+                    buffer: value_buffers[src].as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 3,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: key_buffers[dst].as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 4,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: value_buffers[dst].as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 5,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: histogram_buffer.as_entire_binding(),
+                },
+            ]);
+        }
+
+        compute_shader.create_compute_kernel("radix_scan", vec![
+            ComputeBuffer {
+                binding: 0,
+                buffer_type: ComputeBufferType::Uniform,
+                buffer: param_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 5,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: histogram_buffer.as_entire_binding(),
+            },
+        ]);
+
+        Self {
+            compute_shader,
+            key_buffers,
+            value_buffers,
+            histogram_buffer,
+            param_buffer,
+            pass_param_buffer,
+            count,
+            capacity: count,
+            pass_count,
+            dispatch_size,
+            max_work_group_count,
+        }
+    }
+
+    // 只排序前 count 个键值，用于容量有余量的 Buffer
+    pub fn set_count(&mut self, queue: &wgpu::Queue, count: u32) {
+        let count = count.min(self.capacity);
+        let block_count = (count + BLOCK_SIZE as u32 - 1) / BLOCK_SIZE as u32;
+        queue.write_buffer(&self.pass_param_buffer, 0, bytemuck::cast_slice(&pass_params(count, self.pass_count)));
+        self.count = count;
+        self.dispatch_size = dispatch_size(block_count, self.max_work_group_count);
+    }
+
+    // 待排序的键值，由调用方的 Kernel 写入
+    pub fn key_buffer(&self) -> &wgpu::Buffer {
+        &self.key_buffers[0]
+    }
+
+    pub fn value_buffer(&self) -> &wgpu::Buffer {
+        &self.value_buffers[0]
+    }
+
+    // 排序结果，趟数为奇数时位于交换后的 Buffer
+    pub fn sorted_key_buffer(&self) -> &wgpu::Buffer {
+        &self.key_buffers[self.pass_count as usize % 2]
+    }
+
+    pub fn sorted_value_buffer(&self) -> &wgpu::Buffer {
+        &self.value_buffers[self.pass_count as usize % 2]
+    }
+
+    // 在 command encoder 中记录全部排序趟数
+    pub fn encode(&self, command_encoder: &mut wgpu::CommandEncoder) {
+
+        if self.count == 0 { return; }
+
+        let param_size = std::mem::size_of::<RadixParam>() as wgpu::BufferAddress;
+
+        command_encoder.push_debug_group("radix_sort");
+        for pass in 0..self.pass_count {
+            command_encoder.copy_buffer_to_buffer(&self.pass_param_buffer, pass as wgpu::BufferAddress * param_size, &self.param_buffer, 0, param_size);
+
+            let direction = pass as usize % 2;
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            self.dispatch(&mut cpass, HISTOGRAM_KERNELS[direction], self.dispatch_size);
+            self.dispatch(&mut cpass, "radix_scan", (1, 1));
+            self.dispatch(&mut cpass, SCATTER_KERNELS[direction], self.dispatch_size);
+        }
+        command_encoder.pop_debug_group();
+    }
+
+    fn dispatch<'a>(&'a self, cpass: &mut wgpu::ComputePass<'a>, kernel_name: &str, work_group_count: (u32, u32)) {
+        let kernel = self.compute_shader.kernels.get(kernel_name).unwrap();
+        cpass.set_pipeline(&kernel.compute_pipeline);
+        cpass.set_bind_group(0, &kernel.bind_group, &[]);
+        cpass.dispatch_workgroups(work_group_count.0, work_group_count.1, 1);
+    }
+
+    pub fn destroy(&self) {
+        for buffer in self.key_buffers.iter().chain(&self.value_buffers) {
+            buffer.destroy();
+        }
+        self.histogram_buffer.destroy();
+        self.param_buffer.destroy();
+        self.pass_param_buffer.destroy();
+    }
+}
+
+fn pass_params(count: u32, pass_count: u32) -> Vec<RadixParam> {
+    let block_count = (count + BLOCK_SIZE as u32 - 1) / BLOCK_SIZE as u32;
+    (0..pass_count).map(|pass| RadixParam {
+        count,
+        shift: pass * RADIX_BITS,
+        block_count,
+        _empty: 0,
+    }).collect()
+}
+
+// 多出的工作组由 Kernel 按块数跳过
+fn dispatch_size(block_count: u32, max_work_group_count: u32) -> (u32, u32) {
+    let x = block_count.clamp(1, max_work_group_count);
+    (x, (block_count + x - 1) / x)
+}
+
+pub fn radix_sort_shader() -> Result<PreprocessedShader, String> {
+    ShaderPreprocessor::default()
+        .define_u32("RADIX_SIZE", RADIX_SIZE as u32)
+        .define_u32("RADIX_MASK", RADIX_MASK)
+        .define_u32("BLOCK_SIZE", BLOCK_SIZE as u32)
+        .process("CS_radix_sort.wgsl")
+}
+
+fn pass_count(key_bits: u32) -> u32 {
+    (key_bits.clamp(1, 32) + RADIX_BITS - 1) / RADIX_BITS
+}
+
+// 有效位数，用于节点序号等上界已知的键
+pub fn key_bits(max_key: u32) -> u32 {
+    32 - max_key.leading_zeros()
+}
+
+// 浮点数映射为保持大小顺序的 u32，须同步修改 CS_graph_solver.wgsl 中的 float_sort_key
+pub fn float_sort_key(value: f32) -> u32 {
+    let bits = value.to_bits();
+    if bits & 0x8000_0000 != 0 { !bits } else { bits | 0x8000_0000 }
+}
+
+// 与 GPU 相同步骤的 CPU 参考实现：逐块统计、前缀和、块内按序分发
+pub fn radix_sort(keys: &mut Vec<u32>, values: &mut Vec<u32>, key_bits: u32) {
+
+    let count = keys.len();
+    let block_count = (count + BLOCK_SIZE - 1) / BLOCK_SIZE;
+    let mut key_dst = vec![0; count];
+    let mut value_dst = vec![0; count];
+
+    for pass in 0..pass_count(key_bits) {
+        let shift = pass * RADIX_BITS;
+        let digit = |key: u32| ((key >> shift) & RADIX_MASK) as usize;
+
+        let mut histogram = vec![0usize; RADIX_SIZE * block_count];
+        for (index, &key) in keys.iter().enumerate() {
+            histogram[digit(key) * block_count + index / BLOCK_SIZE] += 1;
+        }
+
+        let mut prefix = 0;
+        for offset in histogram.iter_mut() {
+            let count = *offset;
+            *offset = prefix;
+            prefix += count;
+        }
+
+        // 按块内顺序依次分发，等价于 GPU 中统计块内排在前面的相同位值
+        for (index, &key) in keys.iter().enumerate() {
+            let offset = &mut histogram[digit(key) * block_count + index / BLOCK_SIZE];
+            key_dst[*offset] = key;
+            value_dst[*offset] = values[index];
+            *offset += 1;
+        }
+
+        std::mem::swap(keys, &mut key_dst);
+        std::mem::swap(values, &mut value_dst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_keys(count: usize, seed: u64, mask: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..count).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u32 & mask
+        }).collect()
+    }
+
+    fn check(keys: Vec<u32>, key_bits: u32) {
+        let mut values: Vec<u32> = (0..keys.len() as u32).collect();
+        let mut expected: Vec<(u32, u32)> = keys.iter().copied().zip(values.iter().copied()).collect();
+        expected.sort_by_key(|&(key, _)| key);
+
+        let mut keys = keys;
+        radix_sort(&mut keys, &mut values, key_bits);
+
+        let result: Vec<(u32, u32)> = keys.into_iter().zip(values).collect();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn sorts_full_keys() {
+        for count in [0, 1, 255, 256, 257, 1000, 100_000] {
+            check(random_keys(count, count as u64 + 1, u32::MAX), 32);
+        }
+    }
+
+    #[test]
+    fn stable_with_duplicate_keys() {
+        check(random_keys(10_000, 7, 0xf), 32);
+        check(vec![3; 1000], 32);
+    }
+
+    #[test]
+    fn sorts_with_partial_key_bits() {
+        for max_key in [1, 15, 16, 1000, 123_456] {
+            let keys = random_keys(5000, max_key as u64, u32::MAX).into_iter().map(|key| key % (max_key + 1)).collect();
+            check(keys, key_bits(max_key));
+        }
+    }
+
+    #[test]
+    fn float_sort_key_preserves_order() {
+        let mut values = vec![f32::NEG_INFINITY, -1e30, -2.5, -1.0, -1e-30, -0.0, 0.0, 1e-30, 0.5, 1.0, 3.0e20, f32::INFINITY];
+        let keys: Vec<u32> = values.iter().map(|&value| float_sort_key(value)).collect();
+        assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        values.reverse();
+        let mut indices: Vec<u32> = (0..values.len() as u32).collect();
+        let mut keys: Vec<u32> = values.iter().map(|&value| float_sort_key(value)).collect();
+        radix_sort(&mut keys, &mut indices, 32);
+        let sorted: Vec<f32> = indices.iter().map(|&index| values[index as usize]).collect();
+        assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn dispatch_size_covers_all_blocks() {
+        for block_count in [1, 255, 65535, 65536, 131_073, 1_000_000] {
+            let (x, y) = dispatch_size(block_count, 65535);
+            assert!(x <= 65535 && y <= 65535);
+            assert!(x * y >= block_count && x * (y - 1) < block_count);
+        }
+    }
+}
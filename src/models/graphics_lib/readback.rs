@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+// 暂存 Buffer 的状态
+const SLOT_IDLE: u8 = 0;
+const SLOT_PENDING: u8 = 1;
+const SLOT_READY: u8 = 2;
+const SLOT_FAILED: u8 = 3;
+
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    state: Arc<AtomicU8>,
+    // 发起读取时的计算帧号
+    frame: u32,
+}
+
+// 异步回读的暂存 Buffer 环
+// 每次提交时复制到一个空闲的暂存 Buffer 并 map_async，之后每帧以 Maintain::Poll 轮询，结果在若干帧后取回，不阻塞 UI 线程
+// 所有暂存 Buffer 都在等待时跳过本次回读
+pub struct ReadbackRing {
+    slots: Vec<ReadbackSlot>,
+    size: wgpu::BufferAddress,
+    next: usize,
+}
+
+impl ReadbackRing {
+
+    pub fn new(device: &wgpu::Device, size: wgpu::BufferAddress, slot_count: usize, label: &str) -> Self {
+
+        let slots = (0..slot_count.max(1)).map(|_| ReadbackSlot {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            state: Arc::new(AtomicU8::new(SLOT_IDLE)),
+            frame: 0,
+        }).collect();
+
+        Self {
+            slots,
+            size,
+            next: 0,
+        }
+    }
+
+    // 在 command encoder 中记录复制命令，返回使用的暂存 Buffer 序号；没有空闲的暂存 Buffer 时返回 None
+    // 提交 command encoder 之后须调用 request 发起读取
+    pub fn copy(&mut self, command_encoder: &mut wgpu::CommandEncoder, source: &wgpu::Buffer, frame: u32) -> Option<usize> {
+
+        let index = self.next;
+        let slot = &mut self.slots[index];
+        if slot.state.load(Ordering::Acquire) != SLOT_IDLE { return None; }
+
+        command_encoder.copy_buffer_to_buffer(source, 0, &slot.buffer, 0, self.size);
+        slot.frame = frame;
+        slot.state.store(SLOT_PENDING, Ordering::Release);
+        self.next = (self.next + 1) % self.slots.len();
+
+        Some(index)
+    }
+
+    pub fn request(&self, index: usize) {
+        let state = self.slots[index].state.clone();
+        self.slots[index].buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            state.store(if result.is_ok() { SLOT_READY } else { SLOT_FAILED }, Ordering::Release);
+        });
+    }
+
+    // 非阻塞地轮询，按发起顺序取回已完成的结果（计算帧号，数据）
+    pub fn poll(&mut self, device: &wgpu::Device) -> Vec<(u32, Vec<u8>)> {
+
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut results = vec![];
+        let count = self.slots.len();
+
+        // self.next 指向最早发起的暂存 Buffer
+        for offset in 0..count {
+            let slot = &self.slots[(self.next + offset) % count];
+            match slot.state.load(Ordering::Acquire) {
+                SLOT_READY => {
+                    let data = slot.buffer.slice(..).get_mapped_range().to_vec();
+                    slot.buffer.unmap();
+                    results.push((slot.frame, data));
+                    slot.state.store(SLOT_IDLE, Ordering::Release);
+                }
+                SLOT_FAILED => {
+                    slot.state.store(SLOT_IDLE, Ordering::Release);
+                }
+                _ => {}
+            }
+        }
+
+        results.sort_by_key(|(frame, _)| *frame);
+        results
+    }
+}
@@ -1,4 +1,4 @@
-use crate::constant::{CAST_TEXTURE_FORMAT, DEPTH_FORMAT, MULTISAMPLE_STATE, TEXTURE_FORMAT};
+use crate::constant::{CAST_TEXTURE_FORMAT, DEPTH_FORMAT, MULTISAMPLE_STATE, OIT_ACCUM_FORMAT, OIT_REVEAL_FORMAT, TEXTURE_FORMAT};
 
 pub struct RenderPipeline {
     pub render_pipeline: wgpu::RenderPipeline,
@@ -136,7 +136,8 @@ impl RenderPipeline {
 
     }
 
-    pub fn create_axis_render_pipeline( device: &wgpu::Device, layouts: &[&wgpu::BindGroupLayout], axis_shader: &wgpu::ShaderModule ) -> Self {
+    // is_graticule 为 true 时绘制 Geographic 布局的经纬网
+    pub fn create_axis_render_pipeline( device: &wgpu::Device, layouts: &[&wgpu::BindGroupLayout], axis_shader: &wgpu::ShaderModule, is_graticule: bool ) -> Self {
 
         let axis_render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("axis render"),
@@ -149,7 +150,7 @@ impl RenderPipeline {
             layout: Some(&axis_render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &axis_shader,
-                entry_point: "main_vs",
+                entry_point: if !is_graticule { "main_vs" } else { "graticule_vs" },
                 buffers: &[
                     wgpu::VertexBufferLayout {
                         array_stride: 2 * 4,
@@ -160,7 +161,7 @@ impl RenderPipeline {
             },
             fragment: Some(wgpu::FragmentState {
                 module: &axis_shader,
-                entry_point: "main_fs",
+                entry_point: if !is_graticule { "main_fs" } else { "graticule_fs" },
                 targets: &[Some(wgpu::ColorTargetState {
                     format: TEXTURE_FORMAT,
                     blend: Some(wgpu::BlendState {
@@ -186,7 +187,7 @@ impl RenderPipeline {
             },
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: DEPTH_FORMAT,
-                depth_write_enabled: true,
+                depth_write_enabled: !is_graticule,
                 depth_compare: wgpu::CompareFunction::Less, // 1.
                 stencil: wgpu::StencilState::default(), // 2.
                 bias: wgpu::DepthBiasState::default(),
@@ -268,4 +269,147 @@ impl RenderPipeline {
 
     }
 
+    // 加权混合顺序无关透明的累积管线，节点与边共用，入口为 main_vs 与 oit_fs
+    // 累积目标按权重相加，透射率目标乘以 1 - alpha，只做深度测试不写入深度
+    pub fn create_oit_render_pipeline( device: &wgpu::Device, layouts: &[&wgpu::BindGroupLayout], shader: &wgpu::ShaderModule, vertex_entry_point: &str ) -> Self {
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("oit render"),
+            bind_group_layouts: layouts,
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: vertex_entry_point,
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 2 * 4,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "oit_fs",
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: OIT_ACCUM_FORMAT,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: wgpu::BlendFactor::One,
+                                dst_factor: wgpu::BlendFactor::One,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent {
+                                src_factor: wgpu::BlendFactor::One,
+                                dst_factor: wgpu::BlendFactor::One,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: OIT_REVEAL_FORMAT,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: wgpu::BlendFactor::Zero,
+                                dst_factor: wgpu::BlendFactor::OneMinusSrc,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent {
+                                src_factor: wgpu::BlendFactor::Zero,
+                                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                front_face: wgpu::FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: MULTISAMPLE_STATE,
+            multiview: None,
+        });
+
+        Self {
+            render_pipeline,
+        }
+
+    }
+
+    // 将累积结果按透射率合成到颜色目标
+    pub fn create_oit_composite_render_pipeline( device: &wgpu::Device, layouts: &[&wgpu::BindGroupLayout], composite_shader: &wgpu::ShaderModule ) -> Self {
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("oit composite"),
+            bind_group_layouts: layouts,
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &composite_shader,
+                entry_point: "main_vs",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 2 * 4,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &composite_shader,
+                entry_point: "main_fs",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: TEXTURE_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                front_face: wgpu::FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MULTISAMPLE_STATE,
+            multiview: None,
+        });
+
+        Self {
+            render_pipeline,
+        }
+
+    }
+
 }
\ No newline at end of file
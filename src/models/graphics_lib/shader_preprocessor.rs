@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use naga::valid::{Capabilities, ValidationFlags};
+use naga::SourceLocation;
+
+// 内置的 Shader 文件，#include 与 process 按文件名查找
+const SHADER_FILES: [(&str, &str); 10] = [
+    ("common.wgsl", include_str!("../../assets/shaders/common.wgsl")),
+    ("oit.wgsl", include_str!("../../assets/shaders/oit.wgsl")),
+    ("solver.wgsl", include_str!("../../assets/shaders/solver.wgsl")),
+    ("CS_graph_solver.wgsl", include_str!("../../assets/shaders/CS_graph_solver.wgsl")),
+    ("CS_radix_sort.wgsl", include_str!("../../assets/shaders/CS_radix_sort.wgsl")),
+    ("S_axis.wgsl", include_str!("../../assets/shaders/S_axis.wgsl")),
+    ("S_bounding_box.wgsl", include_str!("../../assets/shaders/S_bounding_box.wgsl")),
+    ("S_edge.wgsl", include_str!("../../assets/shaders/S_edge.wgsl")),
+    ("S_node.wgsl", include_str!("../../assets/shaders/S_node.wgsl")),
+    ("S_oit_composite.wgsl", include_str!("../../assets/shaders/S_oit_composite.wgsl")),
+];
+
+// 预处理后的 Shader 源码
+pub struct PreprocessedShader {
+    pub name:       String,
+    pub source:     String,
+    // 输出的每一行来自的文件与行号（从 1 开始），用于报告错误的位置
+    pub line_map:   Vec<(String, usize)>,
+}
+
+impl PreprocessedShader {
+    // line 为输出源码中从 1 开始的行号
+    pub fn location(&self, line: usize) -> Option<(&str, usize)> {
+        let (file, line) = self.line_map.get(line.checked_sub(1)?)?;
+        Some((file.as_str(), *line))
+    }
+
+    // 用 naga 解析并验证预处理后的源码，错误信息包含原始的文件与行号以及 codespan 的诊断
+    pub fn validate(&self) -> Result<naga::Module, String> {
+        let module = naga::front::wgsl::parse_str(&self.source).map_err(|error| {
+            self.error_message(error.location(&self.source), error.emit_to_string_with_path(&self.source, &self.name))
+        })?;
+        naga::valid::Validator::new(ValidationFlags::all(), Capabilities::empty())
+            .validate(&module)
+            .map_err(|error| {
+                // 第一个 span 通常是整个函数，最后一个才是出错的表达式
+                let location = error.spans().last().map(|(span, _)| span.location(&self.source));
+                self.error_message(location, error.emit_to_string_with_path(&self.source, &self.name))
+            })?;
+        Ok(module)
+    }
+
+    fn error_message(&self, location: Option<SourceLocation>, diagnostic: String) -> String {
+        let file_line = location
+            .and_then(|location| self.location(location.line_number as usize))
+            .map_or_else(|| self.name.clone(), |(file, line)| format!("{}:{}", file, line));
+        format!("{}\n{}", file_line, diagnostic)
+    }
+}
+
+// WGSL 的预处理器，支持 #include "file"、#define NAME [value]、#undef、#ifdef、#ifndef、#else、#endif
+// 宏只做标识符的整体替换，不支持参数；每个文件只被包含一次，公共的 struct 因此可以被多处包含
+#[derive(Default)]
+pub struct ShaderPreprocessor {
+    defines:    BTreeMap<String, String>,
+    // 由 Rust 生成的源码，与内置文件一样可以被 #include
+    generated:  BTreeMap<String, String>,
+}
+
+struct Conditional {
+    is_true:        bool,
+    has_else:       bool,
+}
+
+struct State {
+    defines:    BTreeMap<String, String>,
+    included:   BTreeSet<String>,
+    source:     String,
+    line_map:   Vec<(String, usize)>,
+}
+
+impl ShaderPreprocessor {
+
+    pub fn define(mut self, name: &str, value: impl ToString) -> Self {
+        self.defines.insert(String::from(name), value.to_string());
+        self
+    }
+
+    // u32 常量，在 WGSL 中带 u 后缀
+    pub fn define_u32(self, name: &str, value: u32) -> Self {
+        self.define(name, format!("{}u", value))
+    }
+
+    // 开关，只用于 #ifdef
+    pub fn flag(self, name: &str, is_enabled: bool) -> Self {
+        if is_enabled { self.define(name, "") } else { self }
+    }
+
+    pub fn generated(mut self, name: &str, source: String) -> Self {
+        self.generated.insert(String::from(name), source);
+        self
+    }
+
+    pub fn process(&self, name: &str) -> Result<PreprocessedShader, String> {
+        let mut state = State {
+            defines: self.defines.clone(),
+            included: BTreeSet::new(),
+            source: String::new(),
+            line_map: vec![],
+        };
+        self.process_file(name, &mut state)?;
+        Ok(PreprocessedShader { name: String::from(name), source: state.source, line_map: state.line_map })
+    }
+
+    fn file_source(&self, name: &str) -> Option<&str> {
+        self.generated.get(name).map(String::as_str)
+            .or_else(|| SHADER_FILES.iter().find(|(file_name, _)| *file_name == name).map(|(_, source)| *source))
+    }
+
+    fn process_file(&self, name: &str, state: &mut State) -> Result<(), String> {
+
+        let source = self.file_source(name).ok_or_else(|| format!("Shader file '{}' not found", name))?;
+        if !state.included.insert(String::from(name)) {
+            return Ok(());
+        }
+
+        let mut conditionals: Vec<Conditional> = vec![];
+        // 所有外层条件都成立时输出
+        let is_active = |conditionals: &Vec<Conditional>| conditionals.iter().all(|conditional| conditional.is_true);
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let error = |message: &str| format!("{}:{}: {}", name, line_number, message);
+
+            let directive = match line.trim_start().strip_prefix('#') {
+                Some(directive) => directive.trim(),
+                None => {
+                    if is_active(&conditionals) {
+                        state.source += &substitute(line, &state.defines);
+                        state.source.push('\n');
+                        state.line_map.push((String::from(name), line_number));
+                    }
+                    continue;
+                }
+            };
+
+            let (keyword, argument) = match directive.split_once(char::is_whitespace) {
+                Some((keyword, argument)) => (keyword, argument.trim()),
+                None => (directive, ""),
+            };
+            match keyword {
+                "ifdef" | "ifndef" => {
+                    let is_defined = state.defines.contains_key(identifier(argument).ok_or_else(|| error("Expected a macro name"))?);
+                    conditionals.push(Conditional { is_true: is_defined == (keyword == "ifdef"), has_else: false });
+                }
+                "else" => {
+                    let conditional = conditionals.last_mut().ok_or_else(|| error("#else without #ifdef"))?;
+                    if conditional.has_else { return Err(error("Duplicate #else")); }
+                    conditional.has_else = true;
+                    conditional.is_true = !conditional.is_true;
+                }
+                "endif" => {
+                    conditionals.pop().ok_or_else(|| error("#endif without #ifdef"))?;
+                }
+                _ if !is_active(&conditionals) => {}
+                "define" => {
+                    let (macro_name, value) = argument.split_once(char::is_whitespace).unwrap_or((argument, ""));
+                    let macro_name = identifier(macro_name).ok_or_else(|| error("Expected a macro name"))?;
+                    let value = substitute(value.trim(), &state.defines);
+                    state.defines.insert(String::from(macro_name), value);
+                }
+                "undef" => {
+                    state.defines.remove(identifier(argument).ok_or_else(|| error("Expected a macro name"))?);
+                }
+                "include" => {
+                    let file_name = argument.strip_prefix('"').and_then(|argument| argument.strip_suffix('"'))
+                        .ok_or_else(|| error("Expected #include \"file\""))?;
+                    self.process_file(file_name, state).map_err(|message| format!("{}\n  included from {}:{}", message, name, line_number))?;
+                }
+                _ => return Err(error(&format!("Unknown directive #{}", keyword))),
+            }
+        }
+
+        if !conditionals.is_empty() {
+            return Err(format!("{}: Missing #endif", name));
+        }
+        Ok(())
+    }
+}
+
+fn identifier(text: &str) -> Option<&str> {
+    let mut chars = text.chars();
+    let is_identifier = matches!(chars.next(), Some(first) if first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_');
+    if is_identifier { Some(text) } else { None }
+}
+
+// 替换行中与宏同名的标识符，// 之后的注释保持不变
+fn substitute(line: &str, defines: &BTreeMap<String, String>) -> String {
+    if defines.is_empty() { return String::from(line); }
+
+    let (code, comment) = match line.find("//") {
+        Some(index) => line.split_at(index),
+        None => (line, ""),
+    };
+
+    let mut output = String::with_capacity(line.len());
+    let mut token_start = None;
+    for (index, char) in code.char_indices() {
+        let is_identifier_char = char.is_ascii_alphanumeric() || char == '_';
+        match token_start {
+            Some(start) if !is_identifier_char => {
+                push_token(&mut output, &code[start..index], defines);
+                token_start = None;
+                output.push(char);
+            }
+            Some(_) => {}
+            // 数字开头的为字面量，如 1u、0x80000000u，不作为标识符
+            None if is_identifier_char && !char.is_ascii_digit() && !output.ends_with(|last: char| last.is_ascii_alphanumeric()) => {
+                token_start = Some(index);
+            }
+            None => output.push(char),
+        }
+    }
+    if let Some(start) = token_start {
+        push_token(&mut output, &code[start..], defines);
+    }
+    output + comment
+}
+
+fn push_token(output: &mut String, token: &str, defines: &BTreeMap<String, String>) {
+    output.push_str(defines.get(token).map_or(token, String::as_str));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(source: &str, preprocessor: ShaderPreprocessor) -> Result<String, String> {
+        preprocessor.generated("test.wgsl", String::from(source)).process("test.wgsl").map(|shader| shader.source)
+    }
+
+    #[test]
+    fn substitutes_defines() {
+        let source = "#define HALF SIZE / 2u\n@workgroup_size(SIZE) // SIZE\nlet a = HALF + SIZE_2 + 0xSIZE;";
+        let output = process(source, ShaderPreprocessor::default().define_u32("SIZE", 256)).unwrap();
+        assert_eq!(output, "@workgroup_size(256u) // SIZE\nlet a = 256u / 2u + SIZE_2 + 0xSIZE;\n");
+    }
+
+    #[test]
+    fn evaluates_conditionals() {
+        let source = "#ifdef A\na\n#ifndef B\nnot_b\n#else\nb\n#endif\n#else\nnot_a\n#endif\n";
+        assert_eq!(process(source, ShaderPreprocessor::default().flag("A", true)).unwrap(), "a\nnot_b\n");
+        assert_eq!(process(source, ShaderPreprocessor::default().flag("A", true).flag("B", true)).unwrap(), "a\nb\n");
+        assert_eq!(process(source, ShaderPreprocessor::default().flag("B", true)).unwrap(), "not_a\n");
+        assert!(process("#ifdef A\n", ShaderPreprocessor::default()).is_err());
+        assert!(process("#endif\n", ShaderPreprocessor::default()).is_err());
+    }
+
+    #[test]
+    fn includes_each_file_once() {
+        let preprocessor = ShaderPreprocessor::default()
+            .generated("struct.wgsl", String::from("struct S { a: u32, }"))
+            .generated("test.wgsl", String::from("#include \"struct.wgsl\"\n#include \"struct.wgsl\"\nlet b = 1u;"));
+        let shader = preprocessor.process("test.wgsl").unwrap();
+        assert_eq!(shader.source, "struct S { a: u32, }\nlet b = 1u;\n");
+        assert_eq!(shader.location(2), Some(("test.wgsl", 3)));
+
+        let error = process("\n#include \"missing.wgsl\"", ShaderPreprocessor::default()).unwrap_err();
+        assert!(error.contains("missing.wgsl"), "{}", error);
+    }
+
+    // 与运行时相同的宏与开关，不需要 GPU
+    #[test]
+    fn validates_runtime_shaders() {
+        let mut shaders = crate::models::graphics_model::preprocess_shaders();
+        shaders.push(crate::models::graphics_lib::radix_sort::radix_sort_shader());
+        for shader in shaders {
+            let shader = shader.unwrap();
+            if let Err(error) = shader.validate() {
+                panic!("{}", error);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_validation_errors() {
+        let preprocessor = ShaderPreprocessor::default()
+            .generated("struct.wgsl", String::from("struct S {\n    a: u32,\n}"))
+            .generated("test.wgsl", String::from("#include \"struct.wgsl\"\n\nfn f() -> u32 {\n    return 1.0;\n}"));
+        let error = preprocessor.process("test.wgsl").unwrap().validate().unwrap_err();
+        assert!(error.starts_with("test.wgsl:4\n"), "{}", error);
+
+        let preprocessor = preprocessor.generated("test.wgsl", String::from("#include \"struct.wgsl\"\nlet a: u32 = ;"));
+        let error = preprocessor.process("test.wgsl").unwrap().validate().unwrap_err();
+        assert!(error.starts_with("test.wgsl:2\n") && error.contains("error:"), "{}", error);
+    }
+}
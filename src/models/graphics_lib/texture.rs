@@ -51,12 +51,21 @@ impl Texture {
             height: size.height,
             depth_or_array_layers: 1,
         };
+        Self::create_texture_with_format(device, &size, sample_count, if !is_cast { TEXTURE_FORMAT} else { CAST_TEXTURE_FORMAT })
+    }
+
+    pub fn create_texture_with_format(device: &wgpu::Device, size: &wgpu::Extent3d, sample_count: u32, format: wgpu::TextureFormat) -> Self {
+        let size = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
         let desc = wgpu::TextureDescriptor {
             size,
             mip_level_count: 1,
             sample_count,
             dimension: wgpu::TextureDimension::D2,
-            format: if !is_cast { TEXTURE_FORMAT} else { CAST_TEXTURE_FORMAT },
+            format,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT
                 | wgpu::TextureUsages::COPY_SRC
                 | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -9,12 +9,14 @@ pub struct Uniforms {
     time:       [f32; 4],
     screen:     [f32; 4],
     camera:     [f32; 4],
+    geo:        [f32; 4],
 }
 
 pub fn generate_uniforms(
     camera: &Camera,
     viewport_size: glam::Vec2,
-    pointer_pos: glam::Vec2
+    pointer_pos: glam::Vec2,
+    geo: [f32; 4],
 ) -> Uniforms {
     Uniforms {
         view:       *camera.view_matrix.as_ref(),
@@ -34,5 +36,6 @@ pub fn generate_uniforms(
             camera.near_far.x,
             camera.near_far.y
         ],
+        geo,
     }
 }
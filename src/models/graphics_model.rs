@@ -2,43 +2,72 @@
 #![allow(unused_variables)]
 
 use std::borrow::{Cow};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::f32::consts;
 use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
 use chrono::{Local, Utc};
 use egui::{Ui, Vec2};
 use glam::Vec3;
 use wgpu::{Queue, ShaderModule, ComputePass};
 use wgpu::util::DeviceExt;
 use crate::models::data_model::GraphicsStatus;
-use crate::models::graphics_lib::{BufferDimensions, Camera, Controls, RenderPipeline, Texture};
+use crate::models::graphics_lib::{headless_render_state, BufferDimensions, Camera, Controls, MemoryPlanner, OitTargets, ReadbackRing, RenderPipeline, Texture};
 
 use rayon::prelude::*;
 use crate::models::graphics_lib::bind_group_layout::BindGroupLayout;
 use crate::models::graphics_lib::compute_shader::{ComputeBuffer, ComputeBufferType, ComputeKernel, ComputeShader};
+use crate::models::graphics_lib::kernel_status::{describe_kernel_status, format_kernel_status, wgsl_constants, KernelRecovery, KERNEL_NAMES, KERNEL_STATUS_COUNT, STATUS_NODES_QUARANTINED};
+use crate::models::graphics_lib::kernel_timer::{KernelTimer, TimingGroup, TimingSource};
+use crate::models::graphics_lib::memory_planner::{BufferRequirement, EdgeChunk, MemoryEstimate, MemoryPlanError};
+use crate::models::graphics_lib::radix_sort::{key_bits, RadixSort};
+use crate::models::graphics_lib::shader_preprocessor::{PreprocessedShader, ShaderPreprocessor};
 use crate::models::graphics_lib::unifrom::{generate_uniforms, Uniforms};
+use crate::models::layout_lib::{geo_layout, layout_metrics, pack_components, spectral_layout, tree_layout, ClusterGroups, CpuLayoutEngine, GeoProjection, LayoutEngine, LayoutHistory, LayoutMetrics, MetricsHistory, MultilevelLayout, TreeStyle};
 use crate::utils::file::create_png;
+use crate::utils::session::Session;
 
 use super::data_model::DataModel;
 use bytemuck::{Pod, Zeroable};
-use crate::utils::message::{message_error, message_warning};
+use strum::Display;
+use crate::utils::message::{message_error, message_info, message_warning};
 
-// 须同步修改 Compute WGSL 中每一个函数的 @workgroup_size
-// WGSL 中没有宏，不能使用类似 HLSL 的 #define 方法
+// 由 ShaderPreprocessor 定义为 WGSL 中的 WORKGROUP_SIZE
 const PARTICLES_PER_GROUP: u32 = 256;
 
+// Node Flag Buffer 中的标记位，由 ShaderPreprocessor 定义为 WGSL 中的同名宏
+pub const NODE_FLAG_PINNED: u32 = 1;
+// 增量删除的节点保留编号，不再绘制和移动
+pub const NODE_FLAG_HIDDEN: u32 = 2;
 
-// 须同步修改各个 WGSL 中的 Node struct
+
+// 以下 struct 与 common.wgsl、solver.wgsl 中的 WGSL struct 对应，内存布局由 build.rs 生成的断言检查
 #[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
 pub struct Node {
     _position:      [f32; 3],
+    _empty_1:       i32,
     _force:         [f32; 3],
+    _empty_2:       i32,
     _prev_force:    [f32; 3],
     _mass:          u32,
 }
 
+impl Node {
+    // 质量由 cal_mass 重新计算，初始为 1
+    pub fn new(position: [f32; 3]) -> Self {
+        Self { _position: position, _mass: 1, ..Zeroable::zeroed() }
+    }
+}
+
 #[repr(C)]
 pub struct Bound {
     _bound_min: [f32; 3],
+    _empty_1:   f32,
     _bound_max: [f32; 3],
+    _empty_2:   f32,
 }
 
 #[repr(C)]
@@ -52,7 +81,8 @@ pub struct BHTree {
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
 pub struct BHTreeNode {
-    _position:  [f32; 4],
+    _position:  [f32; 3],
+    _empty:     i32,
     _mass:      i32,
     _count:     i32,
     _start:     i32,
@@ -69,23 +99,66 @@ pub struct ComputeUniforms {
     tree_node_count:    u32,
     bounding_count:     u32,
     kernel_status_count: u32,
+    seed:               u32,
+    is_deterministic:   u32,
+    cluster_group_count: u32,
+    cluster_attraction: f32,
+    cluster_repulsion:  f32,
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
-pub struct EdgeSort {
-    _node:      [u32; 4],
-    _force:     [f32; 4],
+include!(concat!(env!("OUT_DIR"), "/wgsl_layout.rs"));
+
+// 八叉树 Buffer 的单元数为节点数的倍数，构建时单元不足则加倍后重新创建
+pub const DEFAULT_TREE_SCALE: u32 = 2;
+const MAX_TREE_SCALE: u32 = 16;
+
+// 连续自动恢复的次数上限，超过后停止计算
+const MAX_KERNEL_RECOVERY_ATTEMPTS: u32 = 3;
+
+// validate_nodes 每帧最多记录的隔离节点数
+const MAX_QUARANTINED_NODES: usize = 64;
+// 隔离的节点没有上一步的有效坐标时，编号的最高位为 1
+const QUARANTINE_NO_PREVIOUS: u32 = 0x8000_0000;
+// 同一节点被隔离的次数达到此值时按邻居重新放置
+const QUARANTINE_RESEED_COUNT: u32 = 3;
+// 隔离的节点在此时长内合并为一条消息
+const QUARANTINE_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+// 一段时间内被隔离的节点，合并后报告
+struct QuarantineReport {
+    start:          Instant,
+    first_frame:    u32,
+    last_frame:     u32,
+    // 各帧隔离的节点数之和，同一节点在多帧中被隔离时重复计数
+    count:          usize,
+    node_ids:       BTreeSet<u32>,
 }
 
-pub const KERNEL_STATUS_COUNT: usize = 5;
-pub const KERNEL_NAMES: [&str; KERNEL_STATUS_COUNT] = [
-    "attractive_force",
-    "tree_building",
-    "summarization",
-    "sort",
-    "electron_force",
-];
+impl QuarantineReport {
+
+    fn new(frame: u32) -> Self {
+        Self {
+            start: Instant::now(),
+            first_frame: frame,
+            last_frame: frame,
+            count: 0,
+            node_ids: BTreeSet::new(),
+        }
+    }
+
+    fn content(&self) -> String {
+        let mut node_ids = self.node_ids.iter().take(MAX_QUARANTINED_NODES).map(|index| index.to_string()).collect::<Vec<String>>();
+        if self.node_ids.len() > MAX_QUARANTINED_NODES {
+            node_ids.push(format!("and {} more", self.node_ids.len() - MAX_QUARANTINED_NODES));
+        }
+        let frames = if self.first_frame == self.last_frame {
+            format!("at frame {}", self.first_frame)
+        } else {
+            format!("in frames {} ~ {}", self.first_frame, self.last_frame)
+        };
+        format!("Nodes had NaN or infinite positions or forces {} and were restored from the previous step, {} times in total  \nNodes: {}", frames, self.count, node_ids.join(", "))
+    }
+}
 
 // 计算方法的类型
 // Continuous 为需要连续迭代模拟的方法，如 Force Atlas 2
@@ -104,6 +177,11 @@ pub struct ComputeMethod(pub &'static str, pub ComputeMethodType);
 impl ComputeMethod {
     pub const FORCE_ATLAS2: ComputeMethod = ComputeMethod("Force Atlas 2", ComputeMethodType::Continuous);
     pub const RANDOMIZE: ComputeMethod = ComputeMethod("Randomize", ComputeMethodType::OneStep);
+    pub const SPECTRAL: ComputeMethod = ComputeMethod("Spectral", ComputeMethodType::OneStep);
+    pub const MULTILEVEL: ComputeMethod = ComputeMethod("Multilevel", ComputeMethodType::Continuous);
+    pub const RADIAL_TREE: ComputeMethod = ComputeMethod("Radial Tree", ComputeMethodType::OneStep);
+    pub const TOP_DOWN_TREE: ComputeMethod = ComputeMethod("Top-down Tree", ComputeMethodType::OneStep);
+    pub const GEOGRAPHIC: ComputeMethod = ComputeMethod("Geographic", ComputeMethodType::OneStep);
 }
 
 // 绘图 Model，存放计算状态与计算资源
@@ -179,6 +257,40 @@ pub struct RenderOptions {
     pub is_rendering_axis: bool,
     pub is_rendering_bounding_box: bool,
     pub is_showing_debug:  bool,
+    // Geographic 布局下的经纬网与大圆弧边
+    pub is_rendering_graticule: bool,
+    pub is_great_circle_edge: bool,
+    // 加权混合顺序无关透明，开启时节点与边不再逐帧按深度排序
+    pub is_order_independent: bool,
+    // 在导入时决定是否分配深度排序的资源，关闭时节点按序号绘制
+    pub is_depth_sort: bool,
+}
+
+// 大圆弧边的分段数
+pub const EDGE_ARC_SEGMENT_COUNT: u32 = 16;
+
+// 须同步修改 S_axis.wgsl 中的 MERIDIAN_* / PARALLEL_*
+const GRATICULE_SEGMENT_COUNT: u32 = 24 * 36 + 11 * 72;
+
+// 分组来源，用于 Cluster 引力
+#[derive(Display, PartialEq, Clone, Copy)]
+pub enum ClusterSource {
+    None,
+    Partition,
+    Community,
+}
+
+// 同一输入、同一 seed 在确定性模式下，迭代 N 次后的坐标逐位相同（限同一 GPU 与驱动）
+pub struct ComputeOptions {
+    pub seed: u32,
+    pub is_deterministic: bool,
+    pub cluster_source: ClusterSource,
+    pub cluster_attraction: f32,
+    pub cluster_repulsion: f32,
+    // 树布局的根节点，为 None 时自动选取入度为 0 的节点
+    pub tree_root: Option<u32>,
+    // 每个 UI 帧运行的迭代次数
+    pub iterations_per_frame: u32,
 }
 
 pub struct ComputePipelines {
@@ -200,7 +312,7 @@ pub struct ComputePipelines {
     randomize:             ComputeKernel,
     copy:                  ComputeKernel,
     cal_depth:             ComputeKernel,
-    sort_by_depth:         ComputeKernel,
+    apply_depth_sort:      ComputeKernel,
 }
 
 pub enum CastType {
@@ -208,6 +320,66 @@ pub enum CastType {
     Edge
 }
 
+// 图的增量修改，节点编号与导入时相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphEdit {
+    // 在末尾追加若干个节点
+    AddNodes(u32),
+    AddEdge(u32, u32),
+    // 删除一条相同起点和终点的边
+    RemoveEdge(u32, u32),
+    // 删除节点的所有边并隐藏该节点
+    RemoveNode(u32),
+}
+
+// Buffer 按容量分配，增量修改不超出容量时不重建资源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphCapacity {
+    pub node_count: usize,
+    pub edge_count: usize,
+}
+
+impl GraphCapacity {
+
+    pub fn exact(status: &GraphicsStatus) -> Self {
+        Self {
+            node_count: status.node_count,
+            edge_count: status.edge_count,
+        }
+    }
+
+    pub fn fits(&self, status: &GraphicsStatus) -> bool {
+        status.node_count <= self.node_count && status.edge_count <= self.edge_count
+    }
+
+    // 超出的一项按倍数扩大，使连续的修改平均只重建常数次
+    pub fn grow(&self, status: &GraphicsStatus) -> Self {
+        let grow = |capacity: usize, count: usize| {
+            let mut capacity = capacity.max(PARTICLES_PER_GROUP as usize);
+            while capacity < count { capacity *= 2; }
+            capacity
+        };
+        Self {
+            node_count: if status.node_count > self.node_count { grow(self.node_count, status.node_count) } else { self.node_count },
+            edge_count: if status.edge_count > self.edge_count { grow(self.edge_count, status.edge_count) } else { self.edge_count },
+        }
+    }
+
+    fn status(&self) -> GraphicsStatus {
+        GraphicsStatus {
+            node_count: self.node_count,
+            edge_count: self.edge_count,
+            ..Default::default()
+        }
+    }
+}
+
+// Handle 工具正在拖动的节点
+pub struct NodeDrag {
+    pub index: u32,
+    position: glam::Vec3,
+}
+
 // 绘图资源 Model，存放和计算和绘图相关的一切资源
 pub struct GraphicsResources {
 
@@ -236,6 +408,13 @@ pub struct GraphicsResources {
     cast_depth_texture:           Option<Texture>,
 
     axis_render_pipeline:           wgpu::RenderPipeline,
+    graticule_render_pipeline:      wgpu::RenderPipeline,
+
+    // 顺序无关透明的合成管线与渲染目标，渲染目标在首次使用或尺寸变化时创建
+    oit_composite_bind_group_layout: wgpu::BindGroupLayout,
+    oit_composite_render_pipeline:  wgpu::RenderPipeline,
+    viewport_oit:                   Option<OitTargets>,
+    output_oit:                     Option<OitTargets>,
 
     // 相机
     camera:                         Camera,
@@ -257,42 +436,98 @@ pub struct GraphicsResources {
     pub frames_per_second:          f64,                      // FPS
 
     pub render_options:             RenderOptions,
+    pub compute_options:            ComputeOptions,
     pub need_update:                bool,
 
+    // 当前坐标来自 Geographic 布局时的投影方式，其他计算方法会将其清空
+    pub geo_projection:             Option<GeoProjection>,
+    // 上次写入 Render Uniform 的地理参数
+    geo_uniform:                    [f32; 4],
+
     pub cast_type:                  Option<CastType>,
     pub cast_value:                 u32,
 
+    // 固定的节点，拖动后自动固定，直到取消固定
+    pub pinned_nodes:               BTreeSet<u32>,
+    // 增量删除的节点
+    pub hidden_nodes:               BTreeSet<u32>,
+    // 时间轴窗口之外的节点，没有时间轴时为空
+    pub inactive_nodes:             Vec<bool>,
+    pub node_drag:                  Option<NodeDrag>,
+
     pub shaders:                    Vec<ShaderModule>,
+    // Shader 验证失败的原因，此时无法导入数据与绘制
+    pub shader_error:               Option<String>,
     pub compute_shader:             ComputeShader,
 
     pub graph_resources:            Option<GraphResources>,
+
+    // Multilevel 布局的粗化层级与进度，其他计算方法下为 None
+    pub multilevel:                 Option<MultilevelLayout>,
+
+    // Cluster 引力使用的节点分组
+    pub cluster_groups:             Option<ClusterGroups>,
+
+    // 布局质量指标的历史
+    pub metrics_history:            MetricsHistory,
+    // 正在工作线程上计算的指标，同一时间只有一个
+    metrics_receiver:               Option<Receiver<(u32, LayoutMetrics)>>,
+
+    // 持续计算时记录的坐标快照
+    pub layout_history:             LayoutHistory,
+
+    // 每个 Kernel 的耗时
+    pub kernel_timer:               KernelTimer,
+
+    // 八叉树单元数与节点数之比，单元不足时自动加倍
+    pub tree_scale:                 u32,
+    // 连续自动恢复的次数，与恢复时的计算帧号，之前提交的帧仍会报告同样的错误
+    kernel_recovery_attempts:       u32,
+    kernel_recovery_frame:          u32,
+    // 每个节点被 validate_nodes 隔离的次数
+    quarantine_counts:              BTreeMap<u32, u32>,
+    // 尚未报告的隔离节点
+    quarantine_report:              Option<QuarantineReport>,
+    // 没有窗口时记录的第一个设备错误，由 create_gpu_layout_engine 注册
+    device_error:                   Arc<Mutex<Option<String>>>,
 }
 
 pub struct GraphResources {
 
     // 包含 Node / Edge Count
     pub status:                     GraphicsStatus,
+    // Buffer 可容纳的节点数与边数，不小于 status 中的数量
+    pub capacity:                   GraphCapacity,
 
+    uniforms:                       ComputeUniforms,
     uniform_buffer:                 wgpu::Buffer,
     node_buffer:                    wgpu::Buffer,
     node_copy_buffer:               wgpu::Buffer,
+    node_flag_buffer:               wgpu::Buffer,
     node_edge_sort_range_buffer:    wgpu::Buffer,
     edge_buffer:                    wgpu::Buffer,
-    edge_sort_node_buffer:          wgpu::Buffer,
-    edge_sort_dir_buffer:           wgpu::Buffer,
+    // 边排序 Buffer 按 GPU 的绑定上限分块
+    edge_chunks:                    Vec<EdgeChunkResources>,
     bounding_buffer:                wgpu::Buffer,
     tree_buffer:                    wgpu::Buffer,
     tree_node_buffer:               wgpu::Buffer,
     tree_child_buffer:              wgpu::Buffer,
     depth_sort_buffer:              wgpu::Buffer,
-    depth_sort_param_buffer:        wgpu::Buffer,
     kernel_status_buffer:           wgpu::Buffer,
+    // 每个节点上一步的有效坐标，w 为 0 时没有
+    last_valid_position_buffer:     wgpu::Buffer,
+    // 隔离节点数与节点编号
+    node_quarantine_buffer:         wgpu::Buffer,
 
     // Bind Group
     node_render_bind_group:         wgpu::BindGroup,
     edge_render_bind_group:         wgpu::BindGroup,
     bounding_box_render_bind_group: wgpu::BindGroup,
 
+    // 基数排序，深度排序按节点深度，边排序按边的起点
+    // 导入时关闭深度排序则为 None
+    depth_sort:                     Option<RadixSort>,
+    edge_sort:                      RadixSort,
 
     pub debugger:                   GraphicsDebugger,
     pub buffer_bytes:               Option<Vec<u8>>,
@@ -302,23 +537,112 @@ pub struct GraphResources {
     edge_render_pipeline:           wgpu::RenderPipeline,
     edge_cast_render_pipeline:      wgpu::RenderPipeline,
     bounding_box_render_pipeline:   wgpu::RenderPipeline,
+    // 节点与边的顺序无关透明管线，后端不支持时为 None
+    oit_render_pipelines:           Option<(wgpu::RenderPipeline, wgpu::RenderPipeline)>,
+
+    cluster:                        Option<ClusterResources>,
 
     // 计算管线
     pub kernel_status_codes:        Vec<i32>,
     pub is_kernel_error:            bool,
 
+    // 八叉树单元数与节点数之比
+    tree_scale:                     u32,
+
     // 线程组数 = 线程数 / 每组线程数
     node_work_group_count:          u32,
     edge_work_group_count:          u32,
-    edge_sort_work_group_count:     u32,
     tree_node_work_group_count:     u32,
     step_work_group_count:          u32,
     bb_work_group_count:            u32,
 }
 
+// 边排序 Buffer 的一个分块，边排序相关的 Kernel 按分块逐一 dispatch
+// chunk 为当前边数下的分块，capacity 为分块 Buffer 的容量
+struct EdgeChunkResources {
+    chunk:                          EdgeChunk,
+    capacity:                       EdgeChunk,
+    chunk_buffer:                   wgpu::Buffer,
+    edge_sort_node_buffer:          wgpu::Buffer,
+    edge_sort_dir_buffer:           wgpu::Buffer,
+}
+
+// 节点分组上传到 GPU 后的 Buffer
+pub struct ClusterResources {
+    node_group_buffer:              wgpu::Buffer,
+    group_member_buffer:            wgpu::Buffer,
+    group_range_buffer:             wgpu::Buffer,
+    group_centroid_buffer:          wgpu::Buffer,
+    group_force_buffer:             wgpu::Buffer,
+
+    group_count:                    u32,
+    group_work_group_count:         u32,
+}
+
 pub struct GraphicsDebugger {
-    debug_buffer:    wgpu::Buffer,
-    buffer_size:     u32,
+    // Kernel 状态码的异步回读
+    status_readback: ReadbackRing,
+    // 隔离节点列表的异步回读
+    quarantine_readback: ReadbackRing,
+    // 布局历史坐标的异步回读，开始记录时创建，节点数变化时丢弃
+    layout_readback: Option<ReadbackRing>,
+    // 自动计算指标的坐标回读，节点数变化时丢弃
+    metrics_readback: Option<ReadbackRing>,
+}
+
+// Kernel 状态回读的暂存 Buffer 数，即结果最多滞后的计算帧数
+const STATUS_READBACK_SLOT_COUNT: usize = 4;
+// 坐标回读的暂存 Buffer 按节点数分配，只保留两个
+const LAYOUT_READBACK_SLOT_COUNT: usize = 2;
+// 指标的计算比回读慢得多，只保留一个，上一次的结果取回前不发起新的回读
+const METRICS_READBACK_SLOT_COUNT: usize = 1;
+
+pub const MAX_ITERATIONS_PER_FRAME: u32 = 100;
+
+impl GraphResources {
+
+    // 在容量以内更新节点数和边数，Buffer 中超出数量的部分不参与计算和绘制
+    fn set_counts(&mut self, queue: &Queue, status: GraphicsStatus) {
+
+        let node_count = status.node_count as u32;
+        let edge_count = status.edge_count as u32;
+        let edge_sort_count = edge_count * 2;
+        let tree_node_count = get_tree_node_count(&node_count, self.tree_scale);
+
+        // 线程组数 = 线程数 / 每组线程数（取整）
+        self.node_work_group_count = (node_count + PARTICLES_PER_GROUP - 1) / PARTICLES_PER_GROUP;
+        self.edge_work_group_count = (edge_count + PARTICLES_PER_GROUP - 1) / PARTICLES_PER_GROUP;
+        self.tree_node_work_group_count = (tree_node_count + PARTICLES_PER_GROUP - 1) / PARTICLES_PER_GROUP;
+        self.step_work_group_count = (node_count.min(16384) + PARTICLES_PER_GROUP - 1) / PARTICLES_PER_GROUP;
+        self.bb_work_group_count = (self.node_work_group_count + PARTICLES_PER_GROUP - 1) / PARTICLES_PER_GROUP;
+
+        self.uniforms.node_count = node_count;
+        self.uniforms.edge_count = edge_count;
+        self.uniforms.edge_sort_count = edge_sort_count;
+        self.uniforms.tree_node_count = tree_node_count;
+        self.uniforms.bounding_count = self.node_work_group_count;
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
+
+        // 分块的边界不变，只缩短分块内的数量，超出边数的分块为空
+        for edge_chunk in &mut self.edge_chunks {
+            edge_chunk.chunk.count = edge_sort_count
+                .saturating_sub(edge_chunk.capacity.offset)
+                .min(edge_chunk.capacity.count);
+            queue.write_buffer(&edge_chunk.chunk_buffer, 0, bytemuck::cast_slice(&[edge_chunk.chunk]));
+        }
+
+        self.edge_sort.set_count(queue, edge_sort_count);
+        if let Some(depth_sort) = &mut self.depth_sort {
+            depth_sort.set_count(queue, node_count);
+        }
+
+        // 坐标回读的暂存 Buffer 按节点数分配
+        if status.node_count != self.status.node_count {
+            self.debugger.layout_readback = None;
+            self.debugger.metrics_readback = None;
+        }
+        self.status = status;
+    }
 }
 
 impl GraphicsResources {
@@ -331,24 +655,33 @@ impl GraphicsResources {
         let device = &render_state.device;
         let _queue = &render_state.queue;
 
-        // 从文件中创建 Shader
+        // 从文件中创建 Shader，先由 ShaderPreprocessor 展开 #include 与 Rust 定义的宏，再由 naga 验证
 
-        let shader_files = [
-            include_str!("../assets/shaders/S_node.wgsl"),
-            include_str!("../assets/shaders/S_edge.wgsl"),
-            include_str!("../assets/shaders/S_axis.wgsl"),
-            include_str!("../assets/shaders/S_bounding_box.wgsl"),
-            include_str!("../assets/shaders/CS_graph_solver.wgsl"),
-        ];
+        let shader_sources = preprocess_shaders();
 
-        let mut shaders = shader_files.par_iter().map(|shader_file| unsafe {
+        let mut shader_errors = vec![];
+        for (shader_file, shader_source) in GRAPHICS_SHADER_FILES.iter().zip(shader_sources.iter()) {
+            let result = shader_source.as_ref().map_err(String::clone).and_then(|shader_source| shader_source.validate().map(|_| ()));
+            if let Err(error) = result {
+                message_error("Shader Validation Failed", error.as_str());
+                shader_errors.push(format!("{}: {}", shader_file, error.lines().next().unwrap_or_default()));
+            }
+        }
+        let shader_error = if shader_errors.is_empty() { None } else { Some(shader_errors.join("\n")) };
 
-            let shader = device.create_shader_module_unchecked(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(shader_file)),
-            });
+        // 验证失败时 wgpu 仍会报错，捕获这些错误避免程序退出，之后不再导入数据与绘制
+        if shader_error.is_some() {
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+        }
 
-            shader
+        let mut shaders = GRAPHICS_SHADER_FILES.iter().zip(shader_sources).map(|(shader_file, shader_source)| {
+
+            let source = shader_source.map(|shader_source| shader_source.source).unwrap_or_default();
+
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(shader_file),
+                source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+            })
 
         }).collect::<Vec<ShaderModule>>();
 
@@ -361,7 +694,23 @@ impl GraphicsResources {
         let axis_render_pipeline = RenderPipeline::create_axis_render_pipeline(
             device,
             &[&render_uniform_bind_group_layout],
-            axis_shader
+            axis_shader,
+            false,
+        ).render_pipeline;
+
+        let graticule_render_pipeline = RenderPipeline::create_axis_render_pipeline(
+            device,
+            &[&render_uniform_bind_group_layout],
+            axis_shader,
+            true,
+        ).render_pipeline;
+
+        let oit_composite_bind_group_layout =       BindGroupLayout::create_oit_composite_bind_group_layout(device).bing_group_layout;
+
+        let oit_composite_render_pipeline = RenderPipeline::create_oit_composite_render_pipeline(
+            device,
+            &[&oit_composite_bind_group_layout],
+            &shaders[4],
         ).render_pipeline;
 
 
@@ -441,9 +790,12 @@ impl GraphicsResources {
         let compute_shader = ComputeShader {
             shader: compute_shader,
             device: device.clone(),
-            kernels: Default::default()
+            kernels: Default::default(),
+            pipelines: Default::default(),
         };
 
+        let kernel_timer = KernelTimer::new(device, &render_state.queue);
+
 
         let graphics_resources = GraphicsResources {
             render_state,
@@ -468,6 +820,11 @@ impl GraphicsResources {
             render_uniform_buffer,
             render_uniform_bind_group,
             axis_render_pipeline,
+            graticule_render_pipeline,
+            oit_composite_bind_group_layout,
+            oit_composite_render_pipeline,
+            viewport_oit: None,
+            output_oit: None,
             compute_frame_count: 0,
             render_frame_count: 0,
             last_time: 0,
@@ -478,39 +835,174 @@ impl GraphicsResources {
                 is_rendering_edge: true,
                 is_rendering_axis: false,
                 is_rendering_bounding_box: false,
-                is_showing_debug: false
+                is_showing_debug: false,
+                is_rendering_graticule: true,
+                is_great_circle_edge: true,
+                is_order_independent: false,
+                is_depth_sort: true,
+            },
+            compute_options: ComputeOptions {
+                seed: 0,
+                is_deterministic: false,
+                cluster_source: ClusterSource::None,
+                cluster_attraction: 1.0,
+                cluster_repulsion: 0.0,
+                tree_root: None,
+                iterations_per_frame: 1,
             },
             need_update: true,
+            geo_projection: None,
+            geo_uniform: [0.0, 1.0, 0.0, 0.0],
             cast_depth_texture: None,
             cast_type: None,
             cast_value: 0,
+            pinned_nodes: BTreeSet::new(),
+            hidden_nodes: BTreeSet::new(),
+            inactive_nodes: vec![],
+            node_drag: None,
             shaders,
             compute_shader,
             graph_resources: None,
-            render_uniform_bind_group_layout
+            multilevel: None,
+            cluster_groups: None,
+            metrics_history: MetricsHistory::default(),
+            metrics_receiver: None,
+            layout_history: LayoutHistory::default(),
+            kernel_timer,
+            tree_scale: DEFAULT_TREE_SCALE,
+            kernel_recovery_attempts: 0,
+            kernel_recovery_frame: 0,
+            quarantine_counts: BTreeMap::new(),
+            quarantine_report: None,
+            device_error: Arc::new(Mutex::new(None)),
+            render_uniform_bind_group_layout,
+            shader_error,
         };
 
+        if graphics_resources.shader_error.is_some() {
+            let _ = pollster::block_on(graphics_resources.render_state.device.pop_error_scope());
+        }
+
         graphics_resources
 
     }
 
-    pub fn init_data(&mut self, render_state: egui_wgpu::RenderState, model: &mut DataModel) {
+    // 图数据超出 GPU 的限制时返回原因，不创建任何资源
+    pub fn init_data(&mut self, render_state: egui_wgpu::RenderState, model: &mut DataModel) -> Result<(), String> {
 
-        let device = &render_state.device;
-        let _queue = &render_state.queue;
+        if let Some(shader_error) = &self.shader_error {
+            return Err(format!("Shaders failed validation\n{}", shader_error));
+        }
 
-        let status = model.status.clone();
+        self.multilevel = None;
+        self.pinned_nodes.clear();
+        self.hidden_nodes.clear();
+        self.inactive_nodes.clear();
+        self.node_drag = None;
+        self.cluster_groups = None;
+        self.compute_options.cluster_source = ClusterSource::None;
+        self.compute_options.tree_root = None;
+        self.geo_projection = None;
+        self.metrics_history.clear();
+        // 丢弃上一个图的指标计算，工作线程发送结果时失败即退出
+        self.metrics_receiver = None;
+        self.layout_history.clear();
+        self.kernel_timer.timings.clear();
+        self.tree_scale = DEFAULT_TREE_SCALE;
+        self.kernel_recovery_attempts = 0;
+        self.kernel_recovery_frame = 0;
+        self.quarantine_counts.clear();
+        self.quarantine_report = None;
+
+        let capacity = GraphCapacity::exact(&model.status);
+        let result = self.init_graph(render_state, model.status.clone(), capacity, model.source_target_list.as_ref().unwrap());
+
+        // 清空 Model 中的 Source Target List
+        model.clear_source_target_list();
+
+        result.map_err(|error| error.to_string())
+    }
+
+    // 导入前按行数估算显存占用，与 init_graph 中创建的 Buffer 对应
+    // 视口尚未创建时按 1920 x 1080 估算纹理
+    pub fn estimate_memory(&self, node_count: usize, edge_count: usize) -> MemoryEstimate {
+
+        let node_count = node_count as u64;
+        let edge_count = edge_count as u64;
+        let edge_sort_count = edge_count * 2;
+        let tree_node_count = get_tree_node_count(&(node_count.min(u32::MAX as u64 / 4) as u32), DEFAULT_TREE_SCALE) as u64;
+        let group_size = PARTICLES_PER_GROUP as u64;
+        let histogram_size = |count: u64| (count + group_size - 1) / group_size * 16 * 4;
+
+        // Node / Copy / Flag / Edge Sort Range / Spring Force / Last Valid Position / Bounding
+        let node_size = pad_size(mem::size_of::<Node>(), 1) * node_count
+            + node_count * (3 * 4 + 4 + 2 * 4 + 4 * 4 + 4 * 4)
+            + (node_count + group_size - 1) / group_size * 8 * 4;
+
+        // 分块的 Edge Sort Node / Dir 与基数排序的两组键值
+        let edge_sort_size = edge_sort_count * (2 * 4 + 4 * 4 + 4 * 4) + histogram_size(edge_sort_count);
+
+        let depth_sort_size = node_count * 2 * 4 + match self.render_options.is_depth_sort {
+            true => node_count * 4 * 4 + histogram_size(node_count),
+            false => 0,
+        };
+
+        let pixel_count = match self.viewport_texture_extent.width * self.viewport_texture_extent.height {
+            0 => 1920 * 1080,
+            pixel_count => pixel_count as u64,
+        };
+        // 颜色、多重采样颜色与多重采样深度，顺序无关透明另有累积与透射率目标
+        let texture_size = pixel_count * (4 + 4 * 4 + 4 * 4) + match self.render_options.is_order_independent {
+            true => pixel_count * (8 * 4 + 2 * 4 + 8 + 2),
+            false => 0,
+        };
+
+        MemoryEstimate {
+            items: vec![
+                BufferRequirement { label: "Node", size: node_size },
+                BufferRequirement { label: "Edge", size: edge_count * 2 * 4 },
+                BufferRequirement { label: "Tree", size: 4 * ((tree_node_count + 1) * 8) + pad_size(mem::size_of::<BHTree>(), 1) },
+                BufferRequirement { label: "Tree Child", size: 4 * ((tree_node_count + 1) * 8) },
+                BufferRequirement { label: "Edge Sort", size: edge_sort_size },
+                BufferRequirement { label: "Depth Sort", size: depth_sort_size },
+                BufferRequirement { label: "Texture", size: texture_size },
+            ],
+        }
+    }
+
+    // 按 GPU 的限制检查能否导入，与 init_graph 中的检查相同
+    pub fn check_memory(&self, node_count: usize, edge_count: usize) -> Result<(), MemoryPlanError> {
+        let status = GraphicsStatus {
+            node_count,
+            edge_count,
+            ..Default::default()
+        };
+        plan_graph_memory(&self.memory_planner(), &status, mem::size_of::<Node>()).map(|_| ())
+    }
+
+    pub fn memory_planner(&self) -> MemoryPlanner {
+        MemoryPlanner::new(&self.render_state.device.limits())
+    }
 
+    // 按给定的节点数和边表创建计算与绘图资源，Buffer 按 capacity 分配
+    // Multilevel 布局的每一层粗化图也通过这里重建资源
+    fn init_graph(&mut self, render_state: egui_wgpu::RenderState, status: GraphicsStatus, capacity: GraphCapacity, source_target_list: &[u32]) -> Result<(), MemoryPlanError> {
 
-        // 从 Graphics Model 中获取 Node 和 Edge 的数量
-        let node_count = model.status.node_count as u32;
-        let edge_count = model.status.edge_count as u32;
-        let edge_sort_count = (model.status.edge_count * 2) as u32;
-        let tree_node_count = get_tree_node_count(&node_count);
+        let device = &render_state.device;
+        let queue = &render_state.queue;
 
         // Node 和 Edge 结构体的占内存大小，用于计算 Buffer 长度
         let node_struct_size = mem::size_of::<Node>();
 
+        // 分配之前按 GPU 的限制检查，超出时不创建任何资源
+        let edge_chunks = plan_graph_memory(&self.memory_planner(), &capacity.status(), node_struct_size)?;
+
+        // 以下 Buffer 均按容量分配，实际的数量由 set_counts 写入
+        let node_count = capacity.node_count as u32;
+        let edge_count = capacity.edge_count as u32;
+        let edge_sort_count = (capacity.edge_count * 2) as u32;
+        let tree_node_count = get_tree_node_count(&node_count, self.tree_scale);
+
         let shaders = &self.shaders;
 
         let node_shader = &shaders[0];
@@ -564,27 +1056,32 @@ impl GraphicsResources {
             bounding_box_shader
         ).render_pipeline;
 
+        // 部分降级后端（如 GL）不支持浮点格式的多重采样，此时不提供顺序无关透明
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
 
-        // 计算线程组数
-        // 线程组数 = 线程数 / 每组线程数（取整）
-
-        let node_work_group_count =
-            ((node_count as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
-
-        let edge_work_group_count =
-            ((edge_count as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
+        let node_oit_render_pipeline = RenderPipeline::create_oit_render_pipeline(
+            device,
+            &[&self.render_uniform_bind_group_layout, &node_render_bind_group_layout],
+            node_shader,
+            "oit_vs",
+        ).render_pipeline;
 
-        let edge_sort_work_group_count =
-            ((edge_sort_count as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
+        let edge_oit_render_pipeline = RenderPipeline::create_oit_render_pipeline(
+            device,
+            &[&self.render_uniform_bind_group_layout, &edge_render_bind_group_layout],
+            edge_shader,
+            "main_vs",
+        ).render_pipeline;
 
-        let tree_node_work_group_count =
-            (tree_node_count as f32 / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
+        let oit_render_pipelines = match pollster::block_on(device.pop_error_scope()) {
+            None => Some((node_oit_render_pipeline, edge_oit_render_pipeline)),
+            Some(_) => None,
+        };
 
-        let step_work_group_count =
-            (std::cmp::min(node_count, 16384) as f32 / PARTICLES_PER_GROUP as f32).ceil() as u32;
 
-        let bb_work_group_count =
-            ((node_work_group_count as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
+        // 线程组数由 set_counts 按实际数量计算，Bounding Buffer 按容量分配
+        let node_work_group_count =
+            ((node_count as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
 
         // Buffer 创建
 
@@ -596,8 +1093,16 @@ impl GraphicsResources {
             tree_node_count,
             bounding_count: node_work_group_count,
             kernel_status_count: KERNEL_NAMES.len() as u32,
+            seed: self.compute_options.seed,
+            is_deterministic: self.compute_options.is_deterministic as u32,
+            cluster_group_count: 0,
+            cluster_attraction: self.compute_options.cluster_attraction,
+            cluster_repulsion: self.compute_options.cluster_repulsion,
         };
 
+        // 帧号参与随机数种子，每次导入都从 0 开始，保证结果可复现
+        self.compute_frame_count = 0;
+
         // 创建 Uniform Buffer
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
@@ -616,7 +1121,8 @@ impl GraphicsResources {
             size: node_buffer_size,
             usage: wgpu::BufferUsages::VERTEX
                 | wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_DST,
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
             mapped_at_creation: false
         });
 
@@ -633,6 +1139,15 @@ impl GraphicsResources {
             mapped_at_creation: false
         });
 
+        // 每个节点一个 u32 标记，见 NODE_FLAG_*
+        let node_flag_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Node Flag Buffer"),
+            size: (node_count * 4) as _,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
         let node_edge_sort_range_buffer_size = node_count * 2 * 4;
 
         let node_edge_sort_range_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -667,58 +1182,72 @@ impl GraphicsResources {
             mapped_at_creation: false
         });
 
-        let depth_sort_buffer_size = node_count * 2 * 4;
+        // 初始为节点序号，不做深度排序时按此顺序绘制
+        let depth_sort_kvps: Vec<[u32; 2]> = (0..node_count).map(|index| [0, index]).collect();
 
-        let depth_sort_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        let depth_sort_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Depth Sort Buffer"),
-            size: depth_sort_buffer_size as wgpu::BufferAddress,
+            contents: bytemuck::cast_slice(&depth_sort_kvps),
             usage: wgpu::BufferUsages::VERTEX
                 | wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false
         });
 
-        let depth_sort_param_buffer_size = 2 * 4;
-
-        let depth_sort_param_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Depth Sort Param Buffer"),
-            size: depth_sort_param_buffer_size as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false
-        });
+        let depth_sort = self.render_options.is_depth_sort
+            .then(|| RadixSort::new(device, node_count, 32, "Depth Sort"));
 
-        // 新建 Edge Buffer 并传入数据
-        let edge_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        // 新建 Edge Buffer 并传入数据，空 Buffer 无法绑定，至少保留一条边
+        let edge_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Edge Buffer"),
-            contents: bytemuck::cast_slice(model.source_target_list.as_ref().unwrap()),
+            size: (edge_count.max(1) * 2 * 4) as _,
             usage: wgpu::BufferUsages::VERTEX
                 | wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
         });
+        queue.write_buffer(&edge_buffer, 0, bytemuck::cast_slice(source_target_list));
 
-        let edge_sort_node_buffer_size = edge_sort_count * 2 * 4;
+        // 每个分块一组边排序 Buffer，空 Buffer 无法绑定，至少保留一个元素
+        let edge_chunks = edge_chunks.into_iter().map(|chunk| {
 
-        let edge_sort_node_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Edge Sort Node Buffer"),
-            size: edge_sort_node_buffer_size as _,
-            usage: wgpu::BufferUsages::VERTEX
-                | wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_DST
-                | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false
-        });
+            let chunk_count = (chunk.count as u64).max(1);
 
-        let edge_sort_dir_buffer_size = edge_sort_count * 4 * 4;
+            let chunk_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Edge Chunk Buffer"),
+                contents: bytemuck::cast_slice(&[chunk]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
 
-        let edge_sort_dir_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Edge Sort Dir Buffer"),
-            size: edge_sort_dir_buffer_size as _,
-            usage: wgpu::BufferUsages::VERTEX
-                | wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_DST
-                | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false
-        });
+            let edge_sort_node_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Edge Sort Node Buffer"),
+                size: chunk_count * 2 * 4,
+                usage: wgpu::BufferUsages::VERTEX
+                    | wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false
+            });
+
+            let edge_sort_dir_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Edge Sort Dir Buffer"),
+                size: chunk_count * 4 * 4,
+                usage: wgpu::BufferUsages::VERTEX
+                    | wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false
+            });
+
+            EdgeChunkResources {
+                chunk,
+                capacity: chunk,
+                chunk_buffer,
+                edge_sort_node_buffer,
+                edge_sort_dir_buffer,
+            }
+        }).collect::<Vec<_>>();
+
+        let edge_sort = RadixSort::new(device, edge_sort_count, key_bits(node_count.saturating_sub(1)), "Edge Sort");
 
         // Tree Buffer
         let tree_buffer_size = pad_size(mem::size_of::<BHTree>(), 1);
@@ -747,10 +1276,6 @@ impl GraphicsResources {
             mapped_at_creation: false
         });
 
-        // 清空 Model 中的 Source Target List
-        model.clear_source_target_list();
-
-
         // Bind Group
         // 用于绑定 Buffer 与 Bind Group Layout，后连接 Pipeline Layout 和 Pipeline
         // 需与 Bind Group Layout 保持索引和容量一致
@@ -767,6 +1292,10 @@ impl GraphicsResources {
                     binding: 1,
                     resource: depth_sort_buffer.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: node_flag_buffer.as_entire_binding(),
+                },
             ],
             label: None,
         });
@@ -811,16 +1340,28 @@ impl GraphicsResources {
         });
 
 
-        let debug_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Debug Buffer"),
-            size: kernel_status_buffer_size as _,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        // 初始为 0，即没有有效坐标，由 set_position 与 validate_nodes 写入
+        let last_valid_position_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Last Valid Position Buffer"),
+            size: (node_count * 4 * 4) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+
+        let node_quarantine_buffer_size = (1 + MAX_QUARANTINED_NODES) * 4;
+        let node_quarantine_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Node Quarantine Buffer"),
+            size: node_quarantine_buffer_size as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false
         });
 
         let debugger = GraphicsDebugger {
-            debug_buffer,
-            buffer_size: kernel_status_buffer_size as _,
+            status_readback: ReadbackRing::new(device, kernel_status_buffer_size as _, STATUS_READBACK_SLOT_COUNT, "Kernel Status Readback Buffer"),
+            quarantine_readback: ReadbackRing::new(device, node_quarantine_buffer_size as _, STATUS_READBACK_SLOT_COUNT, "Node Quarantine Readback Buffer"),
+            layout_readback: None,
+            metrics_readback: None,
         };
 
         let graph_compute = &mut self.compute_shader;
@@ -836,6 +1377,11 @@ impl GraphicsResources {
                 buffer_type: ComputeBufferType::Storage,
                 buffer: kernel_status_buffer.as_entire_binding(),
             },
+            ComputeBuffer {
+                binding: 28,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: node_quarantine_buffer.as_entire_binding(),
+            },
         ]);
         graph_compute.create_compute_kernel("gen_node", vec![
             ComputeBuffer {
@@ -864,7 +1410,7 @@ impl GraphicsResources {
                 buffer: node_edge_sort_range_buffer.as_entire_binding(),
             },
         ]);
-        graph_compute.create_compute_kernel("cal_mass", vec![
+        graph_compute.create_compute_kernel("reset_edge_state", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
@@ -876,39 +1422,31 @@ impl GraphicsResources {
                 buffer: node_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 2,
-                buffer_type: ComputeBufferType::StorageReadOnly,
-                buffer: edge_buffer.as_entire_binding(),
+                binding: 3,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: spring_force_buffer.as_entire_binding(),
             },
-        ]);
-        graph_compute.create_compute_kernel("cal_gravity_force", vec![
             ComputeBuffer {
-                binding: 0,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: uniform_buffer.as_entire_binding(),
+                binding: 13,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: node_edge_sort_range_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 1,
+                binding: 14,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: node_buffer.as_entire_binding(),
+                buffer: node_edge_sort_range_buffer.as_entire_binding(),
             },
         ]);
-
-        graph_compute.create_compute_kernel("prepare_edge_sort", vec![
+        graph_compute.create_compute_kernel("cal_mass", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
                 buffer: uniform_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 12,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_node_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 13,
+                binding: 1,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_dir_buffer.as_entire_binding(),
+                buffer: node_buffer.as_entire_binding(),
             },
             ComputeBuffer {
                 binding: 2,
@@ -916,131 +1454,145 @@ impl GraphicsResources {
                 buffer: edge_buffer.as_entire_binding(),
             },
         ]);
-        graph_compute.create_compute_kernel("sort_edge", vec![
+        graph_compute.create_compute_kernel("cal_gravity_force", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
                 buffer: uniform_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 12,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_node_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 13,
+                binding: 1,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_dir_buffer.as_entire_binding(),
+                buffer: node_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 9,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: depth_sort_param_buffer.as_entire_binding(),
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_flag_buffer.as_entire_binding(),
             },
         ]);
-        graph_compute.create_compute_kernel("compute_node_edge_sort_range", vec![
+
+        graph_compute.create_compute_kernel("prepare_edge_sort", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
                 buffer: uniform_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 12,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_node_buffer.as_entire_binding(),
+                binding: 2,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: edge_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 13,
+                binding: 22,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_dir_buffer.as_entire_binding(),
+                buffer: edge_sort.key_buffer().as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 14,
+                binding: 23,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: node_edge_sort_range_buffer.as_entire_binding(),
-            },
-        ]);
-        graph_compute.create_compute_kernel("compute_node_edge_sort_range_2", vec![
-            ComputeBuffer {
-                binding: 0,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: uniform_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 12,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_node_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 13,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_dir_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 14,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: node_edge_sort_range_buffer.as_entire_binding(),
-            },
-        ]);
-        graph_compute.create_compute_kernel("spring_force_reduction", vec![
-            ComputeBuffer {
-                binding: 0,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: uniform_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 1,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: node_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 3,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: spring_force_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 12,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_node_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 13,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_dir_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 14,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: node_edge_sort_range_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 11,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: kernel_status_buffer.as_entire_binding(),
-            },
-        ]);
-        graph_compute.create_compute_kernel("spring_force", vec![
-            ComputeBuffer {
-                binding: 0,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: uniform_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 3,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: spring_force_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 13,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: edge_sort_dir_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 14,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: node_edge_sort_range_buffer.as_entire_binding(),
+                buffer: edge_sort.value_buffer().as_entire_binding(),
             },
         ]);
+        // 边排序相关的 Kernel 每个分块注册一次，绑定该分块的 Buffer
+        for (chunk_index, edge_chunk) in edge_chunks.iter().enumerate() {
+
+            graph_compute.create_named_compute_kernel(&chunk_kernel_name("apply_edge_sort", chunk_index), "apply_edge_sort", vec![
+                ComputeBuffer {
+                    binding: 12,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: edge_chunk.edge_sort_node_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 24,
+                    buffer_type: ComputeBufferType::StorageReadOnly,
+                    buffer: edge_sort.sorted_value_buffer().as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 25,
+                    buffer_type: ComputeBufferType::StorageReadOnly,
+                    buffer: edge_sort.sorted_key_buffer().as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 26,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: edge_chunk.chunk_buffer.as_entire_binding(),
+                },
+            ]);
+            for entry_point in ["compute_node_edge_sort_range", "compute_node_edge_sort_range_2"] {
+                graph_compute.create_named_compute_kernel(&chunk_kernel_name(entry_point, chunk_index), entry_point, vec![
+                    ComputeBuffer {
+                        binding: 12,
+                        buffer_type: ComputeBufferType::Storage,
+                        buffer: edge_chunk.edge_sort_node_buffer.as_entire_binding(),
+                    },
+                    ComputeBuffer {
+                        binding: 14,
+                        buffer_type: ComputeBufferType::Storage,
+                        buffer: node_edge_sort_range_buffer.as_entire_binding(),
+                    },
+                    ComputeBuffer {
+                        binding: 26,
+                        buffer_type: ComputeBufferType::Uniform,
+                        buffer: edge_chunk.chunk_buffer.as_entire_binding(),
+                    },
+                ]);
+            }
+            graph_compute.create_named_compute_kernel(&chunk_kernel_name("spring_force_reduction", chunk_index), "spring_force_reduction", vec![
+                ComputeBuffer {
+                    binding: 1,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: node_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 12,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: edge_chunk.edge_sort_node_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 13,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: edge_chunk.edge_sort_dir_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 14,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: node_edge_sort_range_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 26,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: edge_chunk.chunk_buffer.as_entire_binding(),
+                },
+            ]);
+            graph_compute.create_named_compute_kernel(&chunk_kernel_name("spring_force", chunk_index), "spring_force", vec![
+                ComputeBuffer {
+                    binding: 0,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: uniform_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 3,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: spring_force_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 13,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: edge_chunk.edge_sort_dir_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 14,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: node_edge_sort_range_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 26,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: edge_chunk.chunk_buffer.as_entire_binding(),
+                },
+            ]);
+        }
         graph_compute.create_compute_kernel("reduction_bounding", vec![
             ComputeBuffer {
                 binding: 0,
@@ -1057,6 +1609,11 @@ impl GraphicsResources {
                 buffer_type: ComputeBufferType::Storage,
                 buffer: bounding_buffer.as_entire_binding(),
             },
+            ComputeBuffer {
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_flag_buffer.as_entire_binding(),
+            },
         ]);
         graph_compute.create_compute_kernel("reduction_bounding_2", vec![
             ComputeBuffer {
@@ -1135,6 +1692,11 @@ impl GraphicsResources {
                 buffer_type: ComputeBufferType::Storage,
                 buffer: kernel_status_buffer.as_entire_binding(),
             },
+            ComputeBuffer {
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_flag_buffer.as_entire_binding(),
+            },
         ]);
         graph_compute.create_compute_kernel("clear_2", vec![
             ComputeBuffer {
@@ -1272,8 +1834,13 @@ impl GraphicsResources {
                 buffer_type: ComputeBufferType::Storage,
                 buffer: node_buffer.as_entire_binding(),
             },
+            ComputeBuffer {
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_flag_buffer.as_entire_binding(),
+            },
         ]);
-        graph_compute.create_compute_kernel("randomize", vec![
+        graph_compute.create_compute_kernel("validate_nodes", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
@@ -1284,8 +1851,28 @@ impl GraphicsResources {
                 buffer_type: ComputeBufferType::Storage,
                 buffer: node_buffer.as_entire_binding(),
             },
+            ComputeBuffer {
+                binding: 11,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: kernel_status_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_flag_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 27,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: last_valid_position_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 28,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: node_quarantine_buffer.as_entire_binding(),
+            },
         ]);
-        graph_compute.create_compute_kernel("copy", vec![
+        graph_compute.create_compute_kernel("randomize", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
@@ -1297,12 +1884,12 @@ impl GraphicsResources {
                 buffer: node_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 15,
-                buffer_type: ComputeBufferType::Storage,
-                buffer: node_copy_buffer.as_entire_binding(),
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_flag_buffer.as_entire_binding(),
             },
         ]);
-        graph_compute.create_compute_kernel("cal_depth", vec![
+        graph_compute.create_compute_kernel("copy", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
@@ -1314,58 +1901,104 @@ impl GraphicsResources {
                 buffer: node_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 8,
+                binding: 15,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: depth_sort_buffer.as_entire_binding(),
-            },
-            ComputeBuffer {
-                binding: 10,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: self.render_uniform_buffer.as_entire_binding(),
+                buffer: node_copy_buffer.as_entire_binding(),
             },
         ]);
-        graph_compute.create_compute_kernel("sort_by_depth", vec![
+        graph_compute.create_compute_kernel("set_position", vec![
             ComputeBuffer {
                 binding: 0,
                 buffer_type: ComputeBufferType::Uniform,
                 buffer: uniform_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 8,
+                binding: 1,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: depth_sort_buffer.as_entire_binding(),
+                buffer: node_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 9,
-                buffer_type: ComputeBufferType::Uniform,
-                buffer: depth_sort_param_buffer.as_entire_binding(),
+                binding: 15,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: node_copy_buffer.as_entire_binding(),
             },
             ComputeBuffer {
-                binding: 11,
+                binding: 27,
                 buffer_type: ComputeBufferType::Storage,
-                buffer: kernel_status_buffer.as_entire_binding(),
+                buffer: last_valid_position_buffer.as_entire_binding(),
             },
         ]);
+        if let Some(depth_sort) = &depth_sort {
+            graph_compute.create_compute_kernel("cal_depth", vec![
+                ComputeBuffer {
+                    binding: 0,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: uniform_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 1,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: node_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 10,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: self.render_uniform_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 22,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: depth_sort.key_buffer().as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 23,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: depth_sort.value_buffer().as_entire_binding(),
+                },
+            ]);
+            graph_compute.create_compute_kernel("apply_depth_sort", vec![
+                ComputeBuffer {
+                    binding: 0,
+                    buffer_type: ComputeBufferType::Uniform,
+                    buffer: uniform_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 8,
+                    buffer_type: ComputeBufferType::Storage,
+                    buffer: depth_sort_buffer.as_entire_binding(),
+                },
+                ComputeBuffer {
+                    binding: 24,
+                    buffer_type: ComputeBufferType::StorageReadOnly,
+                    buffer: depth_sort.sorted_value_buffer().as_entire_binding(),
+                },
+            ]);
+        }
 
-        let graph_resources = GraphResources {
-            status,
+        let mut graph_resources = GraphResources {
+            status: capacity.status(),
+            capacity,
+            uniforms: compute_uniform,
             uniform_buffer,
             node_buffer,
             node_copy_buffer,
+            node_flag_buffer,
             node_edge_sort_range_buffer,
             edge_buffer,
-            edge_sort_node_buffer,
-            edge_sort_dir_buffer,
+            edge_chunks,
             bounding_buffer,
             tree_buffer,
             tree_node_buffer,
             tree_child_buffer,
             depth_sort_buffer,
-            depth_sort_param_buffer,
             kernel_status_buffer,
+            last_valid_position_buffer,
+            node_quarantine_buffer,
             node_render_bind_group,
             edge_render_bind_group,
             bounding_box_render_bind_group,
+            depth_sort,
+            edge_sort,
             debugger,
             buffer_bytes: None,
             node_render_pipeline,
@@ -1373,32 +2006,40 @@ impl GraphicsResources {
             edge_render_pipeline,
             edge_cast_render_pipeline,
             bounding_box_render_pipeline,
+            oit_render_pipelines,
+            cluster: None,
             kernel_status_codes: vec![-1; KERNEL_STATUS_COUNT],
             is_kernel_error: false,
-            node_work_group_count,
-            edge_work_group_count,
-            edge_sort_work_group_count,
-            tree_node_work_group_count,
-            step_work_group_count,
-            bb_work_group_count
+            tree_scale: self.tree_scale,
+            node_work_group_count: 0,
+            edge_work_group_count: 0,
+            tree_node_work_group_count: 0,
+            step_work_group_count: 0,
+            bb_work_group_count: 0,
         };
 
+        graph_resources.set_counts(queue, status);
         self.graph_resources = Some(graph_resources);
 
         self.gen_node();
         self.prepare_cast();
         self.need_update = true;
 
+        Ok(())
     }
 
     pub fn compute(&mut self) {
 
         if self.graph_resources.is_none() { return; }
+        self.update_compute_uniforms();
+
         let graph_resources = self.graph_resources.as_ref().unwrap();
 
         let device = self.render_state.device.clone();
         let queue = self.render_state.queue.clone();
 
+        self.kernel_timer.begin_batch(TimingGroup::Compute);
+
         let mut command_encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
         command_encoder.push_debug_group("compute render movement");
@@ -1411,9 +2052,9 @@ impl GraphicsResources {
 
             Self::dispatch_compute_kernel(&self, &mut cpass, "cal_gravity_force", graph_resources.node_work_group_count);
 
-            Self::dispatch_compute_kernel(&self, &mut cpass, "spring_force_reduction", graph_resources.edge_sort_work_group_count);
+            Self::dispatch_edge_chunk_kernel(&self, &mut cpass, "spring_force_reduction", None);
 
-            Self::dispatch_compute_kernel(&self, &mut cpass, "spring_force", graph_resources.node_work_group_count);
+            Self::dispatch_edge_chunk_kernel(&self, &mut cpass, "spring_force", Some(graph_resources.node_work_group_count));
 
             Self::calc_bounding_box(&self, &mut cpass);
 
@@ -1431,59 +2072,285 @@ impl GraphicsResources {
 
             Self::dispatch_compute_kernel(&self, &mut cpass, "electron_force", graph_resources.step_work_group_count);
 
+            if let Some(cluster) = &graph_resources.cluster {
+
+                Self::dispatch_compute_kernel(&self, &mut cpass, "cluster_centroid", cluster.group_count);
+
+                Self::dispatch_compute_kernel(&self, &mut cpass, "cluster_repulsion", cluster.group_work_group_count);
+
+                Self::dispatch_compute_kernel(&self, &mut cpass, "cluster_force", graph_resources.node_work_group_count);
+            }
+
             Self::dispatch_compute_kernel(&self, &mut cpass, "main", graph_resources.node_work_group_count);
 
             Self::dispatch_compute_kernel(&self, &mut cpass, "displacement", graph_resources.node_work_group_count);
 
+            Self::dispatch_compute_kernel(&self, &mut cpass, "validate_nodes", graph_resources.node_work_group_count);
+
         }
 
         // Self::debug(self);
 
+        let timing_slot = self.kernel_timer.end_batch(&mut command_encoder);
+        self.replay_deferred(TimingGroup::Compute);
+
         // queue.submit(Some(command_encoder.finish()));
         self.compute_frame_count += 1;
         // device.poll(wgpu::Maintain::Wait);
 
         let graph_resources = self.graph_resources.as_mut().unwrap();
 
+        // 状态码异步回读，结果由 poll_kernel_status 在之后的帧中取回
         let debugger = &mut graph_resources.debugger;
-        command_encoder.copy_buffer_to_buffer(&graph_resources.kernel_status_buffer, 0, &debugger.debug_buffer, 0, debugger.buffer_size as _);
+        let slot = debugger.status_readback.copy(&mut command_encoder, &graph_resources.kernel_status_buffer, self.compute_frame_count);
+        let quarantine_slot = debugger.quarantine_readback.copy(&mut command_encoder, &graph_resources.node_quarantine_buffer, self.compute_frame_count);
         queue.submit(Some(command_encoder.finish()));
+        if let Some(slot) = slot {
+            debugger.status_readback.request(slot);
+        }
+        if let Some(slot) = quarantine_slot {
+            debugger.quarantine_readback.request(slot);
+        }
+        self.kernel_timer.request(timing_slot);
+    }
 
-        let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-        let buffer_slice = debugger.debug_buffer.slice(..);
-        buffer_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
-        device.poll(wgpu::Maintain::Wait);
+    // 按时钟计时时，compute 中的每个 dispatch 单独提交并等待完成
+    fn replay_deferred(&mut self, group: TimingGroup) {
 
-        pollster::block_on(async {
-            if let Some(Ok(())) = receiver.receive().await {
-                let data = buffer_slice.get_mapped_range();
-                let result: Vec<i32> = bytemuck::cast_slice(&data).to_vec();
+        let deferred = self.kernel_timer.take_deferred();
+        if deferred.is_empty() { return; }
 
-                let content = format!("{:?}", &result);
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
 
-                if *result.par_iter().max().unwrap() > 0 {
-                    message_error("Kernel Error", content.as_str());
-                    graph_resources.is_kernel_error = true;
-                } else {
-                    graph_resources.is_kernel_error = false;
-                    if *result.par_iter().min().unwrap() < 0 {
-                        message_warning("Kernel Warning", content.as_str());
-                    }
+        let mut durations = vec![];
+        for (kernel_name, work_group_count) in deferred {
+            let kernel = self.compute_shader.kernels.get(&kernel_name).unwrap();
+            let mut command_encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            {
+                let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+                cpass.set_pipeline(&kernel.compute_pipeline);
+                cpass.set_bind_group(0, &kernel.bind_group, &[]);
+                cpass.dispatch_workgroups(work_group_count, 1, 1);
+            }
+            let start = Instant::now();
+            queue.submit(Some(command_encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+            durations.push((kernel_name, start.elapsed().as_secs_f32() * 1000.0));
+        }
+        self.kernel_timer.push_wall_clock(group, durations);
+    }
+
+    // 取回已完成的 Kernel 状态码，不阻塞
+    // 出现错误时按状态码表自动恢复，连续恢复 MAX_KERNEL_RECOVERY_ATTEMPTS 次仍出错时停止计算
+    pub fn poll_kernel_status(&mut self, model: &DataModel) {
+
+        let device = &self.render_state.device;
+
+        self.kernel_timer.poll(device);
+        self.poll_node_quarantine(model);
+        self.poll_layout_history();
+        self.poll_metrics(model);
+
+        let device = &self.render_state.device;
+
+        let graph_resources = match self.graph_resources.as_mut() {
+            Some(graph_resources) => graph_resources,
+            None => return,
+        };
+
+        let mut recoveries = vec![];
+        let mut error_content = None;
+
+        for (frame, data) in graph_resources.debugger.status_readback.poll(device) {
+            let result: Vec<i32> = bytemuck::cast_slice(&data).to_vec();
+
+            if result.len() == KERNEL_STATUS_COUNT {
+                graph_resources.kernel_status_codes = result.clone();
+            }
+
+            if frame < self.kernel_recovery_frame { continue; }
+
+            let content = result.iter().enumerate()
+                .filter(|(_, &code)| code != 0)
+                .map(|(index, &code)| format_kernel_status(index, code))
+                .collect::<Vec<String>>()
+                .join("  \n");
+
+            if *result.par_iter().max().unwrap() > 0 {
+                for &code in result.iter().filter(|&&code| code > 0) {
+                    let recovery = describe_kernel_status(code).map_or(KernelRecovery::None, |status| status.recovery);
+                    if !recoveries.contains(&recovery) { recoveries.push(recovery); }
+                }
+                error_content = Some(content);
+            } else {
+                graph_resources.is_kernel_error = false;
+                self.kernel_recovery_attempts = 0;
+                // 隔离的节点由 poll_node_quarantine 单独报告
+                if result.iter().any(|&code| code < 0 && code != STATUS_NODES_QUARANTINED) {
+                    message_warning("Kernel Warning", content.as_str());
                 }
+            }
+        }
+
+        let content = match error_content {
+            Some(content) => content,
+            None => return,
+        };
 
-                if result.len() == KERNEL_STATUS_COUNT {
-                    graph_resources.kernel_status_codes = result;
+        let is_recoverable = recoveries.iter().all(|recovery| *recovery != KernelRecovery::None);
+        if !is_recoverable || self.kernel_recovery_attempts >= MAX_KERNEL_RECOVERY_ATTEMPTS {
+            let content = format!("{}  \nComputing stopped after {} recovery attempts", content, self.kernel_recovery_attempts);
+            message_error("Kernel Error", content.as_str());
+            graph_resources.is_kernel_error = true;
+            return;
+        }
+
+        self.kernel_recovery_attempts += 1;
+        let mut notes = vec![content];
+        for recovery in recoveries {
+            match self.recover_kernel(recovery, model) {
+                Ok(note) => notes.push(note),
+                Err(error) => {
+                    notes.push(error);
+                    message_error("Kernel Error", notes.join("  \n").as_str());
+                    self.graph_resources.as_mut().unwrap().is_kernel_error = true;
+                    return;
                 }
+            }
+        }
+        self.kernel_recovery_frame = self.compute_frame_count + 1;
+        message_warning("Kernel Recovered", notes.join("  \n").as_str());
+    }
 
-                drop(data);
-                debugger.debug_buffer.unmap();
+    // 取回 validate_nodes 隔离的节点并报告编号，不阻塞
+    // GPU 已将这些节点恢复到上一步的坐标，没有上一步的坐标或反复被隔离的节点在此按邻居重新放置
+    fn poll_node_quarantine(&mut self, model: &DataModel) {
 
+        let device = &self.render_state.device;
 
-            } else {
-                panic!("failed to run compute on gpu!")
+        let graph_resources = match self.graph_resources.as_mut() {
+            Some(graph_resources) => graph_resources,
+            None => return,
+        };
+
+        let mut reseed_nodes = BTreeSet::new();
+
+        for (frame, data) in graph_resources.debugger.quarantine_readback.poll(device) {
+            let result: Vec<u32> = bytemuck::cast_slice(&data).to_vec();
+
+            let count = result[0] as usize;
+            if count == 0 || frame < self.kernel_recovery_frame { continue; }
+
+            // 持续出现时每帧都有结果，合并到一条消息中
+            let report = self.quarantine_report.get_or_insert_with(|| QuarantineReport::new(frame));
+            report.last_frame = frame;
+            report.count += count;
+
+            for &entry in &result[1..1 + count.min(MAX_QUARANTINED_NODES)] {
+                let index = entry & !QUARANTINE_NO_PREVIOUS;
+                let times = self.quarantine_counts.entry(index).or_insert(0);
+                *times += 1;
+                if entry & QUARANTINE_NO_PREVIOUS != 0 || *times >= QUARANTINE_RESEED_COUNT {
+                    reseed_nodes.insert(index);
+                }
+                report.node_ids.insert(index);
             }
-        });
+        }
+
+        if self.quarantine_report.as_ref().is_some_and(|report| report.start.elapsed() >= QUARANTINE_REPORT_INTERVAL) {
+            let report = self.quarantine_report.take().unwrap();
+            message_warning("Nodes Quarantined", report.content().as_str());
+        }
+
+        if reseed_nodes.is_empty() { return; }
+
+        // Multilevel 的粗化层中编号与原图不对应，只由 GPU 恢复
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) if self.is_node_editable() => source_target_list,
+            _ => return,
+        };
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+        let seed = self.compute_options.seed ^ self.compute_frame_count;
+        let mut positions = self.read_positions();
+        let positions = &mut positions[..node_count * 3];
+        reseed_near_neighbors(positions, source_target_list, &reseed_nodes, seed);
+        self.set_positions(positions);
+
+        for index in &reseed_nodes {
+            self.quarantine_counts.remove(index);
+        }
+        let node_ids = reseed_nodes.iter().map(|index| index.to_string()).collect::<Vec<String>>().join(", ");
+        message_warning("Nodes Re-seeded", format!("Placed nodes near their neighbors after repeated NaN values  \nNodes: {}", node_ids).as_str());
+    }
+
+    // 按状态码表中的恢复方式处理，返回说明
+    fn recover_kernel(&mut self, recovery: KernelRecovery, model: &DataModel) -> Result<String, String> {
+
+        let seed = self.compute_options.seed ^ self.compute_frame_count;
+
+        match recovery {
+            // 隔离的节点由 poll_node_quarantine 处理
+            KernelRecovery::None => Ok(String::new()),
+            KernelRecovery::SpreadNodes => {
+                if !self.is_node_editable() {
+                    return Err(String::from("Cannot move nodes while the multilevel layout is on a coarse level"));
+                }
+                let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+                let mut positions = self.read_positions();
+                let positions = &mut positions[..node_count * 3];
+                let note = format!("Spread {} nodes that were too close to each other", spread_coincident_nodes(positions, seed));
+                self.set_positions(positions);
+                Ok(note)
+            }
+            KernelRecovery::GrowTree => {
+                let tree_scale = self.tree_scale * 2;
+                let node_count = self.graph_resources.as_ref().unwrap().capacity.node_count as u64;
+                let tree_buffer_size = 4 * ((get_tree_node_count(&(node_count as u32), tree_scale) as u64 + 1) * 8);
+                if tree_scale > MAX_TREE_SCALE || node_count * tree_scale as u64 >= u32::MAX as u64
+                    || tree_buffer_size > self.memory_planner().max_binding_size() {
+                    return Err(String::from("The tree buffer cannot be enlarged any further"));
+                }
+                self.tree_scale = tree_scale;
+
+                // Multilevel 的粗化层在进入下一层时使用更大的八叉树
+                if !self.is_node_editable() {
+                    return Ok(format!("Tree buffer will use {}x nodes from the next level", tree_scale));
+                }
+
+                let source_target_list = match &model.source_target_list {
+                    Some(source_target_list) => source_target_list,
+                    None => return Err(String::from("No edge data")),
+                };
+                let positions = self.read_positions();
+                let render_state = self.render_state.clone();
+                let capacity = self.graph_resources.as_ref().unwrap().capacity;
+                self.init_graph(render_state, model.status.clone(), capacity, source_target_list)
+                    .map_err(|error| error.to_string())?;
+                self.set_positions(&positions);
+                self.upload_node_flags();
+                self.upload_cluster_groups();
+                Ok(format!("Enlarged the tree buffer to {}x nodes", tree_scale))
+            }
+        }
+    }
+
+    // 将当前帧号和 Compute Options 写入 Compute Uniforms
+    fn update_compute_uniforms(&mut self) {
+
+        let graph_resources = self.graph_resources.as_mut().unwrap();
+
+        let uniforms = &mut graph_resources.uniforms;
+        uniforms.frame_num = self.compute_frame_count;
+        uniforms.seed = self.compute_options.seed;
+        uniforms.is_deterministic = self.compute_options.is_deterministic as u32;
+        uniforms.cluster_group_count = graph_resources.cluster.as_ref().map_or(0, |cluster| cluster.group_count);
+        uniforms.cluster_attraction = self.compute_options.cluster_attraction;
+        uniforms.cluster_repulsion = self.compute_options.cluster_repulsion;
 
+        self.render_state.queue.write_buffer(&graph_resources.uniform_buffer, 0, bytemuck::cast_slice(&[*uniforms]));
     }
 
     pub fn debug<'a>(&'a mut self) {
@@ -1554,30 +2421,954 @@ impl GraphicsResources {
             if bound_range <= 1 { break; }
             bound_range = ((bound_range as f32) / (PARTICLES_PER_GROUP as f32)).ceil() as u32;
 
-        }
-    }
+        }
+    }
+
+    pub fn dispatch_compute_kernel<'a>(&'a self, cpass: &mut ComputePass<'a>, kernel_name: &str, work_group_count: u32) {
+        // 按时钟计时时由 replay_deferred 逐个提交
+        if self.kernel_timer.defer_dispatch(kernel_name, work_group_count) { return; }
+        let query = self.kernel_timer.begin_query(kernel_name);
+        if let Some((query_set, index)) = query { cpass.write_timestamp(query_set, index); }
+        cpass.set_pipeline(&self.compute_shader.kernels.get(kernel_name).unwrap().compute_pipeline);
+        cpass.set_bind_group(0, &self.compute_shader.kernels.get(kernel_name).unwrap().bind_group, &[]);
+        cpass.dispatch_workgroups(work_group_count, 1, 1);
+        if let Some((query_set, index)) = query { cpass.write_timestamp(query_set, index + 1); }
+    }
+
+    // 对每个非空的边排序分块 dispatch 一次，未指定线程组数时按分块的元素数计算
+    fn dispatch_edge_chunk_kernel<'a>(&'a self, cpass: &mut ComputePass<'a>, kernel_name: &str, work_group_count: Option<u32>) {
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+        for (chunk_index, edge_chunk) in graph_resources.edge_chunks.iter().enumerate() {
+            if edge_chunk.chunk.count == 0 { continue; }
+            let work_group_count = work_group_count.unwrap_or(
+                (edge_chunk.chunk.count + PARTICLES_PER_GROUP - 1) / PARTICLES_PER_GROUP
+            );
+            Self::dispatch_compute_kernel(self, cpass, &chunk_kernel_name(kernel_name, chunk_index), work_group_count);
+        }
+    }
+
+
+    pub fn gen_node(&mut self) {
+
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
+
+        let graph_resources = &self.graph_resources.as_ref().unwrap();
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.push_debug_group("Gen Node");
+        {
+            // compute pass
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            Self::dispatch_compute_kernel(&self, &mut cpass, "gen_node", graph_resources.node_work_group_count);
+
+            Self::dispatch_compute_kernel(&self, &mut cpass, "cal_mass", graph_resources.edge_work_group_count);
+
+            Self::calc_bounding_box(&self, &mut cpass);
+        }
+        command_encoder.pop_debug_group();
+        queue.submit(Some(command_encoder.finish()));
+
+        self.sort_edges();
+
+        self.compute_frame_count += 1;
+    }
+
+    // 按起点排序边并计算每个节点的边区间，排序与前后的 Kernel 在同一次提交中完成
+    // 调用前须清空 Node Edge Sort Range Buffer
+    fn sort_edges(&self) {
+
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
+
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            Self::dispatch_compute_kernel(&self, &mut cpass, "prepare_edge_sort", graph_resources.edge_work_group_count);
+        }
+        graph_resources.edge_sort.encode(&mut command_encoder);
+        {
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            Self::dispatch_edge_chunk_kernel(&self, &mut cpass, "apply_edge_sort", None);
+        }
+        command_encoder.push_debug_group("compute_node_edge_sort_range");
+        {
+            // compute pass
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+
+            // 所有分块写入区间之后再逐块收缩
+            Self::dispatch_edge_chunk_kernel(&self, &mut cpass, "compute_node_edge_sort_range", None);
+            Self::dispatch_edge_chunk_kernel(&self, &mut cpass, "compute_node_edge_sort_range_2", None);
+        }
+        command_encoder.pop_debug_group();
+        queue.submit(Some(command_encoder.finish()));
+    }
+
+    pub fn randomize(&mut self) {
+
+        self.update_compute_uniforms();
+
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
+
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        command_encoder.push_debug_group("randomize render position");
+        {
+            // compute pass
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+
+            Self::dispatch_compute_kernel(&self, &mut cpass, "randomize", graph_resources.node_work_group_count);
+
+            Self::dispatch_compute_kernel(&self, &mut cpass, "copy", graph_resources.node_work_group_count);
+
+            Self::calc_bounding_box(&self, &mut cpass);
+        }
+        command_encoder.pop_debug_group();
+        queue.submit(Some(command_encoder.finish()));
+        self.compute_frame_count += 1;
+    }
+
+    // 执行一次 Continuous 计算方法
+    pub fn iterate(&mut self, model: &DataModel) {
+
+        if self.graph_resources.is_none() { return; }
+
+        for _ in 0..self.compute_options.iterations_per_frame.clamp(1, MAX_ITERATIONS_PER_FRAME) {
+            if model.node_settings.position_compute == ComputeMethod::MULTILEVEL {
+                self.multilevel_step(model);
+            } else {
+                self.cancel_multilevel(model);
+                self.compute();
+            }
+        }
+
+        if self.geo_projection.is_some() {
+            self.set_geo_projection(None);
+        }
+    }
+
+    // Multilevel 布局的一次迭代
+    // 从最粗一层开始，每层运行 iterations_per_level 次 Force Atlas 2，再将坐标延拓到更细一层
+    // 到达原图后保留进度，之后与 Force Atlas 2 相同
+    fn multilevel_step(&mut self, model: &DataModel) {
+
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+
+        let mut multilevel = match self.multilevel.take() {
+            Some(multilevel) => multilevel,
+            None => {
+                let start_time = Utc::now().timestamp_millis();
+                let multilevel = MultilevelLayout::new(model.status.node_count, source_target_list);
+                let content = format!(
+                    "Built {} levels in {} ms",
+                    multilevel.level_count(),
+                    Utc::now().timestamp_millis() - start_time
+                );
+                message_info("Multilevel Layout", content.as_str());
+
+                self.init_level(model, &multilevel, multilevel.current_level);
+                self.multilevel = Some(multilevel);
+                return;
+            }
+        };
+
+        self.compute();
+
+        if multilevel.current_level > 0 {
+            multilevel.iteration += 1;
+
+            if multilevel.iteration >= multilevel.iterations_per_level {
+                let level = multilevel.current_level;
+                let positions = multilevel.prolong(level, &self.read_positions());
+
+                multilevel.current_level -= 1;
+                multilevel.iteration = 0;
+
+                self.init_level(model, &multilevel, level - 1);
+                self.set_positions(&positions);
+
+                if multilevel.current_level == 0 {
+                    message_info("Multilevel Layout", "Reached the original graph");
+                }
+            }
+        }
+
+        self.multilevel = Some(multilevel);
+    }
+
+    // 切换到其他计算方法时，将当前层坐标直接逐层延拓回原图
+    fn cancel_multilevel(&mut self, model: &DataModel) {
+
+        let multilevel = match self.multilevel.take() {
+            Some(multilevel) => multilevel,
+            None => return,
+        };
+
+        if multilevel.current_level == 0 { return; }
+
+        let mut positions = self.read_positions();
+        for level in (1..=multilevel.current_level).rev() {
+            positions = multilevel.prolong(level, &positions);
+        }
+
+        self.init_level(model, &multilevel, 0);
+        self.set_positions(&positions);
+    }
+
+    // 以第 level 层的图重建计算和绘图资源，第 0 层为原图
+    fn init_level(&mut self, model: &DataModel, multilevel: &MultilevelLayout, level: usize) {
+
+        let render_state = self.render_state.clone();
+
+        if level == 0 {
+            if let Some(source_target_list) = &model.source_target_list {
+                let capacity = GraphCapacity::exact(&model.status);
+                match self.init_graph(render_state, model.status.clone(), capacity, source_target_list) {
+                    Ok(()) => {
+                        self.upload_node_flags();
+                        self.upload_cluster_groups();
+                    }
+                    Err(error) => message_error("Graph Too Large for GPU", &error.to_string()),
+                }
+            }
+        } else {
+            let coarse = &multilevel.levels[level];
+            let status = GraphicsStatus {
+                node_count: coarse.node_count,
+                edge_count: coarse.edge_count,
+                ..Default::default()
+            };
+            // 粗化图不大于原图，原图已通过检查
+            let capacity = GraphCapacity::exact(&status);
+            if let Err(error) = self.init_graph(render_state, status, capacity, &coarse.source_target_list) {
+                message_error("Graph Too Large for GPU", &error.to_string());
+            }
+        }
+    }
+
+    // 读取全部节点坐标（每个节点 x / y / z）
+    pub fn read_positions(&mut self) -> Vec<f32> {
+
+        self.debug();
+
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+        graph_resources.buffer_bytes.as_ref().unwrap()
+            .par_chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect()
+    }
+
+    // 执行一次 OneStep 计算方法
+    pub fn dispatch(&mut self, model: &DataModel) {
+
+        if self.graph_resources.is_none() { return; }
+
+        self.cancel_multilevel(model);
+        self.set_geo_projection(None);
+
+        let method = &model.node_settings.position_compute;
+
+        if *method == ComputeMethod::SPECTRAL {
+            self.spectral(model);
+        } else if *method == ComputeMethod::RADIAL_TREE {
+            self.tree(model, TreeStyle::Radial);
+        } else if *method == ComputeMethod::TOP_DOWN_TREE {
+            self.tree(model, TreeStyle::TopDown);
+        } else if *method == ComputeMethod::GEOGRAPHIC {
+            self.geographic(model);
+        } else {
+            self.randomize();
+        }
+    }
+
+    // Spectral 布局在 CPU 上计算，完成后整体上传坐标
+    pub fn spectral(&mut self, model: &DataModel) {
+
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+
+        let start_time = Utc::now().timestamp_millis();
+        let positions = spectral_layout(node_count, source_target_list);
+        let content = format!("Finished in {} ms", Utc::now().timestamp_millis() - start_time);
+
+        self.set_positions(&positions);
+        message_info("Spectral Layout", content.as_str());
+    }
+
+    // 将当前布局中的连通分量紧凑排列，分量内部的几何形状不变
+    pub fn pack_components(&mut self, model: &DataModel) {
+
+        if !self.is_node_editable() {
+            message_warning("Pack Components", "Wait until the multilevel layout reaches the original graph");
+            return;
+        }
+
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+        let positions = self.read_positions();
+
+        let start_time = Utc::now().timestamp_millis();
+        let (positions, component_count) = pack_components(node_count, source_target_list, &positions[..node_count * 3]);
+        let content = format!("{} components, finished in {} ms", component_count, Utc::now().timestamp_millis() - start_time);
+
+        self.set_positions(&positions);
+        self.set_geo_projection(None);
+        message_info("Pack Components", content.as_str());
+    }
+
+    // 在当前坐标上计算布局质量指标，并记录到历史中
+    // 阻塞 UI 线程，只用于手动计算
+    pub fn evaluate_metrics(&mut self, model: &DataModel) {
+
+        if !self.is_node_editable() { return; }
+
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+        let positions = self.read_positions();
+
+        let metrics = layout_metrics(node_count, source_target_list, &positions[..node_count * 3]);
+        self.metrics_history.push(self.compute_frame_count, metrics);
+    }
+
+    // 开启自动计算时，每隔 interval 个计算帧发起一次坐标回读，不阻塞 UI 线程
+    // 结果由 poll_metrics 取回后交给工作线程计算指标
+    pub fn update_metrics(&mut self) {
+
+        if !self.is_node_editable() || !self.metrics_history.is_due(self.compute_frame_count) { return; }
+        if self.graph_resources.as_ref().unwrap().status.node_count == 0 { return; }
+        // 上一次的指标还在计算
+        if self.metrics_receiver.is_some() { return; }
+
+        let device = self.render_state.device.clone();
+        let queue = self.render_state.queue.clone();
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.dispatch_copy(&mut command_encoder);
+
+        let graph_resources = self.graph_resources.as_mut().unwrap();
+        let size = (graph_resources.status.node_count * 3 * 4) as wgpu::BufferAddress;
+        let readback = graph_resources.debugger.metrics_readback.get_or_insert_with(||
+            ReadbackRing::new(&device, size, METRICS_READBACK_SLOT_COUNT, "Metrics Readback Buffer")
+        );
+
+        let slot = match readback.copy(&mut command_encoder, &graph_resources.node_copy_buffer, self.compute_frame_count) {
+            Some(slot) => slot,
+            None => return,
+        };
+        queue.submit(Some(command_encoder.finish()));
+        readback.request(slot);
+        self.metrics_history.request(self.compute_frame_count);
+    }
+
+    // 取回已完成的坐标回读并在工作线程上计算指标，取回已算完的指标，都不阻塞
+    fn poll_metrics(&mut self, model: &DataModel) {
+
+        if let Some(receiver) = &self.metrics_receiver {
+            match receiver.try_recv() {
+                Ok((frame, metrics)) => {
+                    self.metrics_history.push(frame, metrics);
+                    self.metrics_receiver = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.metrics_receiver = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+
+        let device = &self.render_state.device;
+
+        let graph_resources = match self.graph_resources.as_mut() {
+            Some(graph_resources) => graph_resources,
+            None => return,
+        };
+        let readback = match graph_resources.debugger.metrics_readback.as_mut() {
+            Some(readback) => readback,
+            None => return,
+        };
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+
+        // 暂存 Buffer 只有一个，最多取回一次
+        if let Some((frame, data)) = readback.poll(device).pop() {
+            let source_target_list = source_target_list.clone();
+            let (sender, receiver) = mpsc::channel();
+            rayon::spawn(move || {
+                let positions: Vec<f32> = data.par_chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect();
+                let metrics = layout_metrics(positions.len() / 3, &source_target_list, &positions);
+                let _ = sender.send((frame, metrics));
+            });
+            self.metrics_receiver = Some(receiver);
+        }
+    }
+
+    // 将节点坐标写入 node_copy_buffer，供回读使用
+    // 不经过 dispatch_compute_kernel，回读不计入 Kernel 计时
+    fn dispatch_copy(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+        let kernel = self.compute_shader.kernels.get("copy").unwrap();
+        let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        cpass.set_pipeline(&kernel.compute_pipeline);
+        cpass.set_bind_group(0, &kernel.bind_group, &[]);
+        cpass.dispatch_workgroups(graph_resources.node_work_group_count, 1, 1);
+    }
+
+    // 开启记录时，每隔 interval 个计算帧发起一次坐标回读，不阻塞 UI 线程
+    // 结果由 poll_layout_history 在之后的帧中取回并保存为快照
+    pub fn update_layout_history(&mut self) {
+
+        if !self.is_node_editable() || !self.layout_history.is_due(self.compute_frame_count) { return; }
+        if self.graph_resources.as_ref().unwrap().status.node_count == 0 { return; }
+
+        let device = self.render_state.device.clone();
+        let queue = self.render_state.queue.clone();
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.dispatch_copy(&mut command_encoder);
+
+        let graph_resources = self.graph_resources.as_mut().unwrap();
+        let size = (graph_resources.status.node_count * 3 * 4) as wgpu::BufferAddress;
+        let readback = graph_resources.debugger.layout_readback.get_or_insert_with(||
+            ReadbackRing::new(&device, size, LAYOUT_READBACK_SLOT_COUNT, "Layout History Readback Buffer")
+        );
+
+        // 暂存 Buffer 都在等待时跳过本次记录，下一帧再试
+        let slot = match readback.copy(&mut command_encoder, &graph_resources.node_copy_buffer, self.compute_frame_count) {
+            Some(slot) => slot,
+            None => return,
+        };
+        queue.submit(Some(command_encoder.finish()));
+        readback.request(slot);
+        self.layout_history.request(self.compute_frame_count);
+    }
+
+    // 取回已完成的坐标回读并保存为快照，不阻塞
+    fn poll_layout_history(&mut self) {
+
+        let device = &self.render_state.device;
+
+        let graph_resources = match self.graph_resources.as_mut() {
+            Some(graph_resources) => graph_resources,
+            None => return,
+        };
+        let readback = match graph_resources.debugger.layout_readback.as_mut() {
+            Some(readback) => readback,
+            None => return,
+        };
+
+        for (frame, data) in readback.poll(device) {
+            let positions: Vec<f32> = data.par_chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+            self.layout_history.push(frame, &positions);
+        }
+    }
+
+    // 将坐标恢复到某个快照，帧计数器一并恢复，之后继续计算时从该状态开始
+    pub fn restore_snapshot(&mut self, index: usize) -> Result<(), String> {
+
+        if !self.is_node_editable() {
+            return Err(String::from("Wait until the multilevel layout reaches the original graph"));
+        }
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+        let snapshot = match self.layout_history.snapshots.get(index) {
+            Some(snapshot) => snapshot,
+            None => return Err(String::from("Snapshot no longer exists")),
+        };
+        if snapshot.node_count() != node_count {
+            return Err(format!("Snapshot has {} nodes, but the graph now has {}", snapshot.node_count(), node_count));
+        }
+
+        let frame = snapshot.frame;
+        let positions = snapshot.decode();
+        // 尚未取回的回读属于被替换的坐标
+        let debugger = &mut self.graph_resources.as_mut().unwrap().debugger;
+        debugger.layout_readback = None;
+        debugger.metrics_readback = None;
+        self.metrics_receiver = None;
+        self.set_positions(&positions);
+        self.set_geo_projection(None);
+        self.compute_frame_count = frame;
+        self.kernel_recovery_frame = 0;
+        self.layout_history.selected = Some(index);
+        self.need_update = true;
+        Ok(())
+    }
+
+    // 按经纬度属性投影到球面或平面
+    pub fn geographic(&mut self, model: &DataModel) {
+
+        let (latitude, longitude, projection) = &model.node_settings.position_geo;
+        let node_data = &model.node_data;
+
+        let (latitude, longitude) = match (node_data.headers_str_index.get(latitude), node_data.headers_str_index.get(longitude)) {
+            (Some(&latitude), Some(&longitude)) => (latitude, longitude),
+            _ => {
+                message_warning("Geographic Layout", "Choose the latitude and longitude columns first");
+                return;
+            }
+        };
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+        let column = |index: usize| node_data.data.iter().map(move |row| row.get(index).map_or("", String::as_str));
+
+        let (positions, invalid_count) = geo_layout(node_count, column(latitude), column(longitude), *projection);
+
+        self.set_positions(&positions);
+        self.set_geo_projection(Some(*projection));
+
+        if invalid_count > 0 {
+            let content = format!("{} nodes without a valid latitude / longitude are placed at the origin", invalid_count);
+            message_warning("Geographic Layout", content.as_str());
+        } else {
+            message_info("Geographic Layout", format!("Projection: {}", projection).as_str());
+        }
+    }
+
+    pub fn set_geo_projection(&mut self, geo_projection: Option<GeoProjection>) {
+        self.geo_projection = geo_projection;
+        self.need_update = true;
+    }
+
+    // transform.geo，x 为投影方式（0 表示非 Geographic 布局），y 为每条边的分段数
+    fn generate_geo_uniform(&self) -> [f32; 4] {
+        match self.geo_projection {
+            Some(projection) => {
+                let segment_count = if self.render_options.is_great_circle_edge { EDGE_ARC_SEGMENT_COUNT } else { 1 };
+                [projection.shader_code() as f32, segment_count as f32, 0.0, 0.0]
+            }
+            None => [0.0, 1.0, 0.0, 0.0],
+        }
+    }
+
+    fn edge_segment_count(&self) -> u32 {
+        self.geo_uniform[1] as u32
+    }
+
+    // 树布局在 CPU 上计算，完成后整体上传坐标
+    pub fn tree(&mut self, model: &DataModel, style: TreeStyle) {
+
+        let source_target_list = match &model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+
+        let node_count = self.graph_resources.as_ref().unwrap().status.node_count;
+
+        let start_time = Utc::now().timestamp_millis();
+        let positions = tree_layout(node_count, source_target_list, self.compute_options.tree_root, style);
+        let content = format!("Finished in {} ms", Utc::now().timestamp_millis() - start_time);
+
+        self.set_positions(&positions);
+        message_info("Tree Layout", content.as_str());
+    }
+
+    // 将 CPU 端计算的坐标（每个节点 x / y / z）写入 Node Buffer
+    // 先写入 Node Copy Buffer，再由 set_position kernel 复制到 Node 结构体中，并清空受力
+    pub fn set_positions(&mut self, positions: &[f32]) {
+
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
+
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+
+        queue.write_buffer(&graph_resources.node_copy_buffer, 0, bytemuck::cast_slice(positions));
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.push_debug_group("set position");
+        {
+            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+
+            Self::dispatch_compute_kernel(&self, &mut cpass, "set_position", graph_resources.node_work_group_count);
+
+            Self::calc_bounding_box(&self, &mut cpass);
+        }
+        command_encoder.pop_debug_group();
+        queue.submit(Some(command_encoder.finish()));
+        self.compute_frame_count += 1;
+    }
+
+    // Multilevel 处于粗化层时，GPU 上的节点编号与原图不对应，不能固定或拖动节点
+    pub fn is_node_editable(&self) -> bool {
+        self.graph_resources.is_some() && self.multilevel.as_ref().map_or(true, |multilevel| multilevel.current_level == 0)
+    }
+
+    pub fn set_node_pinned(&mut self, index: u32, is_pinned: bool) {
+
+        if is_pinned {
+            self.pinned_nodes.insert(index);
+        } else {
+            self.pinned_nodes.remove(&index);
+        }
+
+        if !self.is_node_editable() { return; }
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+        if index as usize >= graph_resources.status.node_count { return; }
+
+        let flag = self.node_flag(index);
+        self.render_state.queue.write_buffer(&graph_resources.node_flag_buffer, index as u64 * 4, bytemuck::cast_slice(&[flag]));
+    }
+
+    fn node_flag(&self, index: u32) -> u32 {
+        let mut flag = 0;
+        if self.pinned_nodes.contains(&index) { flag |= NODE_FLAG_PINNED; }
+        if self.hidden_nodes.contains(&index) || self.inactive_nodes.get(index as usize) == Some(&true) { flag |= NODE_FLAG_HIDDEN; }
+        flag
+    }
+
+    pub fn clear_pinned_nodes(&mut self) {
+        self.pinned_nodes.clear();
+        self.upload_node_flags();
+    }
+
+    // 按 pinned_nodes 与 hidden_nodes 重写整个 Node Flag Buffer
+    fn upload_node_flags(&self) {
+
+        if !self.is_node_editable() { return; }
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+
+        let mut flags = vec![0u32; graph_resources.status.node_count];
+        for &index in &self.pinned_nodes {
+            if let Some(flag) = flags.get_mut(index as usize) {
+                *flag |= NODE_FLAG_PINNED;
+            }
+        }
+        for &index in &self.hidden_nodes {
+            if let Some(flag) = flags.get_mut(index as usize) {
+                *flag |= NODE_FLAG_HIDDEN;
+            }
+        }
+        for (flag, _) in flags.iter_mut().zip(&self.inactive_nodes).filter(|(_, &is_inactive)| is_inactive) {
+            *flag |= NODE_FLAG_HIDDEN;
+        }
+
+        self.render_state.queue.write_buffer(&graph_resources.node_flag_buffer, 0, bytemuck::cast_slice(&flags));
+    }
+
+    pub fn set_cluster_groups(&mut self, cluster_groups: Option<ClusterGroups>) {
+        self.cluster_groups = cluster_groups;
+        self.upload_cluster_groups();
+    }
+
+    // 将节点分组上传到 GPU，并创建 Cluster 相关的 kernel
+    // Multilevel 处于粗化层时分组与节点编号不对应，暂不启用
+    fn upload_cluster_groups(&mut self) {
+
+        let is_node_editable = self.is_node_editable();
+
+        let graph_resources = match self.graph_resources.as_mut() {
+            Some(graph_resources) => graph_resources,
+            None => return,
+        };
+        graph_resources.cluster = None;
+
+        let groups = match &self.cluster_groups {
+            Some(groups) if is_node_editable && groups.group_count > 0 => groups,
+            _ => return,
+        };
+        if groups.node_group.len() != graph_resources.status.node_count { return; }
+
+        let device = &self.render_state.device;
+
+        let node_group_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Node Group Buffer"),
+            contents: bytemuck::cast_slice(&groups.node_group),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let group_member_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Group Member Buffer"),
+            contents: bytemuck::cast_slice(&groups.members),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let group_range_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Group Range Buffer"),
+            contents: bytemuck::cast_slice(&groups.ranges),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        // 每组一个 vec4，xyz 为质心，w 为节点数
+        let group_centroid_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Group Centroid Buffer"),
+            size: (groups.group_count * 4 * 4) as _,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+
+        let group_force_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Group Force Buffer"),
+            size: (groups.group_count * 4 * 4) as _,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+
+        let graph_compute = &mut self.compute_shader;
+
+        graph_compute.create_compute_kernel("cluster_centroid", vec![
+            ComputeBuffer {
+                binding: 1,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: graph_resources.node_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: graph_resources.node_flag_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 18,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: group_member_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 19,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: group_range_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 20,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: group_centroid_buffer.as_entire_binding(),
+            },
+        ]);
+        graph_compute.create_compute_kernel("cluster_repulsion", vec![
+            ComputeBuffer {
+                binding: 0,
+                buffer_type: ComputeBufferType::Uniform,
+                buffer: graph_resources.uniform_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 20,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: group_centroid_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 21,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: group_force_buffer.as_entire_binding(),
+            },
+        ]);
+        graph_compute.create_compute_kernel("cluster_force", vec![
+            ComputeBuffer {
+                binding: 0,
+                buffer_type: ComputeBufferType::Uniform,
+                buffer: graph_resources.uniform_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 1,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: graph_resources.node_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 16,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: graph_resources.node_flag_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 17,
+                buffer_type: ComputeBufferType::StorageReadOnly,
+                buffer: node_group_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 20,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: group_centroid_buffer.as_entire_binding(),
+            },
+            ComputeBuffer {
+                binding: 21,
+                buffer_type: ComputeBufferType::Storage,
+                buffer: group_force_buffer.as_entire_binding(),
+            },
+        ]);
+
+        let group_count = groups.group_count as u32;
+
+        graph_resources.cluster = Some(ClusterResources {
+            node_group_buffer,
+            group_member_buffer,
+            group_range_buffer,
+            group_centroid_buffer,
+            group_force_buffer,
+            group_count,
+            group_work_group_count: (group_count as f32 / PARTICLES_PER_GROUP as f32).ceil() as u32,
+        });
+    }
+
+    // 增量修改图，model 中的边表与边数据同步修改
+    // 不超出容量时只重写 Edge Buffer 与数量并保留其余资源，超出时按倍数扩大容量重建资源，坐标均保留
+    pub fn apply_edits(&mut self, model: &mut DataModel, edits: &[GraphEdit]) -> Result<(), String> {
+
+        if !self.is_node_editable() {
+            return Err(String::from("Wait until the multilevel layout reaches the original graph"));
+        }
+        if model.source_target_list.is_none() {
+            return Err(String::from("The edge list has been released"));
+        }
+
+        // 时间轴只把活动的边写入 GPU，以 model 中的完整边表为准
+        let old_status = model.status.clone();
+        let old_capacity = self.graph_resources.as_ref().unwrap().capacity;
+
+        // 修改之前按数量的上界检查容量，超出 GPU 的限制时不修改任何数据
+        let upper_status = GraphicsStatus {
+            node_count: edits.iter().fold(old_status.node_count, |node_count, edit| match *edit {
+                GraphEdit::AddNodes(count) => node_count + count as usize,
+                GraphEdit::AddEdge(source, target) => node_count.max(source.max(target) as usize + 1),
+                _ => node_count,
+            }),
+            edge_count: old_status.edge_count + edits.iter().filter(|edit| matches!(edit, GraphEdit::AddEdge(..))).count(),
+            ..Default::default()
+        };
+        let capacity = old_capacity.grow(&upper_status);
+        if capacity != old_capacity {
+            plan_graph_memory(&self.memory_planner(), &capacity.status(), mem::size_of::<Node>())
+                .map_err(|error| error.to_string())?;
+        }
+
+        // 第一次删除时才建立边的索引，只有添加的批次不需要遍历边表
+        let mut edge_index: Option<EdgeIndex> = None;
+        let mut node_count = old_status.node_count;
+        for edit in edits {
+            match *edit {
+                GraphEdit::AddNodes(count) => node_count += count as usize,
+                GraphEdit::AddEdge(source, target) => {
+                    push_edge(model, source, target);
+                    if let Some(edge_index) = edge_index.as_mut() {
+                        edge_index.push(source, target);
+                    }
+                    node_count = node_count.max(source.max(target) as usize + 1);
+                    self.hidden_nodes.remove(&source);
+                    self.hidden_nodes.remove(&target);
+                }
+                GraphEdit::RemoveEdge(source, target) => {
+                    edge_index.get_or_insert_with(|| EdgeIndex::new(model.source_target_list.as_ref().unwrap()))
+                        .remove_edge(source, target);
+                }
+                GraphEdit::RemoveNode(index) => {
+                    if index as usize >= node_count { continue; }
+                    edge_index.get_or_insert_with(|| EdgeIndex::new(model.source_target_list.as_ref().unwrap()))
+                        .remove_node(index);
+                    self.hidden_nodes.insert(index);
+                    self.pinned_nodes.remove(&index);
+                }
+            }
+        }
+        if let Some(edge_index) = edge_index {
+            remove_edges(model, &edge_index.is_removed);
+        }
+
+        model.max_id = model.max_id.max(node_count.saturating_sub(1) as u32);
+        model.status.node_count = node_count;
+        model.status.edge_count = model.source_target_list.as_ref().unwrap().len() / 2;
+        model.status.edge_data_length = model.edge_data.data.len();
+
+        let status = model.status.clone();
+        let source_target_list = model.source_target_list.as_ref().unwrap();
+        let new_positions: Vec<[f32; 3]> = (old_status.node_count..status.node_count)
+            .map(|index| initial_position(self.compute_options.seed, index as u32))
+            .collect();
+
+        if old_capacity.fits(&status) {
+
+            let queue = self.render_state.queue.clone();
+            let graph_resources = self.graph_resources.as_mut().unwrap();
+            queue.write_buffer(&graph_resources.edge_buffer, 0, bytemuck::cast_slice(source_target_list));
+            graph_resources.set_counts(&queue, status);
+
+            // 新节点直接写入 Node 结构体，质量由 cal_mass 重新计算
+            let node_stride = pad_size(mem::size_of::<Node>(), 1);
+            let nodes: Vec<Node> = new_positions.iter().map(|position| Node::new(*position)).collect();
+            queue.write_buffer(&graph_resources.node_buffer, old_status.node_count as u64 * node_stride, bytemuck::cast_slice(&nodes));
+
+            self.upload_node_flags();
+            self.refresh_edges();
+        } else {
+
+            let mut positions = self.read_positions();
+            positions.extend(new_positions.iter().flatten());
+
+            let render_state = self.render_state.clone();
+            let capacity = old_capacity.grow(&status);
+            self.init_graph(render_state, status, capacity, source_target_list)
+                .map_err(|error| error.to_string())?;
+            self.set_positions(&positions);
+            self.upload_node_flags();
+        }
+
+        // 节点数变化后原有的分组不再对应，由 upload_cluster_groups 丢弃
+        self.upload_cluster_groups();
+        self.multilevel = None;
+        self.need_update = true;
+
+        Ok(())
+    }
+
+    // 只让时间轴窗口内的边参与计算和绘制，窗口外的节点隐藏，Buffer 的容量不变
+    pub fn apply_timeline(&mut self, active_edges: &[u32], inactive_nodes: Vec<bool>) {
+
+        if !self.is_node_editable() { return; }
+
+        let queue = self.render_state.queue.clone();
+        let graph_resources = self.graph_resources.as_mut().unwrap();
+        let status = GraphicsStatus {
+            edge_count: active_edges.len() / 2,
+            ..graph_resources.status.clone()
+        };
+        if !graph_resources.capacity.fits(&status) { return; }
+
+        queue.write_buffer(&graph_resources.edge_buffer, 0, bytemuck::cast_slice(active_edges));
+        graph_resources.set_counts(&queue, status);
 
-    pub fn dispatch_compute_kernel<'a>(&'a self, cpass: &mut ComputePass<'a>, kernel_name: &'a str, work_group_count: u32) {
-        cpass.set_pipeline(&self.compute_shader.kernels.get(kernel_name).unwrap().compute_pipeline);
-        cpass.set_bind_group(0, &self.compute_shader.kernels.get(kernel_name).unwrap().bind_group, &[]);
-        cpass.dispatch_workgroups(work_group_count, 1, 1);
+        self.inactive_nodes = inactive_nodes;
+        self.upload_node_flags();
+        self.refresh_edges();
+        self.need_update = true;
     }
 
-
-    pub fn gen_node(&mut self) {
+    // 边变化后重新计算质量与每个节点的边区间，保留坐标
+    fn refresh_edges(&mut self) {
 
         let device = &self.render_state.device;
         let queue = &self.render_state.queue;
 
-        let graph_resources = &self.graph_resources.as_ref().unwrap();
+        let graph_resources = self.graph_resources.as_ref().unwrap();
 
         let mut command_encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-        command_encoder.push_debug_group("Gen Node");
+        command_encoder.push_debug_group("Refresh Edges");
         {
-            // compute pass
             let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
-            Self::dispatch_compute_kernel(&self, &mut cpass, "gen_node", graph_resources.node_work_group_count);
+            Self::dispatch_compute_kernel(&self, &mut cpass, "reset_edge_state", graph_resources.node_work_group_count);
 
             Self::dispatch_compute_kernel(&self, &mut cpass, "cal_mass", graph_resources.edge_work_group_count);
 
@@ -1586,85 +3377,126 @@ impl GraphicsResources {
         command_encoder.pop_debug_group();
         queue.submit(Some(command_encoder.finish()));
 
+        self.sort_edges();
+    }
+
+    // 读取单个节点的坐标
+    pub fn read_node_position(&self, index: u32) -> glam::Vec3 {
 
-        // calc depth
-        let command_buffer = {
-            let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-            {
-                let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
+
+        let graph_resources = self.graph_resources.as_ref().unwrap();
+
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Node Position Read Buffer"),
+            size: 3 * 4,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.copy_buffer_to_buffer(&graph_resources.node_buffer, pad_size(mem::size_of::<Node>(), index), &read_buffer, 0, 3 * 4);
+        queue.submit(Some(command_encoder.finish()));
 
-                Self::dispatch_compute_kernel(&self, &mut cpass, "prepare_edge_sort", graph_resources.edge_work_group_count);
+        let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+        let buffer_slice = read_buffer.slice(..);
+        buffer_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
+        device.poll(wgpu::Maintain::Wait);
 
+        pollster::block_on(async {
+            if let Some(Ok(())) = receiver.receive().await {
+                let data = buffer_slice.get_mapped_range();
+                let position: Vec<f32> = data.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+                glam::Vec3::new(position[0], position[1], position[2])
+            } else {
+                panic!("failed to run compute on gpu!")
             }
-            command_encoder.finish()
+        })
+    }
+
+    // Handle 工具：按下时抓取指针下的节点并固定，拖动时在相机平面内移动
+    // 须在 update_control 之前调用，以便抓取节点时不旋转相机
+    pub fn update_node_drag(&mut self, ui: &Ui, is_hover: bool, scale: f32) {
+
+        let (is_pressed, is_down, delta) = {
+            let pointer = &ui.input().pointer;
+            (pointer.any_pressed() && pointer.primary_down(), pointer.primary_down(), pointer.delta() * scale)
         };
-        queue.submit(Some(command_buffer));
 
-        // sort
-        {
-            let mut dim = 2;
-            while dim < (graph_resources.status.edge_count * 2) * 2 {
-                let mut block_count = dim >> 1;
-                queue.write_buffer(&graph_resources.depth_sort_param_buffer, 0, bytemuck::cast_slice(&[dim as u32]));
-                while block_count > 0 {
-                    queue.write_buffer(&graph_resources.depth_sort_param_buffer, 4, bytemuck::cast_slice(&[block_count as u32]));
-                    let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-                    {
-                        let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
-                        Self::dispatch_compute_kernel(&self, &mut cpass, "sort_edge", graph_resources.edge_sort_work_group_count);
-                    }
-                    queue.submit(Some(command_encoder.finish()));
-                    block_count = block_count >> 1;
+        if self.node_drag.is_none() {
+            if is_hover && is_pressed && self.is_node_editable() {
+                if let Some(CastType::Node) = self.cast_type {
+                    let index = self.cast_value;
+                    let position = self.read_node_position(index);
+                    self.set_node_pinned(index, true);
+                    self.node_drag = Some(NodeDrag { index, position });
+                    self.control.is_primary_captured = true;
                 }
-                dim = dim << 1;
             }
-        };
-
-        let mut command_encoder =
-            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-        command_encoder.push_debug_group("compute_node_edge_sort_range");
-        {
-            // compute pass
-            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            return;
+        }
 
-            Self::dispatch_compute_kernel(&self, &mut cpass, "compute_node_edge_sort_range", graph_resources.edge_sort_work_group_count);
-            Self::dispatch_compute_kernel(&self, &mut cpass, "compute_node_edge_sort_range_2", graph_resources.edge_sort_work_group_count);
+        if !is_down || !self.is_node_editable() {
+            self.node_drag = None;
+            self.control.is_primary_captured = false;
+            return;
         }
-        command_encoder.pop_debug_group();
-        queue.submit(Some(command_encoder.finish()));
 
-        self.compute_frame_count += 1;
-    }
+        if delta == Vec2::ZERO { return; }
 
-    pub fn randomize(&mut self) {
+        // 节点所在深度处，一个像素对应的世界空间长度
+        let camera = &self.camera;
+        let view = camera.view_matrix;
+        let right = glam::Vec3::new(view.x_axis.x, view.y_axis.x, view.z_axis.x);
+        let up = glam::Vec3::new(view.x_axis.y, view.y_axis.y, view.z_axis.y);
+        let forward = (camera.center - camera.position).normalize();
 
-        let device = &self.render_state.device;
-        let queue = &self.render_state.queue;
+        let drag = self.node_drag.as_mut().unwrap();
+        let depth = (drag.position - camera.position).dot(forward).max(camera.near_far.x);
+        let fov_y = consts::FRAC_PI_4 * camera.zoom_factor;
+        let world_per_pixel = 2.0 * depth * (fov_y * 0.5).tan() / self.viewport_size.y;
+
+        drag.position += (right * delta.x - up * delta.y) * world_per_pixel;
 
         let graph_resources = self.graph_resources.as_ref().unwrap();
+        self.render_state.queue.write_buffer(
+            &graph_resources.node_buffer,
+            pad_size(mem::size_of::<Node>(), drag.index),
+            bytemuck::cast_slice(&drag.position.to_array()),
+        );
+        self.need_update = true;
+    }
 
-        let mut command_encoder =
-            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    // 当前布局保存为会话，Multilevel 处于粗化层时返回 None
+    pub fn session(&mut self, node_file_path: String, edge_file_path: String) -> Option<Session> {
 
-        queue.write_buffer(&graph_resources.uniform_buffer, 0, bytemuck::cast_slice(&[self.compute_frame_count as u32]));
-        command_encoder.push_debug_group("randomize render position");
-        {
-            // compute pass
-            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+        if !self.is_node_editable() { return None; }
 
-            Self::dispatch_compute_kernel(&self, &mut cpass, "randomize", graph_resources.node_work_group_count);
+        Some(Session {
+            node_file_path,
+            edge_file_path,
+            positions: self.read_positions(),
+            pinned_nodes: self.pinned_nodes.iter().copied().collect(),
+        })
+    }
 
-            Self::dispatch_compute_kernel(&self, &mut cpass, "copy", graph_resources.node_work_group_count);
+    pub fn restore_session(&mut self, session: Session) {
 
-            Self::calc_bounding_box(&self, &mut cpass);
-        }
-        command_encoder.pop_debug_group();
-        queue.submit(Some(command_encoder.finish()));
-        self.compute_frame_count += 1;
+        self.set_positions(&session.positions);
+        self.set_geo_projection(None);
+        self.pinned_nodes = session.pinned_nodes.into_iter().collect();
+        self.upload_node_flags();
+        self.need_update = true;
     }
 
     pub fn render(&mut self) {
 
+        if self.shader_error.is_some() {
+            return;
+        }
+
         let is_graph_resources = self.graph_resources.is_some();
 
         let new_time = Utc::now().timestamp_millis();
@@ -1677,12 +3509,21 @@ impl GraphicsResources {
 
         self.render_frame_count += 1u32;
 
-        let device = &self.render_state.device;
-        let queue = &self.render_state.queue;
-
         let is_render_output = self.is_render_output;
         self.is_render_output = false;
 
+        let is_oit = self.render_options.is_order_independent
+            && self.graph_resources.as_ref().map_or(false, |graph_resources| graph_resources.oit_render_pipelines.is_some());
+        if is_oit {
+            self.prepare_oit_targets(is_render_output);
+        }
+
+        self.kernel_timer.begin_batch(TimingGroup::Render);
+        let timing_start = Instant::now();
+
+        let device = &self.render_state.device;
+        let queue = &self.render_state.queue;
+
         let
             (
                 resolve_target,
@@ -1711,7 +3552,8 @@ impl GraphicsResources {
             resolve_target,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(if !is_render_output { wgpu::Color::TRANSPARENT } else { wgpu::Color::BLACK }),
-                store: false,
+                // 顺序无关透明的合成 Pass 需要读取多重采样的颜色
+                store: is_oit,
             },
         });
 
@@ -1728,41 +3570,26 @@ impl GraphicsResources {
             }),
         };
 
-        if self.render_options.is_rendering_node && is_graph_resources {
+        if self.render_options.is_rendering_node && is_graph_resources && !is_oit {
             let graph_resources = self.graph_resources.as_ref().unwrap();
 
-            // calc depth
-            let command_buffer = {
+            // 按深度排序节点，远处的节点先绘制
+            if let Some(depth_sort) = &graph_resources.depth_sort {
                 let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
                 {
                     let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
-
                     Self::dispatch_compute_kernel(&self, &mut cpass, "cal_depth", graph_resources.node_work_group_count);
-
                 }
-                command_encoder.finish()
-            };
-            queue.submit(Some(command_buffer));
-
-            // sort
-            {
-                let mut dim = 2;
-                while dim < graph_resources.status.node_count * 2 {
-                    let mut block_count = dim >> 1;
-                    queue.write_buffer(&graph_resources.depth_sort_param_buffer, 0, bytemuck::cast_slice(&[dim as u32]));
-                    while block_count > 0 {
-                        queue.write_buffer(&graph_resources.depth_sort_param_buffer, 4, bytemuck::cast_slice(&[block_count as u32]));
-                        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-                        {
-                            let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
-                            Self::dispatch_compute_kernel(&self, &mut cpass, "sort_by_depth", graph_resources.node_work_group_count);
-                        }
-                        queue.submit(Some(command_encoder.finish()));
-                        block_count = block_count >> 1;
-                    }
-                    dim = dim << 1;
+                let query = self.kernel_timer.begin_query("depth_sort");
+                if let Some((query_set, index)) = query { command_encoder.write_timestamp(query_set, index); }
+                depth_sort.encode(&mut command_encoder);
+                if let Some((query_set, index)) = query { command_encoder.write_timestamp(query_set, index + 1); }
+                {
+                    let mut cpass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+                    Self::dispatch_compute_kernel(&self, &mut cpass, "apply_depth_sort", graph_resources.node_work_group_count);
                 }
-            };
+                queue.submit(Some(command_encoder.finish()));
+            }
 
         }
 
@@ -1778,6 +3605,12 @@ impl GraphicsResources {
                         rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
                         rpass.draw(0..4, 0..2);
                     }
+                    if self.render_options.is_rendering_graticule && self.geo_projection.is_some() {
+                        rpass.set_pipeline(&self.graticule_render_pipeline);
+                        rpass.set_bind_group(0, &self.render_uniform_bind_group, &[]);
+                        rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+                        rpass.draw(0..4, 0..GRATICULE_SEGMENT_COUNT);
+                    }
                 }
                 if is_graph_resources {
                     let graph_resources = self.graph_resources.as_ref().unwrap();
@@ -1794,28 +3627,123 @@ impl GraphicsResources {
                         }
                     }
 
-                    if self.render_options.is_rendering_node {
+                    if self.render_options.is_rendering_node && !is_oit {
+                        let query = self.kernel_timer.begin_query("draw_nodes");
+                        if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index); }
                         rpass.set_pipeline(&graph_resources.node_render_pipeline);
                         rpass.set_bind_group(0, &self.render_uniform_bind_group, &[]);
                         rpass.set_bind_group(1, &graph_resources.node_render_bind_group, &[]);
                         rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
                         rpass.draw(0..4, 0..graph_resources.status.node_count as u32);
+                        if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index + 1); }
                     }
 
 
-                    if self.render_options.is_rendering_edge {
+                    if self.render_options.is_rendering_edge && !is_oit {
+                        let query = self.kernel_timer.begin_query("draw_edges");
+                        if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index); }
                         rpass.set_pipeline(&graph_resources.edge_render_pipeline);
                         rpass.set_bind_group(0, &self.render_uniform_bind_group, &[]);
                         rpass.set_bind_group(1, &graph_resources.edge_render_bind_group, &[]);
                         rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
-                        rpass.draw(0..4, 0..graph_resources.status.edge_count as u32);
+                        rpass.draw(0..4, 0..graph_resources.status.edge_count as u32 * self.edge_segment_count());
+                        if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index + 1); }
                     }
                 }
             }
-            command_encoder.finish()
+
+            // 顺序无关透明：节点与边累积到 OIT 目标，深度只测试不写入，再合成到颜色目标
+            if is_oit {
+                let graph_resources = self.graph_resources.as_ref().unwrap();
+                let (node_oit_render_pipeline, edge_oit_render_pipeline) = graph_resources.oit_render_pipelines.as_ref().unwrap();
+                let oit_targets = if !is_render_output { self.viewport_oit.as_ref() } else { self.output_oit.as_ref() }.unwrap();
+
+                {
+                    let mut rpass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("OIT Accumulation"),
+                        color_attachments: &oit_targets.color_attachments(),
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: &depth_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: false,
+                            }),
+                            stencil_ops: None,
+                        }),
+                    });
+
+                    let query = self.kernel_timer.begin_query("oit_accumulate");
+                    if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index); }
+
+                    if self.render_options.is_rendering_node {
+                        rpass.set_pipeline(node_oit_render_pipeline);
+                        rpass.set_bind_group(0, &self.render_uniform_bind_group, &[]);
+                        rpass.set_bind_group(1, &graph_resources.node_render_bind_group, &[]);
+                        rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+                        rpass.draw(0..4, 0..graph_resources.status.node_count as u32);
+                    }
+
+                    if self.render_options.is_rendering_edge {
+                        rpass.set_pipeline(edge_oit_render_pipeline);
+                        rpass.set_bind_group(0, &self.render_uniform_bind_group, &[]);
+                        rpass.set_bind_group(1, &graph_resources.edge_render_bind_group, &[]);
+                        rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+                        rpass.draw(0..4, 0..graph_resources.status.edge_count as u32 * self.edge_segment_count());
+                    }
+
+                    if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index + 1); }
+                }
+
+                {
+                    let mut rpass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("OIT Composite"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: false,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                    });
+
+                    let query = self.kernel_timer.begin_query("oit_composite");
+                    if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index); }
+                    rpass.set_pipeline(&self.oit_composite_render_pipeline);
+                    rpass.set_bind_group(0, &oit_targets.composite_bind_group, &[]);
+                    rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+                    rpass.draw(0..4, 0..1);
+                    if let Some((query_set, index)) = query { rpass.write_timestamp(query_set, index + 1); }
+                }
+            }
+
+            let timing_slot = self.kernel_timer.end_batch(&mut command_encoder);
+            (command_encoder.finish(), timing_slot)
         };
+        let (command_buffer, timing_slot) = command_buffer;
         queue.submit(Some(command_buffer));
+        self.kernel_timer.request(timing_slot);
+
+        // 按时钟计时时等待本帧绘制完成，只统计整帧的耗时
+        if self.kernel_timer.is_enabled && self.kernel_timer.source == TimingSource::WallClock {
+            device.poll(wgpu::Maintain::Wait);
+            self.kernel_timer.push_wall_clock_total(TimingGroup::Render, timing_start);
+        }
+
+    }
+
+    fn prepare_oit_targets(&mut self, is_render_output: bool) {
 
+        let (extent, targets) = if !is_render_output {
+            (self.viewport_texture_extent, &mut self.viewport_oit)
+        } else {
+            (self.output_texture_extent, &mut self.output_oit)
+        };
+
+        if targets.as_ref().map_or(true, |targets| targets.extent != extent) {
+            *targets = Some(OitTargets::new(&self.render_state.device, extent, &self.oit_composite_bind_group_layout));
+        }
     }
 
     pub fn update_viewport(&mut self, new_size: Vec2) {
@@ -1929,7 +3857,7 @@ impl GraphicsResources {
                     rpass.set_bind_group(0, &self.render_uniform_bind_group, &[]);
                     rpass.set_bind_group(1, &graph_resources.edge_render_bind_group, &[]);
                     rpass.set_vertex_buffer(0, self.quad_buffer.slice(..));
-                    rpass.draw(0..4, 0..graph_resources.status.edge_count as u32);
+                    rpass.draw(0..4, 0..graph_resources.status.edge_count as u32 * self.edge_segment_count());
                 }
             }
             command_encoder.finish()
@@ -2106,6 +4034,12 @@ impl GraphicsResources {
 
         if self.need_update || self.control.is_pointer_update {
 
+            let geo_uniform = self.generate_geo_uniform();
+            if geo_uniform != self.geo_uniform {
+                self.geo_uniform = geo_uniform;
+                self.camera.is_updated = true;
+            }
+
             let pointer_pos = if let Some(pos) = self.control.pointer_pos {
                 Some(glam::Vec2::new(pos.x * scale, pos.y * scale) )
             } else {
@@ -2117,7 +4051,8 @@ impl GraphicsResources {
                 &self.render_uniform_buffer,
                 glam::Vec2::new(self.viewport_size.x, self.viewport_size.y),
                 pointer_pos,
-                self.control.is_pointer_update
+                self.control.is_pointer_update,
+                self.geo_uniform,
             );
         }
 
@@ -2127,17 +4062,38 @@ impl GraphicsResources {
 
     pub fn dispose(&mut self) {
 
-        let graph_resources = self.graph_resources.as_mut().unwrap();
-        graph_resources.node_buffer.destroy();
-        graph_resources.edge_buffer.destroy();
-        graph_resources.bounding_buffer.destroy();
-        graph_resources.tree_buffer.destroy();
-        graph_resources.tree_node_buffer.destroy();
-        graph_resources.tree_child_buffer.destroy();
-        graph_resources.depth_sort_buffer.destroy();
-        graph_resources.depth_sort_param_buffer.destroy();
+        // 导入失败时可能还没有资源
+        if let Some(graph_resources) = self.graph_resources.as_mut() {
+            graph_resources.node_buffer.destroy();
+            graph_resources.edge_buffer.destroy();
+            graph_resources.bounding_buffer.destroy();
+            graph_resources.tree_buffer.destroy();
+            graph_resources.tree_node_buffer.destroy();
+            graph_resources.tree_child_buffer.destroy();
+            graph_resources.depth_sort_buffer.destroy();
+            graph_resources.last_valid_position_buffer.destroy();
+            graph_resources.node_quarantine_buffer.destroy();
+            if let Some(depth_sort) = &graph_resources.depth_sort {
+                depth_sort.destroy();
+            }
+            graph_resources.edge_sort.destroy();
+            for edge_chunk in &graph_resources.edge_chunks {
+                edge_chunk.chunk_buffer.destroy();
+                edge_chunk.edge_sort_node_buffer.destroy();
+                edge_chunk.edge_sort_dir_buffer.destroy();
+            }
+        }
 
         self.graph_resources = None;
+        self.multilevel = None;
+        self.pinned_nodes.clear();
+        self.hidden_nodes.clear();
+        self.inactive_nodes.clear();
+        self.node_drag = None;
+        self.cluster_groups = None;
+        self.layout_history.clear();
+        self.quarantine_counts.clear();
+        self.quarantine_report = None;
         self.need_update = true;
         // self.render();
     }
@@ -2145,6 +4101,131 @@ impl GraphicsResources {
 
 }
 
+// GPU 布局后端，状态码仍需由调用者通过 poll_kernel_status 取回
+impl LayoutEngine for GraphicsResources {
+
+    fn name(&self) -> String {
+        String::from("GPU (wgpu)")
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph_resources.as_ref().map_or(0, |graph_resources| graph_resources.status.node_count)
+    }
+
+    fn frame(&self) -> u32 {
+        self.compute_frame_count
+    }
+
+    // 没有窗口的事件循环时需要主动回收已完成的提交
+    fn step(&mut self) {
+        self.compute();
+        self.render_state.device.poll(wgpu::Maintain::Poll);
+    }
+
+    fn read_positions(&mut self) -> Vec<f32> {
+        GraphicsResources::read_positions(self)
+    }
+
+    fn set_positions(&mut self, positions: &[f32]) {
+        GraphicsResources::set_positions(self, positions);
+    }
+
+    // 等待已提交的计算完成后取回 Kernel 状态，出现设备错误或无法自动恢复的 Kernel 错误时返回原因
+    fn poll(&mut self, model: &DataModel) -> Result<(), String> {
+
+        self.render_state.device.poll(wgpu::Maintain::Wait);
+        self.poll_kernel_status(model);
+
+        if let Some(error) = self.device_error.lock().unwrap().clone() {
+            return Err(format!("GPU device error: {}", error));
+        }
+        match &self.graph_resources {
+            Some(graph_resources) if graph_resources.is_kernel_error => {
+                let content = graph_resources.kernel_status_codes.iter().enumerate()
+                    .filter(|(_, &code)| code > 0)
+                    .map(|(index, &code)| format_kernel_status(index, code))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                Err(format!("GPU kernel error\n{}", content))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// GraphicsResources 使用的 Shader，顺序与 shaders 的下标对应，Compute Shader 在最后
+pub const GRAPHICS_SHADER_FILES: [&str; 6] = [
+    "S_node.wgsl",
+    "S_edge.wgsl",
+    "S_axis.wgsl",
+    "S_bounding_box.wgsl",
+    "S_oit_composite.wgsl",
+    "CS_graph_solver.wgsl",
+];
+
+// 预处理 GRAPHICS_SHADER_FILES 中的 Shader，Compute Shader 中 Node 的 mass 为原子类型
+pub fn preprocess_shaders() -> Vec<Result<PreprocessedShader, String>> {
+    let render_preprocessor = shader_preprocessor();
+    let compute_preprocessor = shader_preprocessor().flag("NODE_ATOMIC_MASS", true);
+    GRAPHICS_SHADER_FILES.par_iter().map(|shader_file| {
+        let preprocessor = if shader_file.starts_with("CS_") { &compute_preprocessor } else { &render_preprocessor };
+        preprocessor.process(shader_file)
+    }).collect()
+}
+
+// Shader 中由 Rust 常量定义的宏，状态码常量与 Rust 共用一张表，由 CS_graph_solver.wgsl 包含
+pub fn shader_preprocessor() -> ShaderPreprocessor {
+    ShaderPreprocessor::default()
+        .define_u32("WORKGROUP_SIZE", PARTICLES_PER_GROUP)
+        .define_u32("NODE_FLAG_PINNED", NODE_FLAG_PINNED)
+        .define_u32("NODE_FLAG_HIDDEN", NODE_FLAG_HIDDEN)
+        .define_u32("MAX_QUARANTINED_NODES", MAX_QUARANTINED_NODES as u32)
+        .define_u32("QUARANTINE_NO_PREVIOUS", QUARANTINE_NO_PREVIOUS)
+        .generated("kernel_status.wgsl", wgsl_constants())
+}
+
+// 不创建窗口时的 GPU 布局后端
+// wgpu 的错误默认会使程序退出，这里记录第一个错误，使调用者可以改用 CPU
+pub fn create_gpu_layout_engine(model: &mut DataModel, seed: u32) -> Result<GraphicsResources, String> {
+
+    let render_state = headless_render_state().ok_or_else(|| String::from("No GPU adapter was found"))?;
+    let device_error = Arc::new(Mutex::new(None));
+    let device_error_sink = device_error.clone();
+    render_state.device.on_uncaptured_error(move |error| {
+        device_error_sink.lock().unwrap().get_or_insert_with(|| error.to_string());
+    });
+
+    let mut graphics_resources = GraphicsResources::new(render_state.clone());
+    graphics_resources.compute_options.seed = seed;
+    graphics_resources.init_data(render_state, model)?;
+
+    let error = device_error.lock().unwrap().take();
+    match error {
+        Some(error) => Err(error),
+        None => {
+            // 计算中的设备错误由 LayoutEngine::poll 报告
+            graphics_resources.device_error = device_error;
+            Ok(graphics_resources)
+        }
+    }
+}
+
+// 不创建窗口时的布局后端，优先使用 GPU，没有可用的适配器或 GPU 无法运行时使用 CPU
+pub fn create_layout_engine(model: &mut DataModel, seed: u32) -> Result<Box<dyn LayoutEngine>, String> {
+
+    if model.source_target_list.is_none() {
+        return Err(String::from("No edge data"));
+    }
+
+    match create_gpu_layout_engine(model, seed) {
+        Ok(graphics_resources) => return Ok(Box::new(graphics_resources)),
+        Err(error) => message_info("Using CPU Solver", error.as_str()),
+    }
+
+    let source_target_list = model.source_target_list.as_ref().unwrap();
+    Ok(Box::new(CpuLayoutEngine::new(model.status.node_count, source_target_list, seed)))
+}
+
 fn update_render_uniforms(
     queue: &Queue,
     camera: &mut Camera,
@@ -2152,6 +4233,7 @@ fn update_render_uniforms(
     viewport_size: glam::Vec2,
     pointer_pos: Option<glam::Vec2>,
     is_pointer_update: bool,
+    geo: [f32; 4],
 ) {
 
     let pos = if let Some(pos) = pointer_pos {
@@ -2164,14 +4246,14 @@ fn update_render_uniforms(
 
         camera.update_projection_matrix();
 
-        let uniform = generate_uniforms(camera, viewport_size, pos);
+        let uniform = generate_uniforms(camera, viewport_size, pos, geo);
 
         queue.write_buffer(&render_uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
         camera.is_updated = false;
 
     } else if pointer_pos.is_some() && is_pointer_update {
 
-        let uniform = generate_uniforms(camera, viewport_size, pos);
+        let uniform = generate_uniforms(camera, viewport_size, pos, geo);
         queue.write_buffer(&render_uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
 
     }
@@ -2189,8 +4271,201 @@ fn pad_size(node_struct_size: usize, num_particles: u32) -> wgpu::BufferAddress
     padded_size
 }
 
-fn get_tree_node_count(node_count: &u32) -> u32 {
-    let mut tree_node_count = node_count * 2;
+// 在边表和边数据的末尾追加一条边，边数据只填写起点和终点两列
+fn push_edge(model: &mut DataModel, source: u32, target: u32) {
+
+    model.source_target_list.as_mut().unwrap().extend([source, target]);
+
+    let mut row = vec![String::new(); model.edge_data.headers_index_str.len()];
+    for (column, id) in [(model.edge_source, source), (model.edge_target, target)] {
+        if let Some(field) = column.and_then(|column| row.get_mut(column)) {
+            *field = id.to_string();
+        }
+    }
+    model.edge_data.data.push(row);
+}
+
+// 一批编辑中按端点与节点查找边，删除时只做标记，最后由 remove_edges 一次压缩
+struct EdgeIndex {
+    by_edge:    HashMap<[u32; 2], Vec<usize>>,
+    by_node:    HashMap<u32, Vec<usize>>,
+    is_removed: Vec<bool>,
+}
+
+impl EdgeIndex {
+
+    fn new(source_target_list: &[u32]) -> Self {
+        let mut edge_index = Self { by_edge: HashMap::new(), by_node: HashMap::new(), is_removed: vec![] };
+        for edge in source_target_list.chunks_exact(2) {
+            edge_index.push(edge[0], edge[1]);
+        }
+        edge_index
+    }
+
+    fn push(&mut self, source: u32, target: u32) {
+        let index = self.is_removed.len();
+        self.is_removed.push(false);
+        self.by_edge.entry([source, target]).or_default().push(index);
+        self.by_node.entry(source).or_default().push(index);
+        if target != source {
+            self.by_node.entry(target).or_default().push(index);
+        }
+    }
+
+    // 重复的边每次删除一条
+    fn remove_edge(&mut self, source: u32, target: u32) {
+        let Some(indices) = self.by_edge.get_mut(&[source, target]) else { return; };
+        while let Some(index) = indices.pop() {
+            if !self.is_removed[index] {
+                self.is_removed[index] = true;
+                return;
+            }
+        }
+    }
+
+    fn remove_node(&mut self, node: u32) {
+        for index in self.by_node.remove(&node).unwrap_or_default() {
+            self.is_removed[index] = true;
+        }
+    }
+}
+
+// 删除标记的边，边表与边数据保持同一顺序
+fn remove_edges(model: &mut DataModel, is_removed: &[bool]) {
+
+    let mut index = 0;
+    model.source_target_list.as_mut().unwrap().retain(|_| {
+        index += 1;
+        !is_removed[(index - 1) / 2]
+    });
+
+    let mut index = 0;
+    model.edge_data.data.retain(|_| {
+        index += 1;
+        !is_removed.get(index - 1).copied().unwrap_or(false)
+    });
+}
+
+// 有限坐标的包围盒，没有有限坐标时为原点处的单位立方体
+fn finite_bounds(positions: &[f32]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for position in positions.chunks_exact(3).filter(|position| position.iter().all(|value| value.is_finite())) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    if min[0] > max[0] { ([-1.0; 3], [1.0; 3]) } else { (min, max) }
+}
+
+// 将量化后落在同一格子中的节点随机偏移，格子为包围盒的 2^-20，返回偏移的节点数
+fn spread_coincident_nodes(positions: &mut [f32], seed: u32) -> usize {
+    let (min, max) = finite_bounds(positions);
+    let extent = (0..3).map(|axis| max[axis] - min[axis]).fold(f32::EPSILON, f32::max);
+    let cell = extent / (1 << 20) as f32;
+
+    let mut occupied = HashSet::new();
+    let mut count = 0;
+    for (index, position) in positions.chunks_exact_mut(3).enumerate() {
+        let key = [0, 1, 2].map(|axis| (position[axis] / cell).floor() as i64);
+        if !occupied.insert(key) {
+            let offset = initial_position(seed, index as u32);
+            for axis in 0..3 { position[axis] += offset[axis] * cell * 64.0; }
+            count += 1;
+        }
+    }
+    count
+}
+
+// 将 nodes 放到坐标有效的邻居的中心附近，偏移为包围盒的 1%，没有这样的邻居时放到包围盒中心附近
+fn reseed_near_neighbors(positions: &mut [f32], source_target_list: &[u32], nodes: &BTreeSet<u32>, seed: u32) {
+    let (min, max) = finite_bounds(positions);
+    let extent = (0..3).map(|axis| max[axis] - min[axis]).fold(f32::EPSILON, f32::max);
+    let node_count = (positions.len() / 3) as u32;
+
+    let is_valid = |index: u32| index < node_count && !nodes.contains(&index)
+        && positions[index as usize * 3..index as usize * 3 + 3].iter().all(|value| value.is_finite());
+
+    let mut neighbors: BTreeMap<u32, ([f32; 3], u32)> = nodes.iter()
+        .filter(|&&index| index < node_count)
+        .map(|&index| (index, ([0.0; 3], 0)))
+        .collect();
+    for edge in source_target_list.chunks_exact(2) {
+        for (node, neighbor) in [(edge[0], edge[1]), (edge[1], edge[0])] {
+            if let Some((sum, count)) = neighbors.get_mut(&node) {
+                if !is_valid(neighbor) { continue; }
+                for axis in 0..3 { sum[axis] += positions[neighbor as usize * 3 + axis]; }
+                *count += 1;
+            }
+        }
+    }
+
+    for (index, (sum, count)) in neighbors {
+        let offset = initial_position(seed, index);
+        for axis in 0..3 {
+            let center = if count > 0 { sum[axis] / count as f32 } else { (min[axis] + max[axis]) * 0.5 };
+            positions[index as usize * 3 + axis] = center + offset[axis] * extent * 0.01;
+        }
+    }
+}
+
+// 新节点的初始坐标，与 gen_node 相同取 [-1, 1] 内的随机值
+fn initial_position(seed: u32, index: u32) -> [f32; 3] {
+    let mut state = ((seed as u64) << 32 | index as u64) ^ 0x9e37_79b9_7f4a_7c15;
+    [0; 3].map(|_: u32| {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut hash = state;
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^= hash >> 31;
+        (hash >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    })
+}
+
+fn chunk_kernel_name(entry_point: &str, chunk_index: usize) -> String {
+    format!("{} {}", entry_point, chunk_index)
+}
+
+// 按 init_graph 中的 Buffer 大小检查，返回边排序 Buffer 的分块
+// 大小以 u64 计算，避免超大图在检查之前溢出
+fn plan_graph_memory(planner: &MemoryPlanner, status: &GraphicsStatus, node_struct_size: usize) -> Result<Vec<EdgeChunk>, MemoryPlanError> {
+
+    let node_count = status.node_count as u64;
+    let edge_count = status.edge_count as u64;
+    let edge_sort_count = edge_count * 2;
+    let group_size = PARTICLES_PER_GROUP as u64;
+
+    // 边表、边排序的 Key / Value Buffer 每条边各占 8 byte，边的 Kernel 每个线程处理一条边，都尚未分块
+    // 默认限制下约为 1600 万条边
+    let max_edge_count = planner.max_count(2 * 4, group_size);
+    if edge_count > max_edge_count {
+        return Err(MemoryPlanError::TooManyEdges { count: edge_count, limit: max_edge_count });
+    }
+
+    planner.check_buffers(&[
+        BufferRequirement { label: "Node Buffer", size: pad_size(node_struct_size, 1) * node_count },
+        BufferRequirement { label: "Spring Force Buffer", size: node_count * 4 * 4 },
+        BufferRequirement { label: "Depth Sort Buffer", size: node_count * 2 * 4 },
+        BufferRequirement { label: "Edge Buffer", size: edge_count * 2 * 4 },
+        BufferRequirement { label: "Edge Sort Key Buffer", size: edge_sort_count * 4 },
+    ])?;
+    planner.check_dispatch("Node kernels", node_count, group_size)?;
+    planner.check_dispatch("Edge kernels", edge_count, group_size)?;
+
+    // 节点数已受上面的限制，不会溢出
+    let tree_node_count = get_tree_node_count(&(node_count as u32), DEFAULT_TREE_SCALE) as u64;
+    planner.check_buffers(&[
+        BufferRequirement { label: "Tree Node Buffer", size: 4 * ((tree_node_count + 1) * 8) },
+    ])?;
+    planner.check_dispatch("Tree kernels", tree_node_count, group_size)?;
+
+    // 按每项占用最大的 Edge Sort Dir Buffer 分块
+    planner.chunks("Edge Sort Dir Buffer", edge_sort_count, 4 * 4)
+}
+
+fn get_tree_node_count(node_count: &u32, tree_scale: u32) -> u32 {
+    let mut tree_node_count = node_count * tree_scale;
     // println!("{}", node_count);
     while tree_node_count & (PARTICLES_PER_GROUP - 1) != 0 {
         tree_node_count += 1;
@@ -2204,4 +4479,89 @@ fn get_tree_node_count(node_count: &u32) -> u32 {
 //     cpass.set_pipeline(&compute_kernel.compute_pipeline);
 //     cpass.set_bind_group(0, &compute_kernel.bind_group, &[]);
 //     cpass.dispatch_workgroups(work_group_count, 1, 1);
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_edges(width: u32) -> Vec<u32> {
+        (0..width * width).flat_map(|index| {
+            let right = (index % width + 1 < width).then(|| [index, index + 1]);
+            let down = (index + width < width * width).then(|| [index, index + width]);
+            right.into_iter().chain(down).flatten()
+        }).collect()
+    }
+
+    fn gpu_engine(node_count: usize, source_target_list: &[u32]) -> (GraphicsResources, DataModel) {
+        let mut model = DataModel::default();
+        model.status.node_count = node_count;
+        model.status.edge_count = source_target_list.len() / 2;
+        model.source_target_list = Some(source_target_list.to_vec());
+        let mut engine = create_gpu_layout_engine(&mut model, 1).unwrap_or_else(|error| panic!("{}", error));
+        engine.compute_options.is_deterministic = true;
+        (engine, model)
+    }
+
+    #[test]
+    fn removes_edges_by_index() {
+        let mut model = DataModel {
+            source_target_list: Some(vec![0, 1, 1, 2, 0, 1, 2, 3, 3, 0]),
+            ..Default::default()
+        };
+        model.edge_data.data = (0..5).map(|index| vec![index.to_string()]).collect();
+
+        let mut edge_index = EdgeIndex::new(model.source_target_list.as_ref().unwrap());
+        edge_index.push(3, 1);
+        edge_index.remove_edge(0, 1);
+        edge_index.remove_edge(2, 0);
+        edge_index.remove_node(3);
+        remove_edges(&mut model, &edge_index.is_removed);
+
+        // 重复的边只删除一条，节点 3 的边全部删除，不存在的边忽略
+        assert_eq!(model.source_target_list.unwrap(), vec![0, 1, 1, 2]);
+        assert_eq!(model.edge_data.data, vec![vec![String::from("0")], vec![String::from("1")]]);
+    }
+
+    #[test]
+    fn refuses_edges_beyond_whole_bindings() {
+        // 默认限制下边表与边的 Kernel 都在 1600 万条边左右达到上限，工作组数略小，分块的边排序 Buffer 不影响
+        let planner = MemoryPlanner::new(&wgpu::Limits::default());
+        let max_edge_count = 65535 * 256;
+        let status = |edge_count| GraphicsStatus { node_count: 1000, edge_count, ..Default::default() };
+
+        assert!(plan_graph_memory(&planner, &status(max_edge_count), mem::size_of::<Node>()).is_ok());
+        let error = plan_graph_memory(&planner, &status(max_edge_count + 1), mem::size_of::<Node>()).unwrap_err();
+        assert!(matches!(error, MemoryPlanError::TooManyEdges { limit, .. } if limit == max_edge_count as u64), "{:?}", error);
+    }
+
+    // 删除的节点紧挨着节点 0，若仍在八叉树或包围盒中，其他节点的坐标会与没有该节点时不同
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn removed_node_exerts_no_force() {
+        let edges = grid_edges(8);
+        let (mut baseline, _) = gpu_engine(64, &edges);
+        let positions = LayoutEngine::read_positions(&mut baseline);
+        LayoutEngine::set_positions(&mut baseline, &positions);
+
+        let mut extended_edges = edges.clone();
+        extended_edges.extend([64, 0]);
+        let (mut edited, mut model) = gpu_engine(65, &extended_edges);
+        edited.apply_edits(&mut model, &[GraphEdit::RemoveNode(64)]).unwrap();
+        let mut extended_positions = positions.clone();
+        extended_positions.extend([positions[0] + 0.01, positions[1], positions[2]]);
+        LayoutEngine::set_positions(&mut edited, &extended_positions);
+
+        for _ in 0..5 {
+            baseline.step();
+            edited.step();
+        }
+        let baseline_positions = LayoutEngine::read_positions(&mut baseline);
+        let edited_positions = LayoutEngine::read_positions(&mut edited);
+
+        assert_eq!(edited_positions[64 * 3..], extended_positions[64 * 3..]);
+        let extent = baseline_positions.iter().fold(0.0f32, |extent, value| extent.max(value.abs()));
+        for (index, (a, b)) in baseline_positions.iter().zip(&edited_positions).enumerate() {
+            assert!((a - b).abs() <= extent * 1e-4, "node {} differs: {} != {}", index / 3, a, b);
+        }
+    }
+}
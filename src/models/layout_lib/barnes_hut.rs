@@ -0,0 +1,247 @@
+use rayon::prelude::*;
+
+// 须同步修改 CS_graph_solver.wgsl 中 electron_force 的参数
+const REPULSION_SCALE: f32 = 0.0003;
+const REPULSION_FACTOR: f32 = 0.25;
+const SOFTENING_SQ: f32 = 0.05 * 0.05;
+
+// 与 electron_force 的遍历栈深度相同，坐标完全重合的节点在此深度放入同一个叶子
+const MAX_TREE_DEPTH: usize = 48;
+
+const EMPTY: u32 = u32::MAX;
+// 子单元中直接保存节点编号时的标记位
+const BODY: u32 = 0x8000_0000;
+
+struct Cell {
+    // 质心与总质量，与 summarization 相同按节点质量加权
+    position:   [f32; 3],
+    mass:       f32,
+    children:   [u32; 8],
+    // 达到最大深度的叶子中的节点，为 bodies 中的 [start, end)
+    start:      u32,
+    end:        u32,
+}
+
+// CPU 上的 Barnes-Hut 八叉树，根单元与 bounding_box 相同，取包围盒中心与最长边
+pub struct Octree {
+    cells:      Vec<Cell>,
+    bodies:     Vec<u32>,
+    diameter:   f32,
+}
+
+impl Octree {
+
+    pub fn build(positions: &[f32], masses: &[f32]) -> Self {
+        Self::build_subset(positions, masses, (0..masses.len() as u32).collect())
+    }
+
+    // 只由 indices 中的节点建树，与 tree_building 跳过隐藏的节点相同，其余节点不计入包围盒与质心
+    pub fn build_subset(positions: &[f32], masses: &[f32], mut indices: Vec<u32>) -> Self {
+
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for &index in &indices {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(positions[index as usize * 3 + axis]);
+                max[axis] = max[axis].max(positions[index as usize * 3 + axis]);
+            }
+        }
+        let center = [0, 1, 2].map(|axis| (min[axis] + max[axis]) * 0.5);
+        let diameter = (0..3).map(|axis| max[axis] - min[axis]).fold(0.0, f32::max);
+
+        let mut tree = Self { cells: vec![], bodies: vec![], diameter };
+        if !indices.is_empty() {
+            tree.build_cell(positions, masses, &mut indices, center, diameter * 0.5, 0);
+        }
+        tree
+    }
+
+    fn build_cell(&mut self, positions: &[f32], masses: &[f32], indices: &mut [u32], center: [f32; 3], half: f32, depth: usize) -> u32 {
+
+        let cell_index = self.cells.len() as u32;
+        self.cells.push(Cell { position: [0.0; 3], mass: 0.0, children: [EMPTY; 8], start: 0, end: 0 });
+
+        if depth + 1 >= MAX_TREE_DEPTH {
+            let start = self.bodies.len() as u32;
+            self.bodies.extend_from_slice(indices);
+            let cell = &mut self.cells[cell_index as usize];
+            cell.start = start;
+            cell.end = self.bodies.len() as u32;
+        } else {
+            // 按八分体把节点编号分段排列
+            let octant = |index: u32| {
+                let position = &positions[index as usize * 3..index as usize * 3 + 3];
+                (0..3).fold(0, |octant, axis| octant | ((position[axis] >= center[axis]) as usize) << axis)
+            };
+            let mut counts = [0usize; 8];
+            for &index in indices.iter() { counts[octant(index)] += 1; }
+            let mut offsets = [0usize; 8];
+            for child in 1..8 { offsets[child] = offsets[child - 1] + counts[child - 1]; }
+            let mut cursor = offsets;
+            let mut sorted = vec![0u32; indices.len()];
+            for &index in indices.iter() {
+                let child = octant(index);
+                sorted[cursor[child]] = index;
+                cursor[child] += 1;
+            }
+            indices.copy_from_slice(&sorted);
+
+            let quarter = half * 0.5;
+            for child in 0..8 {
+                let members = &mut indices[offsets[child]..offsets[child] + counts[child]];
+                let child_index = match members.len() {
+                    0 => continue,
+                    1 => members[0] | BODY,
+                    _ => {
+                        let child_center = [0, 1, 2].map(|axis| {
+                            center[axis] + if child >> axis & 1 == 1 { quarter } else { -quarter }
+                        });
+                        self.build_cell(positions, masses, members, child_center, quarter, depth + 1)
+                    }
+                };
+                self.cells[cell_index as usize].children[child] = child_index;
+            }
+        }
+
+        // 汇总质心
+        let (mut position, mut mass) = ([0.0f32; 3], 0.0f32);
+        let mut add = |child_position: &[f32], child_mass: f32| {
+            for axis in 0..3 { position[axis] += child_position[axis] * child_mass; }
+            mass += child_mass;
+        };
+        let cell = &self.cells[cell_index as usize];
+        for &child in cell.children.iter().filter(|&&child| child != EMPTY) {
+            if child & BODY != 0 {
+                let body = (child & !BODY) as usize;
+                add(&positions[body * 3..body * 3 + 3], masses[body]);
+            } else {
+                let child = &self.cells[child as usize];
+                add(&child.position, child.mass);
+            }
+        }
+        for &body in &self.bodies[cell.start as usize..cell.end as usize] {
+            add(&positions[body as usize * 3..body as usize * 3 + 3], masses[body as usize]);
+        }
+
+        let cell = &mut self.cells[cell_index as usize];
+        cell.position = position.map(|value| value / mass);
+        cell.mass = mass;
+        cell_index
+    }
+
+    // 与 electron_force 相同的遍历：单元的子单元距离平方不小于 (diameter / 2^depth)² + ε² 时视为一个质点
+    pub fn repulsion(&self, index: usize, positions: &[f32], masses: &[f32]) -> [f32; 3] {
+
+        let mut force = [0.0f32; 3];
+        if self.cells.is_empty() { return force; }
+
+        let position = &positions[index * 3..index * 3 + 3];
+        let mass = masses[index];
+        let mut add = |other: &[f32], other_mass: f32| {
+            let dp = [0, 1, 2].map(|axis| position[axis] - other[axis]);
+            let dist2 = dp[0] * dp[0] + dp[1] * dp[1] + dp[2] * dp[2];
+            if dist2 > 0.0 {
+                let factor = REPULSION_SCALE * mass * other_mass / dist2;
+                for axis in 0..3 { force[axis] += dp[axis] * factor; }
+            }
+        };
+        let distance_sq = |other: &[f32]| (0..3).map(|axis| (position[axis] - other[axis]).powi(2)).sum::<f32>();
+
+        let mut stack = vec![(0u32, 0usize)];
+        while let Some((cell_index, depth)) = stack.pop() {
+            let cell = &self.cells[cell_index as usize];
+            let threshold = (self.diameter / (1u64 << depth) as f32).powi(2) + SOFTENING_SQ;
+
+            for &child in cell.children.iter().filter(|&&child| child != EMPTY) {
+                if child & BODY != 0 {
+                    let body = (child & !BODY) as usize;
+                    add(&positions[body * 3..body * 3 + 3], masses[body]);
+                } else {
+                    let child_cell = &self.cells[child as usize];
+                    if distance_sq(&child_cell.position) >= threshold {
+                        add(&child_cell.position, child_cell.mass);
+                    } else {
+                        stack.push((child, depth + 1));
+                    }
+                }
+            }
+            for &body in &self.bodies[cell.start as usize..cell.end as usize] {
+                add(&positions[body as usize * 3..body as usize * 3 + 3], masses[body as usize]);
+            }
+        }
+
+        force.map(|value| value * REPULSION_FACTOR)
+    }
+
+    pub fn repulsions(&self, positions: &[f32], masses: &[f32]) -> Vec<[f32; 3]> {
+        (0..masses.len()).into_par_iter().map(|index| self.repulsion(index, positions, masses)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 所有节点两两之间的斥力
+    fn direct_repulsion(index: usize, positions: &[f32], masses: &[f32]) -> [f32; 3] {
+        let position = &positions[index * 3..index * 3 + 3];
+        let mut force = [0.0f32; 3];
+        for (other, other_position) in positions.chunks_exact(3).enumerate() {
+            let dp = [0, 1, 2].map(|axis| position[axis] - other_position[axis]);
+            let dist2 = dp[0] * dp[0] + dp[1] * dp[1] + dp[2] * dp[2];
+            if dist2 > 0.0 {
+                let factor = REPULSION_SCALE * masses[index] * masses[other] / dist2;
+                for axis in 0..3 { force[axis] += dp[axis] * factor; }
+            }
+        }
+        force.map(|value| value * REPULSION_FACTOR)
+    }
+
+    fn random_points(count: usize, seed: u64) -> (Vec<f32>, Vec<f32>) {
+        let mut state = seed;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        };
+        let positions = (0..count * 3).map(|_| next() * 2.0 - 1.0).collect();
+        let masses = (0..count).map(|_| 1.0 + (next() * 8.0).floor()).collect();
+        (positions, masses)
+    }
+
+    fn norm(vector: [f32; 3]) -> f32 {
+        vector.iter().map(|value| value * value).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn approximates_direct_sum() {
+        let (positions, masses) = random_points(3000, 42);
+        let tree = Octree::build(&positions, &masses);
+        let forces = tree.repulsions(&positions, &masses);
+
+        let (mut error, mut total) = (0.0, 0.0);
+        for index in (0..masses.len()).step_by(7) {
+            let expected = direct_repulsion(index, &positions, &masses);
+            error += norm([0, 1, 2].map(|axis| forces[index][axis] - expected[axis]));
+            total += norm(expected);
+        }
+        assert!(error / total < 0.05, "relative error {}", error / total);
+    }
+
+    #[test]
+    fn handles_coincident_nodes() {
+        let mut positions = vec![0.5f32; 100 * 3];
+        positions.extend_from_slice(&[-1.0, -1.0, -1.0]);
+        let masses = vec![1.0; 101];
+        let tree = Octree::build(&positions, &masses);
+
+        // 重合的节点之间没有斥力，只受到另一个节点的斥力
+        let force = tree.repulsion(0, &positions, &masses);
+        let expected = direct_repulsion(0, &positions, &masses);
+        assert!(force.iter().all(|value| value.is_finite()));
+        assert!(norm([0, 1, 2].map(|axis| force[axis] - expected[axis])) <= norm(expected) * 1e-4);
+
+        assert_eq!(Octree::build(&[], &[]).repulsions(&[], &[]).len(), 0);
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use super::graph::Adjacency;
+use super::random::{hash, random_xy};
+
+// 不属于任何分组的节点
+pub const NO_GROUP: u32 = u32::MAX;
+
+// 分组间斥力的计算量与分组数的平方成正比
+pub const MAX_GROUP_COUNT: usize = 4096;
+
+const MAX_PROPAGATION_ITERATIONS: usize = 20;
+
+// 节点分组，成员按分组连续存放，供 GPU 按组归约质心
+pub struct ClusterGroups {
+    pub group_count: usize,
+    pub node_group: Vec<u32>,
+    pub members: Vec<u32>,
+    // 每组在 members 中的 [start, end)
+    pub ranges: Vec<[u32; 2]>,
+}
+
+impl ClusterGroups {
+
+    // labels 为每个节点的分组编号，须在 0..group_count 内或为 NO_GROUP
+    pub fn from_labels(labels: Vec<u32>, group_count: usize) -> Self {
+
+        let mut counts = vec![0u32; group_count];
+        for &label in &labels {
+            if label != NO_GROUP { counts[label as usize] += 1; }
+        }
+
+        let mut ranges = Vec::with_capacity(group_count);
+        let mut start = 0u32;
+        for &count in &counts {
+            ranges.push([start, start + count]);
+            start += count;
+        }
+
+        let mut cursor: Vec<u32> = ranges.iter().map(|range| range[0]).collect();
+        let mut members = vec![0u32; start as usize];
+        for (index, &label) in labels.iter().enumerate() {
+            if label == NO_GROUP { continue; }
+            members[cursor[label as usize] as usize] = index as u32;
+            cursor[label as usize] += 1;
+        }
+
+        Self {
+            group_count,
+            node_group: labels,
+            members,
+            ranges,
+        }
+    }
+}
+
+// 按属性值分组，编号按值首次出现的顺序分配，空值不分组
+pub fn attribute_labels<'a>(node_count: usize, values: impl Iterator<Item = &'a str>) -> (Vec<u32>, usize) {
+
+    let mut labels = vec![NO_GROUP; node_count];
+    let mut groups: HashMap<&str, u32> = HashMap::new();
+
+    for (index, value) in values.enumerate().take(node_count) {
+        let value = value.trim();
+        if value.is_empty() { continue; }
+        let next = groups.len() as u32;
+        labels[index] = *groups.entry(value).or_insert(next);
+    }
+
+    (labels, groups.len())
+}
+
+// 标签传播社区发现，每轮按哈希打乱的顺序异步更新，结果可复现
+// 当前标签在票数最多的标签之中时保持不变，否则取哈希最小的标签，避免偏向小编号
+// 孤立节点不分组
+pub fn label_propagation(node_count: usize, source_target_list: &[u32]) -> (Vec<u32>, usize) {
+
+    let adjacency = Adjacency::from_edges(node_count, source_target_list);
+    let mut labels: Vec<u32> = (0..node_count as u32).collect();
+    let mut neighbor_labels: Vec<u32> = vec![];
+    let mut order: Vec<u32> = (0..node_count as u32).collect();
+
+    for iteration in 0..MAX_PROPAGATION_ITERATIONS {
+        let mut is_changed = false;
+
+        order.sort_unstable_by_key(|&index| (random_xy(index, iteration as u32).to_bits(), index));
+
+        for &index in &order {
+            let index = index as usize;
+            let neighbors = adjacency.neighbors(index);
+            if neighbors.is_empty() { continue; }
+
+            neighbor_labels.clear();
+            neighbor_labels.extend(neighbors.iter().map(|&n| labels[n as usize]));
+            neighbor_labels.sort_unstable();
+
+            let current = labels[index];
+            let (mut best, mut best_count, mut is_current_best) = (current, 0, false);
+            let mut run_start = 0;
+            for i in 1..=neighbor_labels.len() {
+                if i == neighbor_labels.len() || neighbor_labels[i] != neighbor_labels[run_start] {
+                    let (label, count) = (neighbor_labels[run_start], i - run_start);
+                    if count > best_count {
+                        best = label;
+                        best_count = count;
+                        is_current_best = label == current;
+                    } else if count == best_count {
+                        is_current_best |= label == current;
+                        if (hash(label), label) < (hash(best), best) { best = label; }
+                    }
+                    run_start = i;
+                }
+            }
+
+            if !is_current_best && best != current {
+                labels[index] = best;
+                is_changed = true;
+            }
+        }
+
+        if !is_changed { break; }
+    }
+
+    // 压缩为连续编号
+    let mut compact: HashMap<u32, u32> = HashMap::new();
+    let labels = labels.iter().enumerate().map(|(index, &label)| {
+        if adjacency.degree(index) == 0 { return NO_GROUP; }
+        let next = compact.len() as u32;
+        *compact.entry(label).or_insert(next)
+    }).collect();
+
+    (labels, compact.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 两个 6 节点的完全图，由 5 - 6 一条边相连
+    fn bridged_cliques() -> Vec<u32> {
+        let mut edges = vec![];
+        for offset in [0u32, 6] {
+            for a in 0..6 {
+                for b in a + 1..6 {
+                    edges.extend([offset + a, offset + b]);
+                }
+            }
+        }
+        edges.extend([5, 6]);
+        edges
+    }
+
+    #[test]
+    fn separates_bridged_cliques() {
+        let (labels, group_count) = label_propagation(13, &bridged_cliques());
+        assert_eq!(group_count, 2);
+        assert!(labels[..6].iter().all(|&label| label == labels[0]));
+        assert!(labels[6..12].iter().all(|&label| label == labels[6]));
+        assert_ne!(labels[0], labels[6]);
+        // 孤立节点不分组
+        assert_eq!(labels[12], NO_GROUP);
+        assert_eq!(label_propagation(13, &bridged_cliques()).0, labels);
+    }
+
+    #[test]
+    fn labels_equal_attributes_equally() {
+        let values = ["a", "b", " a ", "", "c", "b"];
+        let (labels, group_count) = attribute_labels(7, values.into_iter());
+        assert_eq!(group_count, 3);
+        assert_eq!(labels, vec![0, 1, 0, NO_GROUP, 2, 1, NO_GROUP]);
+    }
+
+    #[test]
+    fn groups_cover_each_node_once() {
+        let labels = vec![2, 0, NO_GROUP, 2, 1, 0, 2];
+        let groups = ClusterGroups::from_labels(labels.clone(), 3);
+
+        assert_eq!(groups.ranges, vec![[0, 2], [2, 3], [3, 6]]);
+        for (group, range) in groups.ranges.iter().enumerate() {
+            assert!(groups.members[range[0] as usize..range[1] as usize].iter().all(|&member| labels[member as usize] == group as u32));
+        }
+        let mut members = groups.members.clone();
+        members.sort_unstable();
+        assert_eq!(members, vec![0, 1, 3, 4, 5, 6]);
+        assert_eq!(groups.node_group, labels);
+    }
+}
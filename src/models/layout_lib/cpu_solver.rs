@@ -0,0 +1,316 @@
+use rayon::prelude::*;
+
+use super::barnes_hut::Octree;
+use super::cluster::ClusterGroups;
+use super::engine::LayoutEngine;
+use super::graph::Adjacency;
+use super::random::{hash, random_xy};
+
+// 须同步修改 CS_graph_solver.wgsl 中 cal_gravity_force / main / displacement / cluster_repulsion 的参数
+const GRAVITY: f32 = 0.5;
+const SPRING: f32 = 100.0;
+const GLOBAL_SPEED: f32 = 1.0;
+const STEP_SCALE: f32 = 0.01;
+const CLUSTER_REPULSION_SCALE: f32 = 0.0003 * 0.25;
+const CLUSTER_SOFTENING_SQ: f32 = 0.05 * 0.05;
+
+// Cluster 引力的分组与系数，与 ComputeOptions 中的同名参数相同
+struct ClusterForce {
+    groups:     ClusterGroups,
+    attraction: f32,
+    repulsion:  f32,
+}
+
+// CS_graph_solver.wgsl 的 CPU 实现，每次迭代的受力与 GPU 相同，只是求和顺序不同
+// 用于没有 GPU 适配器的环境，以及作为 GPU 结果的参照
+pub struct CpuLayoutEngine {
+    adjacency:  Adjacency,
+    // 与 cal_mass 相同，为 1 加上作为边端点的次数
+    masses:     Vec<f32>,
+    positions:  Vec<f32>,
+    prev_force: Vec<[f32; 3]>,
+    pinned:     Vec<bool>,
+    // 与 NODE_FLAG_HIDDEN 相同，隐藏的节点不移动，也不对其他节点产生任何力
+    hidden:     Vec<bool>,
+    cluster:    Option<ClusterForce>,
+    frame:      u32,
+}
+
+impl CpuLayoutEngine {
+
+    // 初始坐标与 gen_node 相同，由 seed 与第 0 帧得到
+    pub fn new(node_count: usize, source_target_list: &[u32], seed: u32) -> Self {
+
+        let mut masses = vec![1.0f32; node_count];
+        for &node in source_target_list {
+            if (node as usize) < node_count { masses[node as usize] += 1.0; }
+        }
+
+        let positions = (0..node_count * 3).into_par_iter()
+            .map(|index| random_xy((index / 3) as u32, hash(seed).wrapping_add((index % 3) as u32)) * 2.0 - 1.0)
+            .collect();
+
+        Self {
+            adjacency: Adjacency::from_edges(node_count, source_target_list),
+            masses,
+            positions,
+            prev_force: vec![[0.0; 3]; node_count],
+            pinned: vec![false; node_count],
+            hidden: vec![false; node_count],
+            cluster: None,
+            // gen_node 之后 GPU 的帧号为 1
+            frame: 1,
+        }
+    }
+
+    pub fn set_pinned(&mut self, index: u32, is_pinned: bool) {
+        if let Some(pinned) = self.pinned.get_mut(index as usize) {
+            *pinned = is_pinned;
+        }
+    }
+
+    pub fn set_hidden(&mut self, index: u32, is_hidden: bool) {
+        if let Some(hidden) = self.hidden.get_mut(index as usize) {
+            *hidden = is_hidden;
+        }
+    }
+
+    pub fn set_cluster_groups(&mut self, groups: Option<ClusterGroups>, attraction: f32, repulsion: f32) {
+        self.cluster = groups
+            .filter(|groups| groups.group_count > 0 && groups.node_group.len() == self.masses.len())
+            .map(|groups| ClusterForce { groups, attraction, repulsion });
+    }
+
+    fn position(&self, index: usize) -> [f32; 3] {
+        [self.positions[index * 3], self.positions[index * 3 + 1], self.positions[index * 3 + 2]]
+    }
+
+    // cluster_centroid 与 cluster_repulsion，返回每组的质心与分组间斥力
+    fn cluster_forces(&self, cluster: &ClusterForce) -> Vec<([f32; 3], [f32; 3])> {
+
+        let centroids: Vec<([f32; 3], f32)> = cluster.groups.ranges.par_iter().map(|range| {
+            let mut sum = [0.0f32; 3];
+            let mut count = 0.0f32;
+            for &member in &cluster.groups.members[range[0] as usize..range[1] as usize] {
+                if self.hidden[member as usize] { continue; }
+                let position = self.position(member as usize);
+                for axis in 0..3 { sum[axis] += position[axis]; }
+                count += 1.0;
+            }
+            (sum.map(|value| value / count.max(1.0)), count)
+        }).collect();
+
+        (0..centroids.len()).into_par_iter().map(|group| {
+            let (centroid, _) = centroids[group];
+            let mut force = [0.0f32; 3];
+            for (other, &(other_centroid, count)) in centroids.iter().enumerate() {
+                if other == group { continue; }
+                let dp = [0, 1, 2].map(|axis| centroid[axis] - other_centroid[axis]);
+                let dist2 = (dp[0] * dp[0] + dp[1] * dp[1] + dp[2] * dp[2]).max(CLUSTER_SOFTENING_SQ);
+                for axis in 0..3 { force[axis] += dp[axis] * count / dist2; }
+            }
+            (centroid, force.map(|value| value * CLUSTER_REPULSION_SCALE * cluster.repulsion))
+        }).collect()
+    }
+}
+
+impl LayoutEngine for CpuLayoutEngine {
+
+    fn name(&self) -> String {
+        format!("CPU ({} threads)", rayon::current_num_threads())
+    }
+
+    fn node_count(&self) -> usize {
+        self.masses.len()
+    }
+
+    fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    fn step(&mut self) {
+
+        let tree = if self.hidden.contains(&true) {
+            let visible = (0..self.node_count() as u32).filter(|&index| !self.hidden[index as usize]).collect();
+            Octree::build_subset(&self.positions, &self.masses, visible)
+        } else {
+            Octree::build(&self.positions, &self.masses)
+        };
+        let cluster_forces = self.cluster.as_ref().map(|cluster| self.cluster_forces(cluster));
+
+        // 与 compute 中 Kernel 的顺序相同：引力、斥力、Cluster、弹簧力
+        let forces: Vec<[f32; 3]> = (0..self.node_count()).into_par_iter().map(|index| {
+            if self.hidden[index] { return [0.0; 3]; }
+            let position = self.position(index);
+            let mass = self.masses[index];
+
+            let mut force = position.map(|value| -value * GRAVITY);
+
+            let repulsion = tree.repulsion(index, &self.positions, &self.masses);
+            for axis in 0..3 { force[axis] += repulsion[axis]; }
+
+            if let (Some(cluster), Some(cluster_forces)) = (&self.cluster, &cluster_forces) {
+                if let Some((centroid, group_force)) = cluster_forces.get(cluster.groups.node_group[index] as usize) {
+                    for axis in 0..3 {
+                        force[axis] += ((centroid[axis] - position[axis]) * cluster.attraction + group_force[axis]) * mass;
+                    }
+                }
+            }
+
+            let mut spring = [0.0f32; 3];
+            // 隐藏节点的边在 GPU 上已被删除
+            for &neighbor in self.adjacency.neighbors(index) {
+                if self.hidden[neighbor as usize] { continue; }
+                let other = self.position(neighbor as usize);
+                for axis in 0..3 { spring[axis] += other[axis] - position[axis]; }
+            }
+            for axis in 0..3 { force[axis] += spring[axis] * SPRING; }
+
+            force
+        }).collect();
+
+        // displacement
+        let masses = &self.masses;
+        let pinned = &self.pinned;
+        let hidden = &self.hidden;
+        self.positions.par_chunks_exact_mut(3)
+            .zip(self.prev_force.par_iter_mut())
+            .zip(forces.par_iter())
+            .enumerate()
+            .for_each(|(index, ((position, prev_force), force))| {
+                let d_force = [0, 1, 2].map(|axis| force[axis] - prev_force[axis]);
+                let swg = (d_force[0] * d_force[0] + d_force[1] * d_force[1] + d_force[2] * d_force[2]).sqrt();
+                let factor = GLOBAL_SPEED / (1.0 + (GLOBAL_SPEED * swg).sqrt()) / masses[index];
+                *prev_force = *force;
+                if pinned[index] || hidden[index] { return; }
+                for axis in 0..3 { position[axis] += force[axis] * factor * STEP_SCALE; }
+            });
+
+        self.frame += 1;
+    }
+
+    fn read_positions(&mut self) -> Vec<f32> {
+        self.positions.clone()
+    }
+
+    // 与 set_position 相同，清空之前的受力
+    fn set_positions(&mut self, positions: &[f32]) {
+        let length = self.positions.len().min(positions.len());
+        self.positions[..length].copy_from_slice(&positions[..length]);
+        self.prev_force.iter_mut().for_each(|force| *force = [0.0; 3]);
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::models::data_model::DataModel;
+    use crate::models::graphics_model::create_gpu_layout_engine;
+    use crate::models::layout_lib::layout_metrics;
+    use crate::utils::csv_loader::read_edge_pairs;
+
+    fn example_graph() -> (usize, Vec<u32>) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples").join("dataset").join("small_data_edge_raw.csv");
+        let source_target_list: Vec<u32> = read_edge_pairs(&path, 0, 1).unwrap()
+            .into_iter()
+            .flat_map(|(source, target)| [source, target])
+            .collect();
+        let node_count = source_target_list.iter().max().map_or(0, |&max_id| max_id as usize + 1);
+        (node_count, source_target_list)
+    }
+
+    // 坐标之差的最大值，相对于布局的范围
+    fn max_relative_difference(a: &[f32], b: &[f32]) -> f32 {
+        let extent = a.iter().fold(0.0f32, |extent, value| extent.max(value.abs()));
+        a.iter().zip(b).fold(0.0f32, |difference, (a, b)| difference.max((a - b).abs())) / extent
+    }
+
+    #[test]
+    fn is_deterministic() {
+        let (node_count, source_target_list) = example_graph();
+        let mut a = CpuLayoutEngine::new(node_count, &source_target_list, 7);
+        let mut b = CpuLayoutEngine::new(node_count, &source_target_list, 7);
+        a.run(20);
+        b.run(20);
+        assert_eq!(a.read_positions(), b.read_positions());
+        assert_eq!(a.frame(), 21);
+    }
+
+    #[test]
+    fn lays_out_example_dataset() {
+        let (node_count, source_target_list) = example_graph();
+        let mut engine = CpuLayoutEngine::new(node_count, &source_target_list, 1);
+        let initial = layout_metrics(node_count, &source_target_list, &engine.read_positions());
+
+        engine.run(300);
+        let positions = engine.read_positions();
+        assert!(positions.iter().all(|value| value.is_finite()));
+
+        let metrics = layout_metrics(node_count, &source_target_list, &positions);
+        assert!(metrics.stress < initial.stress * 0.5, "stress {} -> {}", initial.stress, metrics.stress);
+        assert!(metrics.neighborhood_preservation > initial.neighborhood_preservation * 2.0,
+            "neighborhood preservation {} -> {}", initial.neighborhood_preservation, metrics.neighborhood_preservation);
+    }
+
+    #[test]
+    fn keeps_pinned_nodes() {
+        let (node_count, source_target_list) = example_graph();
+        let mut engine = CpuLayoutEngine::new(node_count, &source_target_list, 1);
+        let initial = engine.read_positions();
+        engine.set_pinned(0, true);
+        engine.run(10);
+        let positions = engine.read_positions();
+        assert_eq!(positions[..3], initial[..3]);
+        assert_ne!(positions[3..6], initial[3..6]);
+    }
+
+    #[test]
+    fn hidden_nodes_exert_no_force() {
+        let (node_count, source_target_list) = example_graph();
+        let mut near = CpuLayoutEngine::new(node_count, &source_target_list, 1);
+        let mut far = CpuLayoutEngine::new(node_count, &source_target_list, 1);
+        near.set_hidden(0, true);
+        far.set_hidden(0, true);
+
+        // 隐藏的节点移到远处后，其余节点的结果不变
+        let mut positions = near.read_positions();
+        near.set_positions(&positions);
+        positions[..3].copy_from_slice(&[1000.0, -1000.0, 1000.0]);
+        far.set_positions(&positions);
+
+        near.run(10);
+        far.run(10);
+        let (near, far) = (near.read_positions(), far.read_positions());
+        assert_eq!(far[..3], positions[..3]);
+        assert_eq!(near[3..], far[3..]);
+        assert_ne!(near[3..], positions[3..]);
+    }
+
+    // GPU 与 CPU 从相同的初始坐标出发，前几次迭代只有求和顺序的误差
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn matches_gpu_solver() {
+        let (node_count, source_target_list) = example_graph();
+        let mut model = DataModel::default();
+        model.status.node_count = node_count;
+        model.status.edge_count = source_target_list.len() / 2;
+        model.source_target_list = Some(source_target_list.clone());
+
+        let mut gpu = create_gpu_layout_engine(&mut model, 3).unwrap();
+        let mut cpu = CpuLayoutEngine::new(node_count, &source_target_list, 3);
+
+        let difference = max_relative_difference(&LayoutEngine::read_positions(&mut gpu), &cpu.read_positions());
+        assert!(difference < 1e-6, "initial positions differ by {}", difference);
+
+        for iteration in 1..=5 {
+            gpu.step();
+            cpu.step();
+            let difference = max_relative_difference(&LayoutEngine::read_positions(&mut gpu), &cpu.read_positions());
+            assert!(difference < 1e-3, "iteration {} differs by {}", iteration, difference);
+        }
+        assert_eq!(LayoutEngine::frame(&gpu), cpu.frame());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::models::data_model::DataModel;
+
+// 布局计算的后端
+// GPU 实现为 graphics_model.rs 中的 GraphicsResources，CPU 实现为 cpu_solver.rs 中的 CpuLayoutEngine
+// 坐标均为长度 node_count * 3 的 x / y / z
+pub trait LayoutEngine {
+
+    fn name(&self) -> String;
+
+    fn node_count(&self) -> usize;
+
+    // 已运行的计算帧数，参与随机数种子
+    fn frame(&self) -> u32;
+
+    // 运行一次 Force Atlas 2 迭代
+    fn step(&mut self);
+
+    fn read_positions(&mut self) -> Vec<f32>;
+
+    fn set_positions(&mut self, positions: &[f32]);
+
+    // 检查计算是否出错，GPU 在此取回 Kernel 状态并自动恢复，出错后结果不再可信
+    fn poll(&mut self, _model: &DataModel) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn run(&mut self, iterations: u32) {
+        for _ in 0..iterations {
+            self.step();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::f32::consts::{FRAC_PI_4, PI};
+
+use strum::Display;
+
+// Web Mercator 的纬度范围，超出时截断，投影后 y 落在 [-1, 1]
+pub const MERCATOR_MAX_LATITUDE: f32 = 85.051_13;
+
+// 须同步修改 S_edge.wgsl 与 S_axis.wgsl 中的 PROJECTION_*
+#[derive(Display, PartialEq, Clone, Copy)]
+pub enum GeoProjection {
+    // 单位球面，y 轴为地轴
+    Globe,
+    // x = 经度 / π，y ∈ [-1, 1]
+    Mercator,
+    // x = 经度 / π，y = 纬度 / π
+    Equirectangular,
+}
+
+impl GeoProjection {
+    pub fn shader_code(&self) -> u32 {
+        match self {
+            GeoProjection::Globe => 1,
+            GeoProjection::Mercator => 2,
+            GeoProjection::Equirectangular => 3,
+        }
+    }
+}
+
+// 经纬度（角度）投影为坐标
+pub fn project(latitude: f32, longitude: f32, projection: GeoProjection) -> [f32; 3] {
+
+    let lambda = longitude.to_radians();
+
+    match projection {
+        GeoProjection::Globe => {
+            let phi = latitude.to_radians();
+            [phi.cos() * lambda.sin(), phi.sin(), phi.cos() * lambda.cos()]
+        }
+        GeoProjection::Mercator => {
+            let phi = latitude.clamp(-MERCATOR_MAX_LATITUDE, MERCATOR_MAX_LATITUDE).to_radians();
+            [lambda / PI, (FRAC_PI_4 + phi * 0.5).tan().ln() / PI, 0.0]
+        }
+        GeoProjection::Equirectangular => {
+            [lambda / PI, latitude.clamp(-90.0, 90.0).to_radians() / PI, 0.0]
+        }
+    }
+}
+
+// Geographic 布局
+// 纬度、经度为角度，经度超出 [-180, 180] 时环绕；缺失或无法解析的节点放在原点
+// 返回坐标与无效节点的数量
+pub fn geo_layout<'a>(
+    node_count: usize,
+    latitudes: impl Iterator<Item = &'a str>,
+    longitudes: impl Iterator<Item = &'a str>,
+    projection: GeoProjection,
+) -> (Vec<f32>, usize) {
+
+    let mut positions = vec![0.0; node_count * 3];
+    let mut valid_count = 0;
+
+    for (index, (latitude, longitude)) in latitudes.zip(longitudes).enumerate().take(node_count) {
+        let (latitude, longitude) = match (latitude.trim().parse::<f32>(), longitude.trim().parse::<f32>()) {
+            (Ok(latitude), Ok(longitude)) if latitude.is_finite() && longitude.is_finite() => (latitude, longitude),
+            _ => continue,
+        };
+        if latitude.abs() > 90.0 { continue; }
+
+        let longitude = (longitude + 180.0).rem_euclid(360.0) - 180.0;
+        positions[index * 3..index * 3 + 3].copy_from_slice(&project(latitude, longitude, projection));
+        valid_count += 1;
+    }
+
+    (positions, node_count - valid_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: [f32; 3], expected: [f32; 3]) {
+        assert!((0..3).all(|axis| (actual[axis] - expected[axis]).abs() < 1e-5), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn projects_known_points() {
+        use GeoProjection::*;
+
+        assert_near(project(0.0, 0.0, Globe), [0.0, 0.0, 1.0]);
+        assert_near(project(0.0, 90.0, Globe), [1.0, 0.0, 0.0]);
+        assert_near(project(90.0, 0.0, Globe), [0.0, 1.0, 0.0]);
+        assert_near(project(-90.0, 45.0, Globe), [0.0, -1.0, 0.0]);
+
+        assert_near(project(0.0, 0.0, Mercator), [0.0, 0.0, 0.0]);
+        assert_near(project(0.0, 180.0, Mercator), [1.0, 0.0, 0.0]);
+        assert_near(project(0.0, -180.0, Mercator), [-1.0, 0.0, 0.0]);
+        // 纬度在截断处投影到 y = ±1，超出的纬度被截断
+        assert!((project(MERCATOR_MAX_LATITUDE, 0.0, Mercator)[1] - 1.0).abs() < 1e-4);
+        assert_eq!(project(90.0, 0.0, Mercator), project(MERCATOR_MAX_LATITUDE, 0.0, Mercator));
+        assert!((project(-90.0, 0.0, Mercator)[1] + 1.0).abs() < 1e-4);
+
+        assert_near(project(0.0, 0.0, Equirectangular), [0.0, 0.0, 0.0]);
+        assert_near(project(90.0, -180.0, Equirectangular), [-1.0, 0.5, 0.0]);
+        assert_near(project(-45.0, 90.0, Equirectangular), [0.5, -0.25, 0.0]);
+    }
+
+    #[test]
+    fn keeps_invalid_nodes_at_origin() {
+        let latitudes = ["10", "", "abc", "91", "NaN", "0", " -20 "];
+        let longitudes = ["20", "30", "40", "50", "60", "540", "inf"];
+        let (positions, invalid_count) = geo_layout(8, latitudes.into_iter(), longitudes.into_iter(), GeoProjection::Globe);
+
+        assert_eq!(positions.len(), 24);
+        assert!(positions.iter().all(|value| value.is_finite()));
+        assert_eq!(invalid_count, 6);
+        assert_near([positions[0], positions[1], positions[2]], project(10.0, 20.0, GeoProjection::Globe));
+        // 经度环绕到 [-180, 180)
+        assert_near([positions[15], positions[16], positions[17]], project(0.0, 180.0, GeoProjection::Globe));
+        for index in [1, 2, 3, 4, 6, 7] {
+            assert_eq!(positions[index * 3..index * 3 + 3], [0.0; 3], "node {}", index);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use rayon::prelude::*;
+
+// 压缩稀疏行（CSR）格式的无向邻接表
+// 由 source_target_list 构建，忽略自环，重边保留（相当于边权累加）
+pub struct Adjacency {
+    pub offsets: Vec<usize>,
+    pub neighbors: Vec<u32>,
+}
+
+impl Adjacency {
+    pub fn from_edges(node_count: usize, source_target_list: &[u32]) -> Self {
+        let mut offsets = vec![0usize; node_count + 1];
+
+        for edge in source_target_list.chunks_exact(2) {
+            let (source, target) = (edge[0] as usize, edge[1] as usize);
+            if source == target || source >= node_count || target >= node_count { continue; }
+            offsets[source + 1] += 1;
+            offsets[target + 1] += 1;
+        }
+
+        for i in 0..node_count {
+            offsets[i + 1] += offsets[i];
+        }
+
+        let mut cursor = offsets.clone();
+        let mut neighbors = vec![0u32; offsets[node_count]];
+
+        for edge in source_target_list.chunks_exact(2) {
+            let (source, target) = (edge[0] as usize, edge[1] as usize);
+            if source == target || source >= node_count || target >= node_count { continue; }
+            neighbors[cursor[source]] = target as u32;
+            cursor[source] += 1;
+            neighbors[cursor[target]] = source as u32;
+            cursor[target] += 1;
+        }
+
+        Self { offsets, neighbors }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn degree(&self, index: usize) -> usize {
+        self.offsets[index + 1] - self.offsets[index]
+    }
+
+    pub fn neighbors(&self, index: usize) -> &[u32] {
+        &self.neighbors[self.offsets[index]..self.offsets[index + 1]]
+    }
+}
+
+// 以固定大小分块求和，块内顺序累加，保证并行求和的结果与线程调度无关
+const SUM_CHUNK_SIZE: usize = 4096;
+
+pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.par_chunks(SUM_CHUNK_SIZE)
+        .zip(b.par_chunks(SUM_CHUNK_SIZE))
+        .map(|(a, b)| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>())
+        .collect::<Vec<f64>>()
+        .iter()
+        .sum()
+}
+
+// 连通分量，使用并查集（路径减半 + 按大小合并）
+// 返回每个节点的分量编号（按分量中最小节点编号的顺序，从 0 连续编号）与分量数
+pub fn connected_components(node_count: usize, source_target_list: &[u32]) -> (Vec<u32>, usize) {
+
+    let mut parent: Vec<u32> = (0..node_count as u32).collect();
+    let mut size = vec![1u32; node_count];
+
+    fn find(parent: &mut [u32], mut x: u32) -> u32 {
+        while parent[x as usize] != x {
+            parent[x as usize] = parent[parent[x as usize] as usize];
+            x = parent[x as usize];
+        }
+        x
+    }
+
+    for edge in source_target_list.chunks_exact(2) {
+        let (source, target) = (edge[0], edge[1]);
+        if source as usize >= node_count || target as usize >= node_count { continue; }
+        let (a, b) = (find(&mut parent, source), find(&mut parent, target));
+        if a == b { continue; }
+        let (small, large) = if size[a as usize] < size[b as usize] { (a, b) } else { (b, a) };
+        parent[small as usize] = large;
+        size[large as usize] += size[small as usize];
+    }
+
+    let mut component = vec![u32::MAX; node_count];
+    let mut labels = vec![0u32; node_count];
+    let mut component_count = 0u32;
+    for index in 0..node_count as u32 {
+        let root = find(&mut parent, index) as usize;
+        if component[root] == u32::MAX {
+            component[root] = component_count;
+            component_count += 1;
+        }
+        labels[index as usize] = component[root];
+    }
+
+    (labels, component_count as usize)
+}
@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+
+use rayon::prelude::*;
+
+pub const DEFAULT_HISTORY_INTERVAL: u32 = 50;
+pub const DEFAULT_HISTORY_MEMORY_CAP: usize = 256 * 1024 * 1024;
+
+// 一帧的节点坐标，每个分量按包围盒量化为 16 位整数，占原数据的一半
+pub struct LayoutSnapshot {
+    pub frame:  u32,
+    min:        [f32; 3],
+    scale:      [f32; 3],
+    data:       Vec<u16>,
+}
+
+impl LayoutSnapshot {
+
+    pub fn encode(frame: u32, positions: &[f32]) -> Self {
+
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for position in positions.chunks_exact(3) {
+            for axis in 0..3 {
+                if position[axis].is_finite() {
+                    min[axis] = min[axis].min(position[axis]);
+                    max[axis] = max[axis].max(position[axis]);
+                }
+            }
+        }
+        for axis in 0..3 {
+            if min[axis] > max[axis] { min[axis] = 0.0; max[axis] = 0.0; }
+        }
+        let scale = [0, 1, 2].map(|axis| (max[axis] - min[axis]) / u16::MAX as f32);
+
+        let data = positions.par_iter().enumerate().map(|(index, &value)| {
+            let axis = index % 3;
+            if scale[axis] > 0.0 && value.is_finite() {
+                ((value - min[axis]) / scale[axis]).round().clamp(0.0, u16::MAX as f32) as u16
+            } else {
+                0
+            }
+        }).collect();
+
+        Self { frame, min, scale, data }
+    }
+
+    pub fn decode(&self) -> Vec<f32> {
+        self.data.par_iter().enumerate()
+            .map(|(index, &value)| self.min[index % 3] + value as f32 * self.scale[index % 3])
+            .collect()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.data.len() / 3
+    }
+
+    pub fn size(&self) -> usize {
+        self.data.len() * 2
+    }
+}
+
+// 持续计算时每隔 interval 个计算帧记录一次坐标，总大小超过 memory_cap 时丢弃最早的记录
+pub struct LayoutHistory {
+    pub snapshots:      VecDeque<LayoutSnapshot>,
+    pub is_recording:   bool,
+    pub interval:       u32,
+    pub memory_cap:     usize,
+    // 正在查看的记录，之后恢复计算时丢弃其后的记录
+    pub selected:       Option<usize>,
+    last_frame:         Option<u32>,
+}
+
+impl Default for LayoutHistory {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            is_recording: false,
+            interval: DEFAULT_HISTORY_INTERVAL,
+            memory_cap: DEFAULT_HISTORY_MEMORY_CAP,
+            selected: None,
+            last_frame: None,
+        }
+    }
+}
+
+impl LayoutHistory {
+
+    pub fn is_due(&self, frame: u32) -> bool {
+        // 回到某条记录后，间隔从该记录的帧开始计算
+        let last_frame = match self.selected.and_then(|index| self.snapshots.get(index)) {
+            Some(snapshot) => Some(snapshot.frame),
+            None => self.last_frame,
+        };
+        self.is_recording && last_frame.map_or(true, |last| frame.wrapping_sub(last) >= self.interval.max(1))
+    }
+
+    // 发起异步回读时即计入间隔，结果取回后由 push 保存
+    pub fn request(&mut self, frame: u32) {
+        // 从选中的记录继续计算时，其后的记录被新的结果替换
+        self.truncate_after_selected();
+        self.last_frame = Some(frame);
+    }
+
+    pub fn push(&mut self, frame: u32, positions: &[f32]) {
+        self.truncate_after_selected();
+        let snapshot = LayoutSnapshot::encode(frame, positions);
+        // 回读结果滞后若干帧，不回退已发起的回读
+        self.last_frame = Some(self.last_frame.map_or(frame, |last| last.max(frame)));
+        // 单帧超过上限时不记录
+        if snapshot.size() > self.memory_cap { return; }
+        self.snapshots.push_back(snapshot);
+        self.enforce_memory_cap();
+    }
+
+    pub fn enforce_memory_cap(&mut self) {
+        while self.size() > self.memory_cap {
+            self.snapshots.pop_front();
+            self.selected = self.selected.and_then(|index| index.checked_sub(1));
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.snapshots.iter().map(LayoutSnapshot::size).sum()
+    }
+
+    // 最接近 frame 的记录
+    pub fn nearest(&self, frame: u32) -> Option<usize> {
+        (0..self.snapshots.len()).min_by_key(|&index| (self.snapshots[index].frame as i64 - frame as i64).abs())
+    }
+
+    // 从选中的记录恢复计算，其后的记录不再对应
+    pub fn truncate_after_selected(&mut self) {
+        if let Some(selected) = self.selected.take() {
+            self.snapshots.truncate(selected + 1);
+            self.last_frame = self.snapshots.back().map(|snapshot| snapshot.frame);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.selected = None;
+        self.last_frame = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(node_count: usize, offset: f32) -> Vec<f32> {
+        (0..node_count * 3).map(|index| offset + (index as f32 * 0.618).sin() * 100.0 * (index % 3 + 1) as f32).collect()
+    }
+
+    fn history_with(frames: &[u32], node_count: usize) -> LayoutHistory {
+        let mut history = LayoutHistory { is_recording: true, ..Default::default() };
+        for &frame in frames {
+            history.push(frame, &positions(node_count, frame as f32));
+        }
+        history
+    }
+
+    #[test]
+    fn quantization_error_is_bounded() {
+        let mut positions = positions(1000, 0.0);
+        positions[5] = f32::NAN;
+
+        let snapshot = LayoutSnapshot::encode(7, &positions);
+        let decoded = snapshot.decode();
+        assert_eq!(snapshot.frame, 7);
+        assert_eq!(snapshot.node_count(), 1000);
+        assert_eq!(snapshot.size(), positions.len() * 2);
+
+        // 每个分量的误差不超过半个量化步长
+        for axis in 0..3 {
+            let values = positions.iter().skip(axis).step_by(3).filter(|value| value.is_finite());
+            let min = values.clone().fold(f32::INFINITY, |a, &b| a.min(b));
+            let max = values.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+            let bound = (max - min) / u16::MAX as f32 / 2.0 + (max - min) * f32::EPSILON * 4.0;
+            for index in (axis..positions.len()).step_by(3).filter(|&index| positions[index].is_finite()) {
+                assert!((decoded[index] - positions[index]).abs() <= bound, "axis {} index {}", axis, index);
+            }
+        }
+        // 无效坐标落在包围盒的最小值上
+        assert!(decoded[5].is_finite());
+    }
+
+    #[test]
+    fn encodes_flat_axes() {
+        let positions = vec![1.0, 2.0, 0.0, 3.0, 2.0, 0.0];
+        assert_eq!(LayoutSnapshot::encode(0, &positions).decode(), positions);
+    }
+
+    #[test]
+    fn evicts_oldest_snapshots_over_memory_cap() {
+        let mut history = history_with(&[0, 50, 100], 10);
+        let snapshot_size = history.snapshots[0].size();
+        history.selected = Some(2);
+
+        history.memory_cap = snapshot_size * 2;
+        history.enforce_memory_cap();
+        assert_eq!(history.snapshots.iter().map(|snapshot| snapshot.frame).collect::<Vec<u32>>(), vec![50, 100]);
+        assert_eq!(history.selected, Some(1));
+        assert!(history.size() <= history.memory_cap);
+
+        // 单帧超过上限时不记录
+        history.memory_cap = snapshot_size * 2;
+        history.selected = None;
+        history.push(150, &positions(1000, 0.0));
+        assert_eq!(history.snapshots.back().map(|snapshot| snapshot.frame), Some(100));
+    }
+
+    #[test]
+    fn truncates_after_selected_snapshot() {
+        let mut history = history_with(&[0, 50, 100, 150], 10);
+        history.selected = Some(1);
+
+        // 回到选中记录后，间隔从该记录的帧开始计算
+        assert!(!history.is_due(99));
+        assert!(history.is_due(100));
+
+        history.truncate_after_selected();
+        assert_eq!(history.selected, None);
+        assert_eq!(history.snapshots.iter().map(|snapshot| snapshot.frame).collect::<Vec<u32>>(), vec![0, 50]);
+        assert!(!history.is_due(99));
+        assert!(history.is_due(100));
+
+        // 未选中时不截断
+        history.truncate_after_selected();
+        assert_eq!(history.snapshots.len(), 2);
+    }
+
+    #[test]
+    fn counts_requested_frames_towards_interval() {
+        let mut history = history_with(&[0], 10);
+        history.request(50);
+        assert!(!history.is_due(60));
+
+        // 滞后的回读结果不回退间隔
+        history.request(100);
+        history.push(50, &positions(10, 0.0));
+        assert_eq!(history.snapshots.len(), 2);
+        assert!(!history.is_due(149));
+        assert!(history.is_due(150));
+    }
+}
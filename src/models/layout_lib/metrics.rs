@@ -0,0 +1,393 @@
+use std::collections::{HashSet, VecDeque};
+
+use rayon::prelude::*;
+
+use super::graph::Adjacency;
+use super::random::hash;
+
+// 计算应力时 BFS 的起点数
+const STRESS_PIVOT_COUNT: usize = 32;
+
+// 邻域保持度的采样节点数，度数超过上限的节点不参与采样
+const NEIGHBORHOOD_SAMPLE_COUNT: usize = 128;
+const NEIGHBORHOOD_MAX_DEGREE: usize = 64;
+
+// 边数超过该值时不统计交叉数
+pub const MAX_CROSSING_EDGE_COUNT: usize = 100_000;
+// 边经过的网格单元总数超过该值时不统计交叉数，限制长边很多时的内存与时间
+const MAX_CROSSING_CELL_ENTRIES: usize = 8_000_000;
+// 光栅化时格子边界的容差（格子边长的比例），使交点所在的格子一定包含两条边
+const CELL_EPSILON: f32 = 1e-3;
+
+// z 方向范围不超过 xy 范围的该比例时视为二维布局
+const FLAT_RATIO: f32 = 1e-4;
+
+pub const MAX_HISTORY_LENGTH: usize = 512;
+
+pub const DEFAULT_METRICS_INTERVAL: u32 = 100;
+
+// 布局质量指标
+#[derive(Clone, Copy, Default)]
+pub struct LayoutMetrics {
+    // 归一化应力，按最优缩放后的 Σ w(αl - d)² / Σ w d²，w = d⁻²，范围 [0, 1]，越小越好
+    pub stress: f32,
+    // 边长方差除以边长均值的平方，与布局的整体缩放无关
+    pub edge_length_variance: f32,
+    // 图中邻居与布局空间 k 近邻的 Jaccard 相似度均值，k 为节点的度数，范围 [0, 1]，越大越好
+    pub neighborhood_preservation: f32,
+    // 二维布局的边交叉数，三维布局或边数过多时为 None
+    pub edge_crossings: Option<u64>,
+}
+
+// 指标历史，按计算帧记录
+pub struct MetricsHistory {
+    pub entries: VecDeque<(u32, LayoutMetrics)>,
+    pub is_auto: bool,
+    // 自动计算的间隔（计算帧数）
+    pub interval: u32,
+    pub last_frame: Option<u32>,
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            is_auto: false,
+            interval: DEFAULT_METRICS_INTERVAL,
+            last_frame: None,
+        }
+    }
+}
+
+impl MetricsHistory {
+
+    pub fn push(&mut self, frame: u32, metrics: LayoutMetrics) {
+        if self.entries.len() == MAX_HISTORY_LENGTH {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((frame, metrics));
+        // 异步计算的结果滞后若干帧，不回退已发起的计算
+        self.last_frame = Some(self.last_frame.map_or(frame, |last| last.max(frame)));
+    }
+
+    // 发起异步回读时即计入间隔，结果取回后由 push 保存
+    pub fn request(&mut self, frame: u32) {
+        self.last_frame = Some(frame);
+    }
+
+    pub fn latest(&self) -> Option<&LayoutMetrics> {
+        self.entries.back().map(|(_, metrics)| metrics)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.last_frame = None;
+    }
+
+    pub fn is_due(&self, frame: u32) -> bool {
+        self.is_auto && self.last_frame.is_none_or(|last| frame.wrapping_sub(last) >= self.interval.max(1))
+    }
+}
+
+// 在当前坐标上计算全部指标，positions 为每个节点 x / y / z
+pub fn layout_metrics(node_count: usize, source_target_list: &[u32], positions: &[f32]) -> LayoutMetrics {
+
+    if node_count == 0 { return LayoutMetrics::default(); }
+
+    let adjacency = Adjacency::from_edges(node_count, source_target_list);
+
+    LayoutMetrics {
+        stress: stress(&adjacency, positions),
+        edge_length_variance: edge_length_variance(source_target_list, positions),
+        neighborhood_preservation: neighborhood_preservation(&adjacency, positions),
+        edge_crossings: edge_crossings(source_target_list, positions),
+    }
+}
+
+fn distance(positions: &[f32], a: usize, b: usize) -> f32 {
+    let dx = positions[a * 3] - positions[b * 3];
+    let dy = positions[a * 3 + 1] - positions[b * 3 + 1];
+    let dz = positions[a * 3 + 2] - positions[b * 3 + 2];
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+// 由哈希选取的采样节点，结果可复现
+fn sample_nodes(node_count: usize, sample_count: usize, filter: impl Fn(usize) -> bool) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..node_count).filter(|&index| filter(index)).collect();
+    if order.len() > sample_count {
+        order.sort_unstable_by_key(|&index| (hash(index as u32), index));
+        order.truncate(sample_count);
+    }
+    order
+}
+
+// 以 BFS 起点到所有可达节点的节点对近似全部节点对
+// 最优缩放 α = Σ(l/d) / Σ(l²/d²) 时，应力化简为 1 - (Σ l/d)² / (Σ l²/d² · N)
+fn stress(adjacency: &Adjacency, positions: &[f32]) -> f32 {
+
+    let node_count = adjacency.node_count();
+    let pivots = sample_nodes(node_count, STRESS_PIVOT_COUNT, |index| adjacency.degree(index) > 0);
+
+    let (a, b, n) = pivots.par_iter().map(|&pivot| {
+        let mut hops = vec![u32::MAX; node_count];
+        let mut queue = VecDeque::new();
+        hops[pivot] = 0;
+        queue.push_back(pivot as u32);
+
+        let (mut a, mut b, mut n) = (0.0f64, 0.0f64, 0.0f64);
+        while let Some(index) = queue.pop_front() {
+            let index = index as usize;
+            if index != pivot {
+                let d = hops[index] as f64;
+                let l = distance(positions, pivot, index) as f64;
+                a += l / d;
+                b += l * l / (d * d);
+                n += 1.0;
+            }
+            for &neighbor in adjacency.neighbors(index) {
+                if hops[neighbor as usize] == u32::MAX {
+                    hops[neighbor as usize] = hops[index] + 1;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        (a, b, n)
+    }).reduce(|| (0.0, 0.0, 0.0), |x, y| (x.0 + y.0, x.1 + y.1, x.2 + y.2));
+
+    if n == 0.0 || b == 0.0 { return 0.0; }
+    (1.0 - a * a / (b * n)).max(0.0) as f32
+}
+
+fn edge_length_variance(source_target_list: &[u32], positions: &[f32]) -> f32 {
+
+    let lengths: Vec<f64> = source_target_list.par_chunks_exact(2)
+        .filter(|edge| edge[0] != edge[1])
+        .map(|edge| distance(positions, edge[0] as usize, edge[1] as usize) as f64)
+        .collect();
+    if lengths.is_empty() { return 0.0; }
+
+    let mean = lengths.iter().sum::<f64>() / lengths.len() as f64;
+    if mean == 0.0 { return 0.0; }
+    let variance = lengths.iter().map(|l| (l - mean) * (l - mean)).sum::<f64>() / lengths.len() as f64;
+
+    (variance / (mean * mean)) as f32
+}
+
+fn neighborhood_preservation(adjacency: &Adjacency, positions: &[f32]) -> f32 {
+
+    let node_count = adjacency.node_count();
+    let samples = sample_nodes(node_count, NEIGHBORHOOD_SAMPLE_COUNT, |index| {
+        let degree = adjacency.degree(index);
+        degree > 0 && degree <= NEIGHBORHOOD_MAX_DEGREE
+    });
+    if samples.is_empty() { return 0.0; }
+
+    let total: f64 = samples.par_iter().map(|&index| {
+        let neighbors: HashSet<u32> = adjacency.neighbors(index).iter().copied().collect();
+        let k = neighbors.len().min(node_count - 1);
+
+        let mut others: Vec<(f32, u32)> = (0..node_count)
+            .filter(|&other| other != index)
+            .map(|other| (distance(positions, index, other), other as u32))
+            .collect();
+        others.select_nth_unstable_by(k - 1, |x, y| x.0.total_cmp(&y.0).then(x.1.cmp(&y.1)));
+
+        let overlap = others[..k].iter().filter(|(_, other)| neighbors.contains(other)).count();
+        overlap as f64 / (neighbors.len() + k - overlap) as f64
+    }).sum();
+
+    (total / samples.len() as f64) as f32
+}
+
+// 均匀网格加速的线段求交，只统计端点不共享的真正相交
+// 每条边只加入它经过的格子，同一对边只在交点所在的格子中计数
+fn edge_crossings(source_target_list: &[u32], positions: &[f32]) -> Option<u64> {
+
+    let edges: Vec<[u32; 2]> = source_target_list.chunks_exact(2)
+        .filter(|edge| edge[0] != edge[1])
+        .map(|edge| [edge[0], edge[1]])
+        .collect();
+    if edges.len() > MAX_CROSSING_EDGE_COUNT { return None; }
+
+    let node_count = positions.len() / 3;
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for index in 0..node_count {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(positions[index * 3 + axis]);
+            max[axis] = max[axis].max(positions[index * 3 + axis]);
+        }
+    }
+    let extent = (max[0] - min[0]).max(max[1] - min[1]);
+    if extent.is_nan() || extent <= 0.0 || max[2] - min[2] > extent * FLAT_RATIO { return None; }
+
+    let point = |index: u32| [positions[index as usize * 3], positions[index as usize * 3 + 1]];
+
+    let grid_size = ((edges.len() as f64).sqrt().ceil() as usize).clamp(1, 1024);
+    let cell_size = extent / grid_size as f32;
+    let cell = |value: f32, axis: usize| {
+        (((value - min[axis]) / extent * grid_size as f32).max(0.0) as usize).min(grid_size - 1)
+    };
+
+    let mut cells: Vec<Vec<u32>> = vec![vec![]; grid_size * grid_size];
+    let mut entry_count = 0;
+    for (e, edge) in edges.iter().enumerate() {
+        let (a, b) = (point(edge[0]), point(edge[1]));
+        let (low, high) = if a[1] <= b[1] { (a, b) } else { (b, a) };
+        let epsilon = cell_size * CELL_EPSILON;
+
+        // 逐行求线段在该行的 x 范围，只加入线段经过的格子
+        for y in cell(low[1] - epsilon, 1)..=cell(high[1] + epsilon, 1) {
+            let band_low = (min[1] + y as f32 * cell_size - epsilon).max(low[1]);
+            let band_high = (min[1] + (y + 1) as f32 * cell_size + epsilon).min(high[1]);
+            let x_at = |value: f32| low[0] + (high[0] - low[0]) * ((value - low[1]) / (high[1] - low[1])).clamp(0.0, 1.0);
+            let (x_low, x_high) = match high[1] > low[1] {
+                true => (x_at(band_low), x_at(band_high)),
+                false => (low[0], high[0]),
+            };
+            let x_range = cell(x_low.min(x_high) - epsilon, 0)..=cell(x_low.max(x_high) + epsilon, 0);
+
+            entry_count += x_range.clone().count();
+            if entry_count > MAX_CROSSING_CELL_ENTRIES { return None; }
+            for x in x_range {
+                cells[y * grid_size + x].push(e as u32);
+            }
+        }
+    }
+
+    let orientation = |p: [f32; 2], q: [f32; 2], r: [f32; 2]| {
+        let value = (q[0] - p[0]) as f64 * (r[1] - p[1]) as f64 - (q[1] - p[1]) as f64 * (r[0] - p[0]) as f64;
+        if value > 0.0 { 1 } else if value < 0.0 { -1 } else { 0 }
+    };
+
+    let count = cells.par_iter().enumerate().map(|(c, members)| {
+        let (x, y) = (c % grid_size, c / grid_size);
+        let mut count = 0u64;
+        for i in 0..members.len() {
+            for j in i + 1..members.len() {
+                let (e, f) = (members[i] as usize, members[j] as usize);
+
+                let (ea, eb) = (edges[e][0], edges[e][1]);
+                let (fa, fb) = (edges[f][0], edges[f][1]);
+                if ea == fa || ea == fb || eb == fa || eb == fb { continue; }
+
+                let (p1, p2, p3, p4) = (point(ea), point(eb), point(fa), point(fb));
+                let (o1, o2) = (orientation(p1, p2, p3), orientation(p1, p2, p4));
+                let (o3, o4) = (orientation(p3, p4, p1), orientation(p3, p4, p2));
+                if o1 * o2 >= 0 || o3 * o4 >= 0 { continue; }
+
+                // 交点 p1 + t (p2 - p1)
+                let cross = |u: [f32; 2], v: [f32; 2]| u[0] as f64 * v[1] as f64 - u[1] as f64 * v[0] as f64;
+                let (d1, d2) = ([p2[0] - p1[0], p2[1] - p1[1]], [p4[0] - p3[0], p4[1] - p3[1]]);
+                let t = cross([p3[0] - p1[0], p3[1] - p1[1]], d2) / cross(d1, d2);
+                let intersection = [p1[0] + (t * d1[0] as f64) as f32, p1[1] + (t * d1[1] as f64) as f32];
+                if cell(intersection[0], 0) == x && cell(intersection[1], 1) == y {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }).sum();
+
+    Some(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::layout_lib::random::random;
+
+    fn flat(points: &[[f32; 2]]) -> Vec<f32> {
+        points.iter().flat_map(|point| [point[0], point[1], 0.0]).collect()
+    }
+
+    #[test]
+    fn counts_edge_crossings() {
+        // 正方形的四条边与两条对角线，只有对角线相交
+        let positions = flat(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        let edges = [0, 1, 1, 2, 2, 3, 3, 0, 0, 2, 1, 3];
+        assert_eq!(edge_crossings(&edges, &positions), Some(1));
+
+        // 一组长的水平边与一组长的竖直边形成网格，交叉数为两组边数的乘积
+        let mut points = vec![];
+        let mut edges = vec![];
+        for index in 0..20 {
+            let offset = 0.5 + index as f32 * 0.9;
+            points.extend([[0.0, offset], [20.0, offset], [offset, 0.0], [offset, 20.0]]);
+            let base = index * 4;
+            edges.extend([base, base + 1, base + 2, base + 3]);
+        }
+        assert_eq!(edge_crossings(&edges, &flat(&points)), Some(400));
+
+        // 共享端点的边与三维布局不计数
+        assert_eq!(edge_crossings(&[0, 1, 0, 2], &flat(&[[0.0, 0.0], [1.0, 1.0], [1.0, -1.0]])), Some(0));
+        assert_eq!(edge_crossings(&[0, 1], &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]), None);
+    }
+
+    #[test]
+    fn matches_brute_force_crossings() {
+        let node_count = 200;
+        let positions: Vec<f32> = (0..node_count)
+            .flat_map(|index| [random(index * 2), random(index * 2 + 1), 0.0])
+            .collect();
+        let edges: Vec<u32> = (0..300u32).flat_map(|index| [hash(index) % node_count, hash(index + 1000) % node_count]).collect();
+
+        let point = |index: u32| [positions[index as usize * 3] as f64, positions[index as usize * 3 + 1] as f64];
+        let side = |p: [f64; 2], q: [f64; 2], r: [f64; 2]| ((q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])).signum();
+        let edge_list: Vec<&[u32]> = edges.chunks_exact(2).filter(|edge| edge[0] != edge[1]).collect();
+        let mut expected = 0;
+        for (i, e) in edge_list.iter().enumerate() {
+            for f in &edge_list[i + 1..] {
+                if e.iter().any(|index| f.contains(index)) { continue; }
+                let (p1, p2, p3, p4) = (point(e[0]), point(e[1]), point(f[0]), point(f[1]));
+                if side(p1, p2, p3) * side(p1, p2, p4) < 0.0 && side(p3, p4, p1) * side(p3, p4, p2) < 0.0 {
+                    expected += 1;
+                }
+            }
+        }
+        assert_eq!(edge_crossings(&edges, &positions), Some(expected));
+    }
+
+    #[test]
+    fn measures_stress() {
+        // 路径图等间距排成直线时，布局距离与图距离成比例
+        let path = [0, 1, 1, 2, 2, 3, 3, 4];
+        let line = flat(&[[0.0, 0.0], [2.0, 0.0], [4.0, 0.0], [6.0, 0.0], [8.0, 0.0]]);
+        let metrics = layout_metrics(5, &path, &line);
+        assert!(metrics.stress < 1e-6, "{}", metrics.stress);
+        assert!(metrics.edge_length_variance < 1e-6, "{}", metrics.edge_length_variance);
+
+        let folded = flat(&[[0.0, 0.0], [2.0, 0.0], [0.0, 0.1], [2.0, 0.1], [0.0, 0.2]]);
+        assert!(layout_metrics(5, &path, &folded).stress > 0.1);
+    }
+
+    #[test]
+    fn measures_neighborhood_preservation() {
+        let path = [0, 1, 1, 2, 2, 3, 3, 4];
+        let line = flat(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0], [4.0, 0.0]]);
+        assert_eq!(layout_metrics(5, &path, &line).neighborhood_preservation, 1.0);
+
+        // 打乱顺序后邻居不再是最近的节点
+        let shuffled = flat(&[[0.0, 0.0], [3.0, 0.0], [1.0, 0.0], [4.0, 0.0], [2.0, 0.0]]);
+        assert!(layout_metrics(5, &path, &shuffled).neighborhood_preservation < 0.5);
+    }
+
+    #[test]
+    fn schedules_delayed_results() {
+        let mut history = MetricsHistory { is_auto: true, interval: 10, ..Default::default() };
+        assert!(history.is_due(0));
+
+        // 发起回读后即计入间隔，滞后的结果不回退
+        history.request(20);
+        assert!(!history.is_due(25));
+        history.push(20, LayoutMetrics::default());
+        history.request(30);
+        history.push(20, LayoutMetrics::default());
+        assert_eq!(history.last_frame, Some(30));
+        assert!(history.is_due(40));
+
+        history.clear();
+        assert!(history.latest().is_none());
+        assert!(history.is_due(0));
+    }
+}
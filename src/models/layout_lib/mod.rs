@@ -0,0 +1,27 @@
+pub mod barnes_hut;
+pub mod cluster;
+pub mod cpu_solver;
+pub mod engine;
+pub mod geo;
+pub mod graph;
+pub mod history;
+pub mod metrics;
+pub mod multilevel;
+pub mod pack;
+pub mod random;
+pub mod spectral;
+pub mod tree;
+
+pub use {
+    cluster::ClusterGroups,
+    cpu_solver::CpuLayoutEngine,
+    engine::LayoutEngine,
+    geo::{geo_layout, GeoProjection},
+    graph::Adjacency,
+    history::{LayoutHistory, LayoutSnapshot},
+    metrics::{layout_metrics, LayoutMetrics, MetricsHistory},
+    multilevel::MultilevelLayout,
+    pack::pack_components,
+    spectral::spectral_layout,
+    tree::{tree_layout, TreeStyle},
+};
@@ -0,0 +1,233 @@
+use rayon::prelude::*;
+
+use super::graph::Adjacency;
+use super::random::random_xy;
+
+// 最粗一层的目标节点数
+const COARSEST_NODE_COUNT: usize = 256;
+
+// 单次粗化后节点数下降不足该比例时停止
+const MIN_REDUCTION_RATIO: f32 = 0.9;
+
+const MAX_LEVEL_COUNT: usize = 32;
+
+pub const DEFAULT_ITERATIONS_PER_LEVEL: u32 = 200;
+
+// 层级中的一层图
+// levels[0] 为原图，不重复保存其边表；level 越大越粗
+pub struct CoarseLevel {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub source_target_list: Vec<u32>,
+    // 本层节点 → 下一层（更粗）节点，最粗一层为空
+    pub parent: Vec<u32>,
+}
+
+// Multilevel 布局的进度
+pub struct MultilevelLayout {
+    pub levels: Vec<CoarseLevel>,
+    pub current_level: usize,
+    pub iteration: u32,
+    pub iterations_per_level: u32,
+}
+
+impl MultilevelLayout {
+
+    // 由原图构建粗化层级，当前层为最粗一层
+    pub fn new(node_count: usize, source_target_list: &[u32]) -> Self {
+
+        let mut levels = vec![CoarseLevel {
+            node_count,
+            edge_count: source_target_list.len() / 2,
+            source_target_list: vec![],
+            parent: vec![],
+        }];
+
+        while levels.len() < MAX_LEVEL_COUNT {
+            let finer = levels.last().unwrap();
+            if finer.node_count <= COARSEST_NODE_COUNT { break; }
+
+            let finer_list = if levels.len() == 1 { source_target_list } else { &finer.source_target_list };
+            let (parent, coarse_node_count) = collapse_edges(finer.node_count, finer_list);
+
+            if coarse_node_count as f32 > finer.node_count as f32 * MIN_REDUCTION_RATIO { break; }
+
+            // 没有边的粗化图无法创建 Edge Buffer
+            let coarse_list = coarse_edges(finer_list, &parent);
+            if coarse_list.is_empty() { break; }
+
+            levels.last_mut().unwrap().parent = parent;
+            levels.push(CoarseLevel {
+                node_count: coarse_node_count,
+                edge_count: coarse_list.len() / 2,
+                source_target_list: coarse_list,
+                parent: vec![],
+            });
+        }
+
+        let current_level = levels.len() - 1;
+
+        Self {
+            levels,
+            current_level,
+            iteration: 0,
+            iterations_per_level: DEFAULT_ITERATIONS_PER_LEVEL,
+        }
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    // 把第 level 层的坐标延拓到第 level - 1 层
+    // 细节点继承父节点坐标，并加上与平均边长成比例的扰动，使合并的节点彼此分开
+    pub fn prolong(&self, level: usize, positions: &[f32]) -> Vec<f32> {
+
+        let coarse = &self.levels[level];
+        let fine = &self.levels[level - 1];
+
+        let edge_length = average_edge_length(&coarse.source_target_list, positions);
+        let radius = if edge_length > 0.0 { edge_length * 0.1 } else { 0.01 };
+
+        (0..fine.node_count * 3).into_par_iter().map(|i| {
+            let (index, axis) = (i / 3, i % 3);
+            let parent = fine.parent[index] as usize;
+            let jitter = random_xy(index as u32, (level * 3 + axis) as u32) * 2.0 - 1.0;
+            positions[parent * 3 + axis] + jitter * radius
+        }).collect()
+    }
+}
+
+// 边折叠粗化，按度数升序访问节点，与度数最小的未匹配邻居合并
+// 所有邻居都已匹配的节点并入度数最小的邻居所在的组，孤立节点两两合并
+fn collapse_edges(node_count: usize, source_target_list: &[u32]) -> (Vec<u32>, usize) {
+
+    let adjacency = Adjacency::from_edges(node_count, source_target_list);
+
+    let mut order: Vec<u32> = (0..node_count as u32).collect();
+    order.par_sort_by_key(|&index| (adjacency.degree(index as usize), index));
+
+    let mut parent = vec![u32::MAX; node_count];
+    let mut coarse_node_count = 0u32;
+    let mut isolated: Option<usize> = None;
+
+    for &index in &order {
+        let index = index as usize;
+        if parent[index] != u32::MAX { continue; }
+
+        let neighbors = adjacency.neighbors(index);
+
+        if neighbors.is_empty() {
+            match isolated.take() {
+                Some(other) => parent[index] = parent[other],
+                None => {
+                    parent[index] = coarse_node_count;
+                    coarse_node_count += 1;
+                    isolated = Some(index);
+                }
+            }
+            continue;
+        }
+
+        let lightest = |unmatched: bool| neighbors.iter()
+            .map(|&n| n as usize)
+            .filter(|&n| (parent[n] == u32::MAX) == unmatched)
+            .min_by_key(|&n| (adjacency.degree(n), n));
+
+        if let Some(neighbor) = lightest(true) {
+            parent[index] = coarse_node_count;
+            parent[neighbor] = coarse_node_count;
+            coarse_node_count += 1;
+        } else if let Some(neighbor) = lightest(false) {
+            parent[index] = parent[neighbor];
+        }
+    }
+
+    (parent, coarse_node_count as usize)
+}
+
+// 将细层边映射到粗层，去掉自环与重边
+fn coarse_edges(source_target_list: &[u32], parent: &[u32]) -> Vec<u32> {
+
+    let mut edges: Vec<(u32, u32)> = source_target_list.par_chunks_exact(2)
+        .map(|edge| (parent[edge[0] as usize], parent[edge[1] as usize]))
+        .filter(|(source, target)| source != target)
+        .map(|(source, target)| (source.min(target), source.max(target)))
+        .collect();
+
+    edges.par_sort_unstable();
+    edges.dedup();
+
+    edges.into_iter().flat_map(|(source, target)| [source, target]).collect()
+}
+
+fn average_edge_length(source_target_list: &[u32], positions: &[f32]) -> f32 {
+
+    let edge_count = source_target_list.len() / 2;
+    if edge_count == 0 { return 0.0; }
+
+    let total: f64 = source_target_list.par_chunks_exact(2).map(|edge| {
+        let (s, t) = (edge[0] as usize * 3, edge[1] as usize * 3);
+        let dx = positions[s] - positions[t];
+        let dy = positions[s + 1] - positions[t + 1];
+        let dz = positions[s + 2] - positions[t + 2];
+        ((dx * dx + dy * dy + dz * dz) as f64).sqrt()
+    }).sum();
+
+    (total / edge_count as f64) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_edges(width: u32, height: u32) -> Vec<u32> {
+        let mut edges = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                if x + 1 < width { edges.extend([index, index + 1]); }
+                if y + 1 < height { edges.extend([index, index + width]); }
+            }
+        }
+        edges
+    }
+
+    #[test]
+    fn coarsens_grid() {
+        let multilevel = MultilevelLayout::new(64 * 64, &grid_edges(64, 64));
+        assert!(multilevel.level_count() > 1);
+        assert_eq!(multilevel.current_level, multilevel.level_count() - 1);
+
+        for pair in multilevel.levels.windows(2) {
+            let (fine, coarse) = (&pair[0], &pair[1]);
+            assert!(coarse.node_count < fine.node_count);
+            assert_eq!(fine.parent.len(), fine.node_count);
+            assert!(fine.parent.iter().all(|&parent| (parent as usize) < coarse.node_count));
+            assert!(coarse.source_target_list.iter().all(|&node| (node as usize) < coarse.node_count));
+        }
+        assert!(multilevel.levels.last().unwrap().parent.is_empty());
+    }
+
+    #[test]
+    fn prolongs_to_finer_level() {
+        let multilevel = MultilevelLayout::new(64 * 64, &grid_edges(64, 64));
+        let mut positions: Vec<f32> = (0..multilevel.levels[multilevel.current_level].node_count * 3)
+            .map(|index| random_xy(index as u32, 0) * 2.0 - 1.0)
+            .collect();
+
+        for level in (1..multilevel.level_count()).rev() {
+            positions = multilevel.prolong(level, &positions);
+            assert_eq!(positions.len(), multilevel.levels[level - 1].node_count * 3);
+            assert!(positions.iter().all(|value| value.is_finite()));
+        }
+    }
+
+    #[test]
+    fn keeps_single_level_without_edges() {
+        assert_eq!(MultilevelLayout::new(0, &[]).level_count(), 1);
+        assert_eq!(MultilevelLayout::new(1000, &[]).level_count(), 1);
+        // 不超过最粗一层的节点数时不粗化
+        assert_eq!(MultilevelLayout::new(100, &grid_edges(10, 10)).level_count(), 1);
+    }
+}
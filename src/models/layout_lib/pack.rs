@@ -0,0 +1,176 @@
+use std::f32::consts::PI;
+
+use rayon::prelude::*;
+
+use super::graph::connected_components;
+
+// 分量之间的间隙，相对于最大分量的半径
+const GAP_RATIO: f32 = 0.05;
+
+// 单个节点的分量半径为 0，按间隙的一半计算
+const MIN_RADIUS_RATIO: f32 = 0.5;
+
+// 连通分量包围球
+struct Component {
+    center: [f32; 3],
+    radius: f32,
+    node_count: usize,
+}
+
+// 连通分量排布
+// 最大的分量放在原点，其余按半径从大到小沿 xy 平面上的同心圆环依次排列，分量内部的相对坐标保持不变
+// 返回新的坐标与连通分量数
+pub fn pack_components(node_count: usize, source_target_list: &[u32], positions: &[f32]) -> (Vec<f32>, usize) {
+
+    let (labels, component_count) = connected_components(node_count, source_target_list);
+    if component_count <= 1 { return (positions.to_vec(), component_count); }
+
+    let components = bounding_spheres(&labels, component_count, positions);
+
+    let mut order: Vec<usize> = (0..component_count).collect();
+    order.sort_by(|&a, &b| {
+        components[b].radius.total_cmp(&components[a].radius)
+            .then(components[b].node_count.cmp(&components[a].node_count))
+            .then(a.cmp(&b))
+    });
+
+    let gap = (components[order[0]].radius * GAP_RATIO).max(f32::EPSILON);
+    let radius = |c: usize| components[c].radius.max(gap * MIN_RADIUS_RATIO);
+
+    let mut placement = vec![[0.0f32; 3]; component_count];
+
+    // 当前圆环的半径、环上最大分量的半径、已占用的角度，以及环上第一个与上一个分量的半径
+    let mut ring_radius = 0.0f32;
+    let mut ring_width = radius(order[0]);
+    let mut angle = 0.0f32;
+    let mut first_radius = 0.0f32;
+    let mut last_radius = 0.0f32;
+
+    // 相邻两个分量中心在圆环上的最小夹角，保证弦长不小于两半径之和加间隙
+    let step = |ring_radius: f32, a: f32, b: f32| 2.0 * ((a + b + gap) / (2.0 * ring_radius)).min(1.0).asin();
+
+    for &c in &order[1..] {
+        let r = radius(c);
+
+        let is_new_ring = ring_radius == 0.0 || {
+            let next = angle + step(ring_radius, last_radius, r);
+            next + step(ring_radius, r, first_radius) > 2.0 * PI
+        };
+
+        if is_new_ring {
+            // 新圆环紧贴上一环的外侧，环宽由环上第一个（最大的）分量决定
+            ring_radius += ring_width + gap + r;
+            ring_width = r;
+            angle = 0.0;
+            first_radius = r;
+        } else {
+            angle += step(ring_radius, last_radius, r);
+        }
+        last_radius = r;
+
+        placement[c] = [ring_radius * angle.cos(), ring_radius * angle.sin(), 0.0];
+    }
+
+    // 整体平移，使最大的分量保持原位
+    let origin = components[order[0]].center;
+
+    let packed = (0..node_count * 3).into_par_iter().map(|i| {
+        let (index, axis) = (i / 3, i % 3);
+        let c = labels[index] as usize;
+        positions[i] - components[c].center[axis] + placement[c][axis] + origin[axis]
+    }).collect();
+
+    (packed, component_count)
+}
+
+// 以质心为球心，到最远节点的距离为半径
+fn bounding_spheres(labels: &[u32], component_count: usize, positions: &[f32]) -> Vec<Component> {
+
+    let mut sums = vec![[0.0f64; 3]; component_count];
+    let mut counts = vec![0usize; component_count];
+    for (index, &label) in labels.iter().enumerate() {
+        let c = label as usize;
+        for axis in 0..3 {
+            sums[c][axis] += positions[index * 3 + axis] as f64;
+        }
+        counts[c] += 1;
+    }
+
+    let mut components: Vec<Component> = sums.iter().zip(&counts).map(|(sum, &count)| Component {
+        center: [
+            (sum[0] / count as f64) as f32,
+            (sum[1] / count as f64) as f32,
+            (sum[2] / count as f64) as f32,
+        ],
+        radius: 0.0,
+        node_count: count,
+    }).collect();
+
+    for (index, &label) in labels.iter().enumerate() {
+        let component = &mut components[label as usize];
+        let dx = positions[index * 3] - component.center[0];
+        let dy = positions[index * 3 + 1] - component.center[1];
+        let dz = positions[index * 3 + 2] - component.center[2];
+        component.radius = component.radius.max((dx * dx + dy * dy + dz * dz).sqrt());
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::layout_lib::random::random_xy;
+
+    // 大小不同的路径分量与若干孤立节点，坐标都落在原点附近而相互重叠
+    fn overlapping_components() -> (usize, Vec<u32>, Vec<f32>) {
+        let mut edges = vec![];
+        let mut start = 0u32;
+        for length in [12u32, 7, 7, 4, 3, 2, 2, 2] {
+            edges.extend((start + 1..start + length).flat_map(|index| [index - 1, index]));
+            start += length;
+        }
+        let node_count = start as usize + 5;
+        let positions = (0..node_count * 3).map(|index| random_xy(index as u32, 9) * 2.0 - 1.0).collect();
+        (node_count, edges, positions)
+    }
+
+    fn distance(positions: &[f32], a: usize, b: usize) -> f32 {
+        (0..3).map(|axis| (positions[a * 3 + axis] - positions[b * 3 + axis]).powi(2)).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn separates_components_rigidly() {
+        let (node_count, edges, positions) = overlapping_components();
+        let (packed, component_count) = pack_components(node_count, &edges, &positions);
+        let (labels, expected_count) = connected_components(node_count, &edges);
+        assert_eq!(component_count, expected_count);
+        assert_eq!(component_count, 13);
+        assert_eq!(packed.len(), positions.len());
+
+        // 分量内部的距离不变
+        for a in 0..node_count {
+            for b in a + 1..node_count {
+                if labels[a] == labels[b] {
+                    assert!((distance(&packed, a, b) - distance(&positions, a, b)).abs() < 1e-4, "nodes {} {}", a, b);
+                }
+            }
+        }
+
+        // 不同分量的包围球互不重叠
+        let spheres = bounding_spheres(&labels, component_count, &packed);
+        for a in 0..component_count {
+            for b in a + 1..component_count {
+                let center_distance = (0..3).map(|axis| (spheres[a].center[axis] - spheres[b].center[axis]).powi(2)).sum::<f32>().sqrt();
+                assert!(center_distance >= (spheres[a].radius + spheres[b].radius) * (1.0 - 1e-4), "components {} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_single_component_and_empty_graph() {
+        assert_eq!(pack_components(0, &[], &[]), (vec![], 0));
+        let positions = vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(pack_components(2, &[0, 1], &positions), (positions, 1));
+    }
+}
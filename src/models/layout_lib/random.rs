@@ -0,0 +1,21 @@
+// 与 CS_graph_solver.wgsl 中的 hash / random_xy 保持一致
+// CPU 端的布局方法使用同一套伪随机数，结果可复现
+
+pub fn hash(s: u32) -> u32 {
+    let mut t = s;
+    t ^= 2747636419;
+    t = t.wrapping_mul(2654435769);
+    t ^= t >> 16;
+    t = t.wrapping_mul(2654435769);
+    t ^= t >> 16;
+    t = t.wrapping_mul(2654435769);
+    t
+}
+
+pub fn random(seed: u32) -> f32 {
+    hash(seed) as f32 / 4294967295.0 // 2^32-1
+}
+
+pub fn random_xy(seed_x: u32, seed_y: u32) -> f32 {
+    hash(hash(seed_x).wrapping_add(seed_y)) as f32 / 4294967295.0 // 2^32-1
+}
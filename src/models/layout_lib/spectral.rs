@@ -0,0 +1,351 @@
+use rayon::prelude::*;
+
+use super::graph::{Adjacency, dot};
+use super::random::random_xy;
+
+// 取归一化拉普拉斯矩阵第 2–4 小的特征向量，分别作为 x / y / z
+const SPECTRAL_DIMENSION: usize = 3;
+
+// 节点数不超过该值时直接对稠密矩阵做 Jacobi 分解
+const DENSE_NODE_COUNT: usize = 64;
+
+const MAX_ITERATIONS: usize = 300;
+const RESIDUAL_TOLERANCE: f64 = 1e-3;
+
+// 坐标归一化到 [-1, 1] 后叠加的微小扰动，避免完全重合的点（如星形图的叶子）
+const JITTER: f32 = 1e-3;
+
+// Spectral 布局
+// 返回长度为 node_count * 3 的 x / y / z 坐标，范围与 randomize 相同
+// 图不连通时，各连通分量会分别收缩到一点，可在之后运行 Force Atlas 2 展开
+pub fn spectral_layout(node_count: usize, source_target_list: &[u32]) -> Vec<f32> {
+
+    if node_count == 0 { return vec![]; }
+
+    let adjacency = Adjacency::from_edges(node_count, source_target_list);
+
+    let degree_sqrt: Vec<f64> = (0..node_count)
+        .into_par_iter()
+        .map(|index| (adjacency.degree(index) as f64).sqrt())
+        .collect();
+
+    let vectors = if node_count <= DENSE_NODE_COUNT {
+        dense_eigenvectors(&adjacency, &degree_sqrt, SPECTRAL_DIMENSION)
+    } else {
+        lobpcg(&adjacency, &degree_sqrt, SPECTRAL_DIMENSION)
+    };
+
+    // 由 D^1/2 的特征向量换算回随机游走拉普拉斯的特征向量，再归一化到 [-1, 1]
+    let mut axes: Vec<Vec<f32>> = vectors.iter().map(|vector| {
+        let mut axis: Vec<f64> = vector.par_iter().zip(degree_sqrt.par_iter())
+            .map(|(&x, &d)| if d > 0.0 { x / d } else { x })
+            .collect();
+
+        let mean = axis.iter().sum::<f64>() / node_count as f64;
+        let max = axis.iter().fold(0.0f64, |max, x| max.max((x - mean).abs()));
+        let scale = if max > 0.0 { 1.0 / max } else { 0.0 };
+        axis.par_iter_mut().for_each(|x| *x = (*x - mean) * scale);
+
+        axis.iter().map(|&x| x as f32).collect()
+    }).collect();
+
+    while axes.len() < SPECTRAL_DIMENSION {
+        axes.push(vec![0.0; node_count]);
+    }
+
+    (0..node_count * 3).into_par_iter().map(|i| {
+        let (index, axis) = (i / 3, i % 3);
+        axes[axis][index] + (random_xy(index as u32, axis as u32) - 0.5) * JITTER
+    }).collect()
+}
+
+// y = L x，L = I - D^-1/2 A D^-1/2，孤立节点对应的行为单位行
+fn apply_laplacian(adjacency: &Adjacency, degree_sqrt: &[f64], x: &[f64]) -> Vec<f64> {
+    (0..x.len()).into_par_iter().map(|index| {
+        let d = degree_sqrt[index];
+        if d == 0.0 { return x[index]; }
+        let sum: f64 = adjacency.neighbors(index).iter()
+            .map(|&j| x[j as usize] / degree_sqrt[j as usize])
+            .sum();
+        x[index] - sum / d
+    }).collect()
+}
+
+// D^1/2 · 1 为特征值 0 对应的平凡特征向量，需从求解空间中移除
+fn trivial_vector(degree_sqrt: &[f64]) -> Option<Vec<f64>> {
+    let norm = dot(degree_sqrt, degree_sqrt).sqrt();
+    if norm == 0.0 { return None; }
+    Some(degree_sqrt.par_iter().map(|d| d / norm).collect())
+}
+
+// 对 basis 做两遍修正 Gram-Schmidt 正交化并单位化，向量退化时返回 false
+fn orthonormalize(vector: &mut [f64], basis: &[&[f64]]) -> bool {
+    let initial_norm = dot(vector, vector).sqrt();
+    if initial_norm == 0.0 { return false; }
+
+    for _ in 0..2 {
+        for b in basis {
+            let projection = dot(vector, b);
+            vector.par_iter_mut().zip(b.par_iter()).for_each(|(v, b)| *v -= projection * b);
+        }
+    }
+
+    let norm = dot(vector, vector).sqrt();
+    if norm <= initial_norm * 1e-10 { return false; }
+    vector.par_iter_mut().for_each(|v| *v /= norm);
+    true
+}
+
+// Σ coefficients[i] · vectors[i]
+fn combine(vectors: &[Vec<f64>], coefficients: &[f64]) -> Vec<f64> {
+    let length = vectors[0].len();
+    (0..length).into_par_iter().map(|index| {
+        vectors.iter().zip(coefficients).map(|(v, c)| v[index] * c).sum()
+    }).collect()
+}
+
+// LOBPCG（无预条件）求 L 在平凡特征向量正交补上的最小 count 个特征向量
+fn lobpcg(adjacency: &Adjacency, degree_sqrt: &[f64], count: usize) -> Vec<Vec<f64>> {
+
+    let node_count = degree_sqrt.len();
+    let trivial = trivial_vector(degree_sqrt);
+
+    // 初始块使用固定的伪随机数，保证结果可复现
+    let mut x: Vec<Vec<f64>> = Vec::with_capacity(count);
+    for column in 0..count {
+        let mut vector: Vec<f64> = (0..node_count).into_par_iter()
+            .map(|index| random_xy(index as u32, 7 + column as u32) as f64 - 0.5)
+            .collect();
+        let mut basis: Vec<&[f64]> = trivial.iter().map(|v| v.as_slice()).collect();
+        basis.extend(x.iter().map(|v| v.as_slice()));
+        if orthonormalize(&mut vector, &basis) {
+            x.push(vector);
+        }
+    }
+
+    let mut p: Vec<Vec<f64>> = vec![];
+
+    for _ in 0..MAX_ITERATIONS {
+
+        let lx: Vec<Vec<f64>> = x.iter().map(|v| apply_laplacian(adjacency, degree_sqrt, v)).collect();
+
+        // 残差 R = LX - XΛ
+        let mut residual_max = 0.0f64;
+        let residuals: Vec<Vec<f64>> = x.iter().zip(lx.iter()).map(|(v, lv)| {
+            let lambda = dot(v, lv);
+            let r: Vec<f64> = lv.par_iter().zip(v.par_iter()).map(|(lv, v)| lv - lambda * v).collect();
+            residual_max = residual_max.max(dot(&r, &r).sqrt());
+            r
+        }).collect();
+
+        if residual_max < RESIDUAL_TOLERANCE { break; }
+
+        // 搜索子空间 S = [X, W, P]，W 取残差
+        let mut basis: Vec<Vec<f64>> = x.clone();
+        for mut vector in residuals.into_iter().chain(p.into_iter()) {
+            let mut against: Vec<&[f64]> = trivial.iter().map(|v| v.as_slice()).collect();
+            against.extend(basis.iter().map(|v| v.as_slice()));
+            if orthonormalize(&mut vector, &against) {
+                basis.push(vector);
+            }
+        }
+
+        let mut l_basis = lx;
+        for vector in &basis[x.len()..] {
+            l_basis.push(apply_laplacian(adjacency, degree_sqrt, vector));
+        }
+
+        // Rayleigh-Ritz：G = Sᵀ L S
+        let size = basis.len();
+        let mut gram = vec![0.0f64; size * size];
+        for a in 0..size {
+            for b in a..size {
+                let value = dot(&basis[a], &l_basis[b]);
+                gram[a * size + b] = value;
+                gram[b * size + a] = value;
+            }
+        }
+
+        let (_, ritz_vectors) = symmetric_eigen(&gram, size);
+        let current = x.len();
+
+        let mut next_x = Vec::with_capacity(current);
+        let mut next_p = Vec::with_capacity(current);
+        for column in 0..current {
+            let coefficients: Vec<f64> = (0..size).map(|row| ritz_vectors[row * size + column]).collect();
+            next_x.push(combine(&basis, &coefficients));
+            if size > current {
+                next_p.push(combine(&basis[current..], &coefficients[current..]));
+            }
+        }
+
+        x = next_x;
+        p = next_p;
+    }
+
+    x
+}
+
+// 小图直接构造稠密矩阵 L + 2·v0·v0ᵀ，把平凡特征向量移到谱的末端
+fn dense_eigenvectors(adjacency: &Adjacency, degree_sqrt: &[f64], count: usize) -> Vec<Vec<f64>> {
+
+    let node_count = degree_sqrt.len();
+    let trivial = trivial_vector(degree_sqrt);
+
+    let mut matrix = vec![0.0f64; node_count * node_count];
+    for column in 0..node_count {
+        let mut unit = vec![0.0f64; node_count];
+        unit[column] = 1.0;
+        let l_unit = apply_laplacian(adjacency, degree_sqrt, &unit);
+        for row in 0..node_count {
+            matrix[row * node_count + column] = l_unit[row];
+        }
+    }
+
+    if let Some(trivial) = &trivial {
+        for row in 0..node_count {
+            for column in 0..node_count {
+                matrix[row * node_count + column] += 2.0 * trivial[row] * trivial[column];
+            }
+        }
+    }
+
+    let (_, vectors) = symmetric_eigen(&matrix, node_count);
+    let available = if trivial.is_some() { node_count - 1 } else { node_count };
+
+    (0..count.min(available)).map(|column| {
+        (0..node_count).map(|row| vectors[row * node_count + column]).collect()
+    }).collect()
+}
+
+// 循环 Jacobi 方法求实对称矩阵（行主序）的全部特征值与特征向量，仅用于小矩阵
+// 特征值升序排列，第 k 个特征向量为返回矩阵的第 k 列
+pub fn symmetric_eigen(matrix: &[f64], size: usize) -> (Vec<f64>, Vec<f64>) {
+
+    let mut a = matrix.to_vec();
+    let mut v = vec![0.0f64; size * size];
+    for i in 0..size { v[i * size + i] = 1.0; }
+
+    for _ in 0..100 {
+        let mut off_diagonal = 0.0;
+        for p in 0..size {
+            for q in (p + 1)..size {
+                off_diagonal += a[p * size + q] * a[p * size + q];
+            }
+        }
+        if off_diagonal < 1e-24 { break; }
+
+        for p in 0..size {
+            for q in (p + 1)..size {
+                let apq = a[p * size + q];
+                if apq.abs() < 1e-300 { continue; }
+
+                let theta = (a[q * size + q] - a[p * size + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for k in 0..size {
+                    let (akp, akq) = (a[k * size + p], a[k * size + q]);
+                    a[k * size + p] = c * akp - s * akq;
+                    a[k * size + q] = s * akp + c * akq;
+                }
+                for k in 0..size {
+                    let (apk, aqk) = (a[p * size + k], a[q * size + k]);
+                    a[p * size + k] = c * apk - s * aqk;
+                    a[q * size + k] = s * apk + c * aqk;
+                }
+                for k in 0..size {
+                    let (vkp, vkq) = (v[k * size + p], v[k * size + q]);
+                    v[k * size + p] = c * vkp - s * vkq;
+                    v[k * size + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..size).collect();
+    order.sort_by(|&i, &j| a[i * size + i].partial_cmp(&a[j * size + j]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let values = order.iter().map(|&i| a[i * size + i]).collect();
+    let mut vectors = vec![0.0f64; size * size];
+    for (column, &source) in order.iter().enumerate() {
+        for row in 0..size {
+            vectors[row * size + column] = v[row * size + source];
+        }
+    }
+
+    (values, vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_edges(node_count: usize) -> Vec<u32> {
+        (1..node_count as u32).flat_map(|index| [index - 1, index]).collect()
+    }
+
+    // 路径图的 Fiedler 向量单调，允许 JITTER 以内的扰动
+    fn assert_monotone_path(node_count: usize) {
+        let positions = spectral_layout(node_count, &path_edges(node_count));
+        let x: Vec<f32> = positions.chunks_exact(3).map(|position| position[0]).collect();
+        let sign = (x[node_count - 1] - x[0]).signum();
+        assert!(sign != 0.0);
+        for index in 1..node_count {
+            assert!((x[index] - x[index - 1]) * sign > -JITTER, "node {} of {}: {:?}", index, node_count, &x[index - 1..=index]);
+        }
+    }
+
+    #[test]
+    fn orders_path_graph_monotonically() {
+        // 稠密 Jacobi 与 LOBPCG 两条路径
+        assert_monotone_path(20);
+        assert_monotone_path(150);
+    }
+
+    #[test]
+    fn computes_orthonormal_eigenvectors() {
+        let size = 4;
+        let matrix = [
+            4.0, 1.0, 0.5, 0.0,
+            1.0, 3.0, 0.0, 0.2,
+            0.5, 0.0, 2.0, 1.0,
+            0.0, 0.2, 1.0, 1.0,
+        ];
+        let (values, vectors) = symmetric_eigen(&matrix, size);
+        let column = |k: usize| (0..size).map(|row| vectors[row * size + k]).collect::<Vec<f64>>();
+
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+        for (k, &value) in values.iter().enumerate() {
+            for l in 0..size {
+                let expected = if k == l { 1.0 } else { 0.0 };
+                assert!((dot(&column(k), &column(l)) - expected).abs() < 1e-9, "columns {} {}", k, l);
+            }
+            // A·v = λ·v
+            let v = column(k);
+            for row in 0..size {
+                let av: f64 = (0..size).map(|j| matrix[row * size + j] * v[j]).sum();
+                assert!((av - value * v[row]).abs() < 1e-9, "eigenpair {} row {}", k, row);
+            }
+        }
+    }
+
+    #[test]
+    fn handles_degenerate_graphs() {
+        assert!(spectral_layout(0, &[]).is_empty());
+        for (node_count, source_target_list) in [
+            (1, vec![]),
+            (2, vec![0, 1]),
+            (2, vec![]),
+            (10, vec![]),
+            (3, vec![0, 0, 1, 1]),
+            (100, vec![]),
+            (100, vec![5, 5]),
+        ] {
+            let positions = spectral_layout(node_count, &source_target_list);
+            assert_eq!(positions.len(), node_count * 3);
+            assert!(positions.iter().all(|value| value.is_finite()), "{} nodes, edges {:?}", node_count, source_target_list);
+        }
+    }
+}
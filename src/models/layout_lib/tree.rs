@@ -0,0 +1,394 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+use super::graph::Adjacency;
+
+// 相邻兄弟子树之间的最小间距
+const SIBLING_DISTANCE: f32 = 1.0;
+
+const NONE: u32 = u32::MAX;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum TreeStyle {
+    // 以根为球心，每一层为一个同心球壳
+    Radial,
+    // 根在上，逐层向下
+    TopDown,
+}
+
+// 树布局
+// 根为用户指定的节点（所在连通分量），其余连通分量取入度为 0 的节点，全部成环时取度数最大的节点
+// 由根出发按无向边 BFS 得到生成森林与层次，再用 Reingold–Tilford（Buchheim–Walker 线性时间版本）排布
+// 返回长度为 node_count * 3 的 x / y / z 坐标，范围与 randomize 相同
+pub fn tree_layout(node_count: usize, source_target_list: &[u32], root: Option<u32>, style: TreeStyle) -> Vec<f32> {
+
+    if node_count == 0 { return vec![]; }
+
+    let adjacency = Adjacency::from_edges(node_count, source_target_list);
+    let roots = find_roots(node_count, source_target_list, &adjacency, root);
+    let forest = SpanningForest::new(&adjacency, &roots);
+
+    let x = forest.buchheim_walker();
+
+    // 多棵树时，虚拟根所在的第 0 层不输出
+    let depth_offset = forest.has_virtual_root as u32;
+    let max_depth = forest.depth[..node_count].iter().max().copied().unwrap_or(0).saturating_sub(depth_offset);
+
+    let (min_x, max_x) = x[..node_count].iter().fold((f32::MAX, f32::MIN), |(min, max), &x| (min.min(x), max.max(x)));
+    let width = (max_x - min_x).max(SIBLING_DISTANCE);
+
+    let mut positions = vec![0.0; node_count * 3];
+
+    match style {
+        TreeStyle::TopDown => {
+            for index in 0..node_count {
+                let depth = forest.depth[index] - depth_offset;
+                positions[index * 3] = (x[index] - min_x) / width * 2.0 - 1.0;
+                positions[index * 3 + 1] = if max_depth > 0 { 1.0 - 2.0 * depth as f32 / max_depth as f32 } else { 0.0 };
+            }
+        }
+        TreeStyle::Radial => {
+            // 按顺序沿球面螺旋线排布，子树在球壳上占据相邻的一段，与父节点方向一致
+            // 螺旋圈数约为 sqrt(n·π) / 2 时，相邻两圈的间距与相邻节点的间距相当
+            let slot_count = width / SIBLING_DISTANCE + 1.0;
+            let turns = (slot_count * PI).sqrt() * 0.5;
+            let shell_offset = forest.has_virtual_root as u32;
+            let shell_count = max_depth + shell_offset;
+
+            for index in 0..node_count {
+                let t = (x[index] - min_x) / width;
+                let z = 1.0 - 2.0 * t;
+                let r = (1.0 - z * z).max(0.0).sqrt();
+                let azimuth = 2.0 * PI * turns * t;
+                let depth = forest.depth[index] - depth_offset + shell_offset;
+                let radius = if shell_count > 0 { depth as f32 / shell_count as f32 } else { 0.0 };
+                positions[index * 3] = r * azimuth.cos() * radius;
+                positions[index * 3 + 1] = z * radius;
+                positions[index * 3 + 2] = r * azimuth.sin() * radius;
+            }
+        }
+    }
+
+    positions
+}
+
+fn find_roots(node_count: usize, source_target_list: &[u32], adjacency: &Adjacency, root: Option<u32>) -> Vec<u32> {
+
+    let mut in_degree = vec![0u32; node_count];
+    for edge in source_target_list.chunks_exact(2) {
+        let (source, target) = (edge[0] as usize, edge[1] as usize);
+        if source == target || source >= node_count || target >= node_count { continue; }
+        in_degree[target] += 1;
+    }
+
+    // 连通分量编号
+    let mut component = vec![NONE; node_count];
+    let mut component_count = 0u32;
+    let mut queue = VecDeque::new();
+    for start in 0..node_count {
+        if component[start] != NONE { continue; }
+        component[start] = component_count;
+        queue.push_back(start as u32);
+        while let Some(index) = queue.pop_front() {
+            for &neighbor in adjacency.neighbors(index as usize) {
+                if component[neighbor as usize] == NONE {
+                    component[neighbor as usize] = component_count;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        component_count += 1;
+    }
+
+    let root = root.filter(|&root| (root as usize) < node_count);
+    let root_component = root.map_or(NONE, |root| component[root as usize]);
+
+    let mut roots = vec![];
+    let mut has_root = vec![false; component_count as usize];
+    let mut busiest = vec![NONE; component_count as usize];
+
+    if let Some(root) = root {
+        roots.push(root);
+        has_root[root_component as usize] = true;
+    }
+
+    for index in 0..node_count {
+        let c = component[index] as usize;
+        if c as u32 == root_component { continue; }
+        if in_degree[index] == 0 {
+            roots.push(index as u32);
+            has_root[c] = true;
+        }
+        if busiest[c] == NONE || adjacency.degree(index) > adjacency.degree(busiest[c] as usize) {
+            busiest[c] = index as u32;
+        }
+    }
+
+    for c in 0..component_count as usize {
+        if !has_root[c] { roots.push(busiest[c]); }
+    }
+
+    roots
+}
+
+// BFS 生成森林，多个根时增加一个虚拟根（编号为 node_count）把森林连成一棵树
+struct SpanningForest {
+    root: u32,
+    has_virtual_root: bool,
+    parent: Vec<u32>,
+    depth: Vec<u32>,
+    // BFS 顺序，父节点先于子节点
+    order: Vec<u32>,
+    // 子节点按 BFS 发现顺序存放，child_offsets 为 CSR 偏移
+    child_offsets: Vec<usize>,
+    children: Vec<u32>,
+    // 在兄弟中的序号，从 0 开始
+    number: Vec<u32>,
+}
+
+impl SpanningForest {
+
+    fn new(adjacency: &Adjacency, roots: &[u32]) -> Self {
+
+        let node_count = adjacency.node_count();
+        let total = node_count + 1;
+        let has_virtual_root = roots.len() > 1;
+        let root = if has_virtual_root { node_count as u32 } else { roots[0] };
+
+        let mut parent = vec![NONE; total];
+        let mut depth = vec![0u32; total];
+        let mut order = Vec::with_capacity(total);
+        let mut is_visited = vec![false; total];
+
+        let mut queue = VecDeque::new();
+        if has_virtual_root {
+            is_visited[root as usize] = true;
+            order.push(root);
+        }
+        for &r in roots {
+            if is_visited[r as usize] { continue; }
+            is_visited[r as usize] = true;
+            if has_virtual_root {
+                parent[r as usize] = root;
+                depth[r as usize] = 1;
+            }
+            queue.push_back(r);
+        }
+
+        // 所有根同时出发，节点的层次为到最近的根的距离
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+            for &neighbor in adjacency.neighbors(index as usize) {
+                if is_visited[neighbor as usize] { continue; }
+                is_visited[neighbor as usize] = true;
+                parent[neighbor as usize] = index;
+                depth[neighbor as usize] = depth[index as usize] + 1;
+                queue.push_back(neighbor);
+            }
+        }
+
+        // 按 BFS 顺序填入，保持子节点的发现顺序
+        let mut child_offsets = vec![0usize; total + 1];
+        for &index in &order {
+            let p = parent[index as usize];
+            if p != NONE { child_offsets[p as usize + 1] += 1; }
+        }
+        for i in 0..total {
+            child_offsets[i + 1] += child_offsets[i];
+        }
+
+        let mut cursor = child_offsets.clone();
+        let mut children = vec![0u32; child_offsets[total]];
+        let mut number = vec![0u32; total];
+        for &index in &order {
+            let p = parent[index as usize];
+            if p == NONE { continue; }
+            number[index as usize] = (cursor[p as usize] - child_offsets[p as usize]) as u32;
+            children[cursor[p as usize]] = index;
+            cursor[p as usize] += 1;
+        }
+
+        Self { root, has_virtual_root, parent, depth, order, child_offsets, children, number }
+    }
+
+    fn children(&self, v: usize) -> &[u32] {
+        &self.children[self.child_offsets[v]..self.child_offsets[v + 1]]
+    }
+
+    fn next_left(&self, v: usize, thread: &[u32]) -> u32 {
+        self.children(v).first().copied().unwrap_or(thread[v])
+    }
+
+    fn next_right(&self, v: usize, thread: &[u32]) -> u32 {
+        self.children(v).last().copied().unwrap_or(thread[v])
+    }
+
+    // Buchheim–Walker 算法，返回每个节点的横坐标
+    // firstWalk 按 BFS 逆序（子树先于父节点）、secondWalk 按 BFS 顺序迭代执行，避免深树递归栈溢出
+    fn buchheim_walker(&self) -> Vec<f32> {
+
+        let total = self.parent.len();
+
+        let mut prelim = vec![0.0f32; total];
+        let mut modifier = vec![0.0f32; total];
+        let mut change = vec![0.0f32; total];
+        let mut shift = vec![0.0f32; total];
+        let mut midpoint = vec![0.0f32; total];
+        let mut thread = vec![NONE; total];
+        let mut ancestor: Vec<u32> = (0..total as u32).collect();
+
+        for &v in self.order.iter().rev() {
+            let v = v as usize;
+            let children = self.children(v);
+            if children.is_empty() { continue; }
+
+            // 子节点相对左兄弟定位，再与左侧子树的轮廓比较并右移
+            let mut default_ancestor = children[0];
+            for (i, &w) in children.iter().enumerate() {
+                let w = w as usize;
+                if i == 0 {
+                    prelim[w] = midpoint[w];
+                    continue;
+                }
+
+                prelim[w] = prelim[children[i - 1] as usize] + SIBLING_DISTANCE;
+                modifier[w] = prelim[w] - midpoint[w];
+
+                // apportion
+                let (mut vi_p, mut vo_p) = (w as u32, w as u32);
+                let (mut vi_m, mut vo_m) = (children[i - 1], children[0]);
+                let (mut si_p, mut so_p) = (modifier[w], modifier[w]);
+                let (mut si_m, mut so_m) = (modifier[vi_m as usize], modifier[vo_m as usize]);
+
+                loop {
+                    let next_vi_m = self.next_right(vi_m as usize, &thread);
+                    let next_vi_p = self.next_left(vi_p as usize, &thread);
+                    if next_vi_m == NONE || next_vi_p == NONE { break; }
+
+                    vi_m = next_vi_m;
+                    vi_p = next_vi_p;
+                    vo_m = self.next_left(vo_m as usize, &thread);
+                    vo_p = self.next_right(vo_p as usize, &thread);
+                    ancestor[vo_p as usize] = w as u32;
+
+                    let distance = (prelim[vi_m as usize] + si_m) - (prelim[vi_p as usize] + si_p) + SIBLING_DISTANCE;
+                    if distance > 0.0 {
+                        let a = ancestor[vi_m as usize];
+                        let wl = if self.parent[a as usize] == v as u32 { a } else { default_ancestor } as usize;
+
+                        // moveSubtree
+                        let subtrees = (self.number[w] - self.number[wl]) as f32;
+                        change[w] -= distance / subtrees;
+                        shift[w] += distance;
+                        change[wl] += distance / subtrees;
+                        prelim[w] += distance;
+                        modifier[w] += distance;
+
+                        si_p += distance;
+                        so_p += distance;
+                    }
+
+                    si_m += modifier[vi_m as usize];
+                    si_p += modifier[vi_p as usize];
+                    so_m += modifier[vo_m as usize];
+                    so_p += modifier[vo_p as usize];
+                }
+
+                let next_vi_m = self.next_right(vi_m as usize, &thread);
+                if next_vi_m != NONE && self.next_right(vo_p as usize, &thread) == NONE {
+                    thread[vo_p as usize] = next_vi_m;
+                    modifier[vo_p as usize] += si_m - so_p;
+                }
+
+                let next_vi_p = self.next_left(vi_p as usize, &thread);
+                if next_vi_p != NONE && self.next_left(vo_m as usize, &thread) == NONE {
+                    thread[vo_m as usize] = next_vi_p;
+                    modifier[vo_m as usize] += si_p - so_m;
+                    default_ancestor = w as u32;
+                }
+            }
+
+            // executeShifts
+            let (mut total_shift, mut total_change) = (0.0, 0.0);
+            for &w in children.iter().rev() {
+                let w = w as usize;
+                prelim[w] += total_shift;
+                modifier[w] += total_shift;
+                total_change += change[w];
+                total_shift += shift[w] + total_change;
+            }
+
+            midpoint[v] = (prelim[children[0] as usize] + prelim[*children.last().unwrap() as usize]) * 0.5;
+        }
+
+        // secondWalk，offset 为祖先 modifier 之和
+        let mut x = vec![0.0f32; total];
+        let mut offset = vec![0.0f32; total];
+        prelim[self.root as usize] = midpoint[self.root as usize];
+        for &v in &self.order {
+            let v = v as usize;
+            x[v] = prelim[v] + offset[v];
+            for &w in self.children(v) {
+                offset[w as usize] = offset[v] + modifier[v];
+            }
+        }
+
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 堆序编号的完全二叉树，边由父节点指向子节点
+    fn binary_tree(depth: u32) -> (usize, Vec<u32>) {
+        let node_count = (1usize << (depth + 1)) - 1;
+        let edges = (1..node_count as u32).flat_map(|child| [(child - 1) / 2, child]).collect();
+        (node_count, edges)
+    }
+
+    #[test]
+    fn centers_parents_in_top_down_tree() {
+        let (node_count, edges) = binary_tree(4);
+        let positions = tree_layout(node_count, &edges, None, TreeStyle::TopDown);
+        let x = |index: usize| positions[index * 3];
+        let y = |index: usize| positions[index * 3 + 1];
+
+        assert_eq!(y(0), 1.0);
+        for level in 0..=4u32 {
+            let nodes: Vec<usize> = ((1usize << level) - 1..(1usize << (level + 1)) - 1).collect();
+            assert!(nodes.iter().all(|&index| y(index) == y(nodes[0])));
+            // 同层节点从左到右排列，互不重叠
+            for pair in nodes.windows(2) {
+                assert!(x(pair[1]) - x(pair[0]) > 1e-3, "level {} nodes {:?}", level, pair);
+            }
+        }
+        for parent in 0..node_count / 2 {
+            let center = (x(parent * 2 + 1) + x(parent * 2 + 2)) * 0.5;
+            assert!((x(parent) - center).abs() < 1e-5, "parent {}", parent);
+            assert!(y(parent) > y(parent * 2 + 1));
+        }
+    }
+
+    #[test]
+    fn lays_out_cycles_forests_and_explicit_roots() {
+        let cycle: Vec<u32> = (0..6).flat_map(|index| [index, (index + 1) % 6]).collect();
+        let forest = vec![0, 1, 1, 2, 3, 4, 5, 6, 5, 7];
+        for (node_count, edges, root) in [
+            (6, cycle.clone(), None),
+            (6, cycle, Some(3)),
+            (9, forest.clone(), None),
+            (9, forest, Some(7)),
+            (4, vec![], None),
+            (1, vec![], Some(0)),
+        ] {
+            for style in [TreeStyle::TopDown, TreeStyle::Radial] {
+                let positions = tree_layout(node_count, &edges, root, style);
+                assert_eq!(positions.len(), node_count * 3);
+                assert!(positions.iter().all(|value| value.is_finite()), "{} nodes, edges {:?}, root {:?}", node_count, edges, root);
+            }
+        }
+        assert!(tree_layout(0, &[], None, TreeStyle::Radial).is_empty());
+    }
+}
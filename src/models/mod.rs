@@ -1,7 +1,11 @@
 use crate::models::data_model::GraphicsStatus;
+use crate::models::graphics_model::{ClusterSource, GraphEdit};
+use crate::models::layout_lib::cluster::{attribute_labels, label_propagation, ClusterGroups, MAX_GROUP_COUNT};
 use std::path::PathBuf;
 use crate::utils::file::{path_to_string, pick_folder};
-use crate::utils::message::message_info;
+use crate::utils::file_watcher::{diff_edges, EdgeFileWatcher};
+use crate::utils::message::{message_error, message_info, message_warning};
+use crate::utils::session::{read_session, write_session};
 
 use self::{app_model::ImportState, data_model::ExternalData};
 
@@ -9,11 +13,14 @@ pub mod app_model;
 pub mod data_model;
 pub mod graphics_lib;
 pub mod graphics_model;
+pub mod layout_lib;
+pub mod timeline_model;
 
 pub struct Models {
     pub graphics_model: graphics_model::GraphicsModel,
     pub data_model: data_model::DataModel,
     pub app_model: app_model::AppModel,
+    pub timeline_model: timeline_model::TimelineModel,
 }
 
 #[derive(Debug)]
@@ -26,6 +33,8 @@ pub struct ImportedData {
     pub target_index: usize,
     pub source_target_list: Vec<u32>,
     pub max_id: u32,
+    // 边的抽样比例，1 为全部导入
+    pub edge_sample_ratio: f32,
 }
 
 unsafe impl Send for ImportedData {}
@@ -41,6 +50,7 @@ impl Models {
             target_index,
             source_target_list,
             max_id,
+            edge_sample_ratio,
         } = data;
         self.data_model.node_data = node_data;
         self.data_model.edge_data = edge_data;
@@ -51,12 +61,23 @@ impl Models {
         self.data_model.set_status();
         self.app_model.node_file_path = Some(PathBuf::from(node_file_path));
         self.app_model.edge_file_path = Some(PathBuf::from(edge_file_path));
-        self.app_model.import_state = ImportState::Success;
-        self.app_model.is_import_visible = false;
-        self.graphics_model.graphics_resources.init_data(
+        self.app_model.is_edge_sampled = edge_sample_ratio < 1.0;
+        let result = self.graphics_model.graphics_resources.init_data(
             self.graphics_model.compute_render_state.clone(),
             &mut self.data_model,
         );
+        // 图数据超出 GPU 的限制或 Shader 验证失败时放弃导入，保留导入窗口显示原因
+        if let Err(error) = result {
+            let title = if self.graphics_model.graphics_resources.shader_error.is_some() { "Shader Validation Failed" } else { "Graph Too Large for GPU" };
+            self.clear_data();
+            self.app_model.import_state = ImportState::Error(error.clone());
+            message_error(title, error.as_str());
+            return;
+        }
+        self.timeline_model.clear();
+        self.timeline_model.rebuild(&self.data_model);
+        self.app_model.import_state = ImportState::Success;
+        self.app_model.is_import_visible = false;
         let text = format!(
             "Node file: {}  \nEdge file: {}",
             self.app_model.node_file_name().unwrap_or(""),
@@ -69,13 +90,205 @@ impl Models {
         self.app_model.import_state = ImportState::Initial;
         self.app_model.node_file_path = None;
         self.app_model.edge_file_path = None;
+        self.app_model.is_edge_sampled = false;
+        self.app_model.edge_watcher = None;
         self.data_model.node_data = ExternalData::default();
         self.data_model.edge_data = ExternalData::default();
         self.data_model.max_id = 0;
+        self.timeline_model.clear();
         self.graphics_model.reset();
         self.data_model.status = GraphicsStatus::default();
     }
 
+    pub fn save_session(&mut self, path: PathBuf) {
+        let session = self.graphics_model.graphics_resources.session(
+            path_to_string(&self.app_model.node_file_path).unwrap_or_default(),
+            path_to_string(&self.app_model.edge_file_path).unwrap_or_default(),
+        );
+        let session = match session {
+            Some(session) => session,
+            None => {
+                message_warning("Save Session Failed", "Wait until the multilevel layout reaches the original graph");
+                return;
+            }
+        };
+        match write_session(&path, &session) {
+            Ok(()) => message_info("Save Session Succeeded", path.display().to_string().as_str()),
+            Err(error) => message_error("Save Session Failed", error.to_string().as_str()),
+        }
+    }
+
+    pub fn open_session(&mut self, path: PathBuf) {
+        let session = match read_session(&path) {
+            Ok(session) => session,
+            Err(error) => {
+                message_error("Open Session Failed", error.to_string().as_str());
+                return;
+            }
+        };
+        if session.node_count() != self.data_model.status.node_count {
+            let text = format!(
+                "The session has {} nodes, but the current graph has {} nodes",
+                session.node_count(),
+                self.data_model.status.node_count
+            );
+            message_error("Open Session Failed", text.as_str());
+            return;
+        }
+        if !self.graphics_model.graphics_resources.is_node_editable() {
+            message_warning("Open Session Failed", "Wait until the multilevel layout reaches the original graph");
+            return;
+        }
+        let text = format!(
+            "Node file: {}  \nEdge file: {}  \nPinned nodes: {}",
+            session.node_file_path,
+            session.edge_file_path,
+            session.pinned_nodes.len()
+        );
+        self.graphics_model.graphics_resources.restore_session(session);
+        message_info("Open Session Succeeded", text.as_str());
+    }
+
+    // 增量修改图，不重新导入
+    pub fn apply_graph_edits(&mut self, edits: &[GraphEdit]) {
+        if edits.is_empty() { return; }
+        let result = self.graphics_model.graphics_resources.apply_edits(&mut self.data_model, edits);
+        match result {
+            Ok(()) => {
+                // 边数据的行已同步修改，重新解析时间，由 update_timeline 重新过滤
+                if self.timeline_model.is_enabled() {
+                    self.timeline_model.rebuild(&self.data_model);
+                }
+                let text = format!(
+                    "Edits: {}  \nNodes: {}  \nEdges: {}",
+                    edits.len(),
+                    self.data_model.status.node_count,
+                    self.data_model.status.edge_count
+                );
+                message_info("Update Graph Succeeded", text.as_str());
+            }
+            Err(error) => message_error("Update Graph Failed", error.as_str()),
+        }
+    }
+
+    // 每帧推进时间轴，窗口变化时只把活动的边写入 GPU
+    pub fn update_timeline(&mut self, delta_seconds: f64) {
+        let timeline = &mut self.timeline_model;
+        if !timeline.is_enabled() { return; }
+        timeline.advance(delta_seconds);
+
+        let graphics_resources = &mut self.graphics_model.graphics_resources;
+        let gpu_edge_count = match &graphics_resources.graph_resources {
+            Some(graph_resources) => graph_resources.status.edge_count,
+            None => return,
+        };
+        if !timeline.needs_apply(gpu_edge_count) || !graphics_resources.is_node_editable() { return; }
+
+        let source_target_list = match &self.data_model.source_target_list {
+            Some(source_target_list) => source_target_list,
+            None => return,
+        };
+        let active_edges = timeline.active_edges(source_target_list);
+        graphics_resources.apply_timeline(&active_edges, timeline.inactive_nodes());
+        timeline.set_applied(active_edges.len() / 2);
+    }
+
+    // 回到记录的某一帧，停止计算以便查看或导出
+    pub fn restore_layout_snapshot(&mut self, index: usize) {
+        self.graphics_model.set_computing(false);
+        if let Err(error) = self.graphics_model.graphics_resources.restore_snapshot(index) {
+            message_warning("Restore Layout Failed", error.as_str());
+        }
+    }
+
+    // 从当前查看的记录继续计算，丢弃其后的记录
+    pub fn resume_from_snapshot(&mut self) {
+        self.graphics_model.graphics_resources.layout_history.truncate_after_selected();
+        self.graphics_model.set_computing(true);
+    }
+
+    pub fn set_edge_watching(&mut self, is_watching: bool) {
+        if !is_watching {
+            self.app_model.edge_watcher = None;
+            return;
+        }
+        // 抽样后文件中多出的边会被当作新增的边，撤销抽样
+        if self.app_model.is_edge_sampled {
+            message_warning("Watch Edge File Failed", "The edges were sampled on import, reimport all edges to watch the file");
+            return;
+        }
+        let path = match &self.app_model.edge_file_path {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        let (source_index, target_index) = match (self.data_model.edge_source, self.data_model.edge_target) {
+            (Some(source_index), Some(target_index)) => (source_index, target_index),
+            _ => return,
+        };
+        self.app_model.edge_watcher = Some(EdgeFileWatcher::new(path, source_index, target_index));
+    }
+
+    // 每帧调用，应用边文件的变化
+    // 与当前图中的边比较，而不是与上一次读取的文件比较，修改失败或被其他操作改变的图不会与文件错位
+    pub fn poll_edge_watcher(&mut self) {
+        let messages = match &self.app_model.edge_watcher {
+            Some(edge_watcher) => edge_watcher.try_recv(),
+            None => return,
+        };
+        // 只有最后一次读取的文件内容有效
+        let new_edges = match messages.into_iter().last() {
+            Some(Ok(new_edges)) => new_edges,
+            Some(Err(error)) => {
+                message_error("Watch Edge File Failed", error.as_str());
+                return;
+            }
+            None => return,
+        };
+        let edges: Vec<(u32, u32)> = self.data_model.source_target_list.as_deref().unwrap_or(&[])
+            .chunks_exact(2)
+            .map(|edge| (edge[0], edge[1]))
+            .collect();
+        let edits = diff_edges(&edges, &new_edges);
+        self.apply_graph_edits(&edits);
+    }
+
+    // 按 Cluster 来源重新计算节点分组
+    pub fn update_cluster_groups(&mut self) {
+        let node_count = self.data_model.status.node_count;
+        let source = self.graphics_model.graphics_resources.compute_options.cluster_source;
+        let (labels, group_count) = match source {
+            ClusterSource::None => {
+                self.graphics_model.graphics_resources.set_cluster_groups(None);
+                return;
+            }
+            ClusterSource::Partition => {
+                let node_data = &self.data_model.node_data;
+                let column = match node_data.headers_str_index.get(&self.data_model.node_settings.color_partition.0) {
+                    Some(&column) => column,
+                    None => {
+                        message_warning("Update Cluster Failed", "Choose a partition attribute in Node Color first");
+                        self.graphics_model.graphics_resources.set_cluster_groups(None);
+                        return;
+                    }
+                };
+                attribute_labels(node_count, node_data.data.iter().map(|row| row.get(column).map_or("", String::as_str)))
+            }
+            ClusterSource::Community => {
+                let source_target_list = self.data_model.source_target_list.as_deref().unwrap_or(&[]);
+                label_propagation(node_count, source_target_list)
+            }
+        };
+        if group_count > MAX_GROUP_COUNT {
+            let text = format!("{} groups found, at most {} groups are supported", group_count, MAX_GROUP_COUNT);
+            message_warning("Update Cluster Failed", text.as_str());
+            self.graphics_model.graphics_resources.set_cluster_groups(None);
+            return;
+        }
+        self.graphics_model.graphics_resources.set_cluster_groups(Some(ClusterGroups::from_labels(labels, group_count)));
+        let text = format!("Source: {}  \nGroups: {}", source, group_count);
+        message_info("Update Cluster Succeeded", text.as_str());
+    }
+
     pub fn pick_output_folder_and_then(output_folder: &mut String, mut then: impl FnMut(&str) -> ()) {
         if output_folder.is_empty() {
             *output_folder = path_to_string(&pick_folder()).unwrap_or(output_folder.clone());
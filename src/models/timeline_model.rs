@@ -0,0 +1,321 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::data_model::{DataModel, ExternalData};
+
+// 没有时间或无法解析时间的边与节点始终活动
+const ALWAYS: (f64, f64) = (f64::NEG_INFINITY, f64::INFINITY);
+
+// 时间轴，按导入时指定的时间列过滤活动的边与节点
+// 当前窗口为 [current - window, current]，与窗口相交的区间视为活动
+pub struct TimelineModel {
+    edge_intervals:     Vec<(f64, f64)>,
+    node_intervals:     Vec<(f64, f64)>,
+    // 所有有效时间的最小值与最大值，没有时间列时为 None
+    pub range:          Option<(f64, f64)>,
+    // 时间列为日期时按日期显示
+    pub is_datetime:    bool,
+    pub current:        f64,
+    pub window:         f64,
+    pub is_playing:     bool,
+    // 从头播放到尾的秒数
+    pub duration:       f64,
+    // 上一次写入 GPU 的窗口与活动边数，用于判断是否需要重新写入
+    applied:            Option<((f64, f64), usize)>,
+}
+
+impl Default for TimelineModel {
+    fn default() -> Self {
+        Self {
+            edge_intervals: vec![],
+            node_intervals: vec![],
+            range: None,
+            is_datetime: false,
+            current: 0.0,
+            window: 0.0,
+            is_playing: false,
+            duration: 20.0,
+            applied: None,
+        }
+    }
+}
+
+impl TimelineModel {
+
+    // 按 data_model 中的时间列重新解析，导入或增量修改边之后调用
+    // 保留当前位置与窗口，首次启用时从头开始、窗口为整个范围
+    pub fn rebuild(&mut self, data_model: &DataModel) {
+
+        let columns = &data_model.time_columns;
+        let edge_count = data_model.status.edge_count;
+        let node_count = data_model.status.node_count;
+        let was_enabled = self.range.is_some();
+
+        self.is_datetime = false;
+        self.applied = None;
+
+        let (edge_intervals, is_edge_datetime) = read_intervals(&data_model.edge_data, &columns.edge_start, &columns.edge_end);
+        self.edge_intervals = edge_intervals;
+        self.edge_intervals.resize(edge_count, ALWAYS);
+
+        // 没有节点时间列时，节点从第一条边开始活动，到最后一条边结束
+        self.node_intervals = if columns.node_start.is_some() {
+            let (node_intervals, is_node_datetime) = read_intervals(&data_model.node_data, &columns.node_start, &columns.node_end);
+            self.is_datetime |= is_node_datetime;
+            node_intervals
+        } else {
+            let mut node_intervals = vec![(f64::INFINITY, f64::NEG_INFINITY); node_count];
+            let source_target_list = data_model.source_target_list.as_deref().unwrap_or(&[]);
+            for (edge, &(start, end)) in source_target_list.chunks_exact(2).zip(&self.edge_intervals) {
+                for &node in edge {
+                    if let Some(interval) = node_intervals.get_mut(node as usize) {
+                        interval.0 = interval.0.min(start);
+                        interval.1 = interval.1.max(end);
+                    }
+                }
+            }
+            node_intervals.into_iter()
+                .map(|interval| if interval.0 > interval.1 { ALWAYS } else { interval })
+                .collect()
+        };
+        self.node_intervals.resize(node_count, ALWAYS);
+        self.is_datetime |= is_edge_datetime;
+
+        let times = self.edge_intervals.iter().chain(&self.node_intervals)
+            .flat_map(|&(start, end)| [start, end])
+            .filter(|time| time.is_finite());
+        self.range = times.fold(None, |range, time| match range {
+            None => Some((time, time)),
+            Some((min, max)) => Some((time.min(min), time.max(max))),
+        });
+
+        match self.range {
+            Some((min, max)) if !was_enabled => {
+                self.current = min;
+                self.window = max - min;
+                self.is_playing = false;
+            }
+            Some((min, max)) => self.current = self.current.clamp(min, max),
+            None => self.is_playing = false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self {
+            duration: self.duration,
+            ..Default::default()
+        };
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.range.is_some()
+    }
+
+    pub fn window_range(&self) -> (f64, f64) {
+        (self.current - self.window.max(0.0), self.current)
+    }
+
+    // 播放时按帧间隔推进，到达末尾时停止
+    pub fn advance(&mut self, delta_seconds: f64) {
+        let (min, max) = match self.range {
+            Some(range) if self.is_playing => range,
+            _ => return,
+        };
+        self.current += (max - min) * delta_seconds / self.duration.max(0.1);
+        if self.current >= max {
+            self.current = max;
+            self.is_playing = false;
+        }
+    }
+
+    pub fn play(&mut self) {
+        if let Some((min, max)) = self.range {
+            if self.current >= max { self.current = min; }
+            self.is_playing = true;
+        }
+    }
+
+    fn is_active(&self, interval: (f64, f64)) -> bool {
+        let (start, end) = self.window_range();
+        interval.0 <= end && interval.1 >= start
+    }
+
+    // 当前窗口内的边，格式与 source_target_list 相同
+    pub fn active_edges(&self, source_target_list: &[u32]) -> Vec<u32> {
+        source_target_list.chunks_exact(2)
+            .zip(&self.edge_intervals)
+            .filter(|(_, &interval)| self.is_active(interval))
+            .flat_map(|(edge, _)| edge.iter().copied())
+            .collect()
+    }
+
+    pub fn inactive_nodes(&self) -> Vec<bool> {
+        self.node_intervals.iter().map(|&interval| !self.is_active(interval)).collect()
+    }
+
+    // 窗口或 GPU 上的边数变化后需要重新写入
+    pub fn needs_apply(&self, gpu_edge_count: usize) -> bool {
+        match self.applied {
+            Some((window, edge_count)) => window != self.window_range() || edge_count != gpu_edge_count,
+            None => true,
+        }
+    }
+
+    pub fn set_applied(&mut self, edge_count: usize) {
+        self.applied = Some((self.window_range(), edge_count));
+    }
+
+    // 按边的起始时间统计每个时间段的边数
+    pub fn histogram(&self, bin_count: usize) -> Vec<u32> {
+        let mut bins = vec![0; bin_count];
+        let (min, max) = match self.range {
+            Some(range) if bin_count > 0 => range,
+            _ => return bins,
+        };
+        let span = (max - min).max(f64::EPSILON);
+        for &(start, _) in &self.edge_intervals {
+            if start.is_finite() {
+                let bin = ((start - min) / span * bin_count as f64) as usize;
+                bins[bin.min(bin_count - 1)] += 1;
+            }
+        }
+        bins
+    }
+
+    pub fn format_time(&self, time: f64) -> String {
+        if !self.is_datetime {
+            return format!("{}", (time * 1000.0).round() / 1000.0);
+        }
+        match DateTime::<Utc>::from_timestamp(time.floor() as i64, 0) {
+            Some(date_time) => date_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => String::from("-"),
+        }
+    }
+}
+
+// 读取一列或两列时间，返回每行的区间与是否为日期
+fn read_intervals(data: &ExternalData, start: &Option<String>, end: &Option<String>) -> (Vec<(f64, f64)>, bool) {
+
+    let column = |name: &Option<String>| name.as_ref().and_then(|name| data.headers_str_index.get(name).copied());
+    let (start_column, end_column) = match column(start) {
+        Some(start_column) => (start_column, column(end)),
+        None => return (vec![ALWAYS; data.data.len()], false),
+    };
+
+    let mut is_datetime = false;
+    let mut parse = |row: &Vec<String>, column: usize| {
+        let (time, is_date) = parse_time(row.get(column).map_or("", String::as_str))?;
+        is_datetime |= is_date;
+        Some(time)
+    };
+
+    let intervals = data.data.iter().map(|row| {
+        let start = match parse(row, start_column) {
+            Some(start) => start,
+            None => return ALWAYS,
+        };
+        let end = end_column.and_then(|end_column| parse(row, end_column)).unwrap_or(start);
+        (start, end.max(start))
+    }).collect();
+
+    (intervals, is_datetime)
+}
+
+// 支持数值与常见的日期格式，日期转换为 Unix 时间戳（秒）
+pub fn parse_time(field: &str) -> Option<(f64, bool)> {
+    let field = field.trim();
+    if let Ok(value) = field.parse::<f64>() {
+        return value.is_finite().then_some((value, false));
+    }
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(field) {
+        return Some((date_time.timestamp() as f64, true));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M:%S"] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(field, format) {
+            return Some((date_time.and_utc().timestamp() as f64, true));
+        }
+    }
+    for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(field, format) {
+            return Some((date.and_hms_opt(0, 0, 0)?.and_utc().timestamp() as f64, true));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::models::data_model::TimeColumns;
+
+    #[test]
+    fn parses_time_formats() {
+        assert_eq!(parse_time(" 12.5 "), Some((12.5, false)));
+        assert_eq!(parse_time("-3"), Some((-3.0, false)));
+        assert_eq!(parse_time("2021-01-02"), Some((1609545600.0, true)));
+        assert_eq!(parse_time("2021/01/02"), Some((1609545600.0, true)));
+        assert_eq!(parse_time("2021-01-02 03:04:05"), Some((1609556645.0, true)));
+        assert_eq!(parse_time("2021-01-02T03:04:05"), Some((1609556645.0, true)));
+        assert_eq!(parse_time("2021/01/02 03:04:05"), Some((1609556645.0, true)));
+        // RFC 3339 按时区换算为 UTC
+        assert_eq!(parse_time("2021-01-02T04:04:05+01:00"), Some((1609556645.0, true)));
+        assert_eq!(parse_time("inf"), None);
+        assert_eq!(parse_time("NaN"), None);
+        assert_eq!(parse_time("yesterday"), None);
+        assert_eq!(parse_time(""), None);
+    }
+
+    fn timeline(times: &[&str]) -> (TimelineModel, Vec<u32>) {
+        let source_target_list: Vec<u32> = (0..times.len() as u32).flat_map(|index| [index, index + 1]).collect();
+        let mut data_model = DataModel {
+            edge_data: ExternalData {
+                headers_str_index: HashMap::from([(String::from("time"), 0)]),
+                headers_index_str: vec![String::from("time")],
+                data: times.iter().map(|time| vec![time.to_string()]).collect(),
+            },
+            time_columns: TimeColumns { edge_start: Some(String::from("time")), ..Default::default() },
+            source_target_list: Some(source_target_list.clone()),
+            ..Default::default()
+        };
+        data_model.status.edge_count = times.len();
+        data_model.status.node_count = times.len() + 1;
+
+        let mut timeline = TimelineModel::default();
+        timeline.rebuild(&data_model);
+        (timeline, source_target_list)
+    }
+
+    #[test]
+    fn filters_edges_by_window() {
+        let (mut timeline, source_target_list) = timeline(&["0", "10", "20", "", "30"]);
+        assert_eq!(timeline.range, Some((0.0, 30.0)));
+        assert!(!timeline.is_datetime);
+
+        // 首次启用时位于开头，窗口为整个范围
+        assert_eq!(timeline.window_range(), (-30.0, 0.0));
+        assert_eq!(timeline.active_edges(&source_target_list), vec![0, 1, 3, 4]);
+
+        // 窗口 [10, 20] 包含两端，没有时间的边始终活动
+        timeline.current = 20.0;
+        timeline.window = 10.0;
+        assert_eq!(timeline.active_edges(&source_target_list), vec![1, 2, 2, 3, 3, 4]);
+
+        // 节点在其边的时间范围内活动
+        assert_eq!(timeline.inactive_nodes(), vec![true, false, false, false, false, true]);
+
+        timeline.current = 9.999;
+        timeline.window = 0.0;
+        assert_eq!(timeline.active_edges(&source_target_list), vec![3, 4]);
+    }
+
+    #[test]
+    fn counts_histogram_bins() {
+        let (timeline, _) = timeline(&["2021-01-01", "2021-01-02", "2021-01-02", "2021-01-05"]);
+        assert!(timeline.is_datetime);
+        // 最大值落在最后一个分段中
+        assert_eq!(timeline.histogram(4), vec![1, 2, 0, 1]);
+        assert_eq!(timeline.histogram(0), Vec::<u32>::new());
+        assert_eq!(timeline.format_time(1609556645.5), "2021-01-02 03:04:05");
+    }
+}
@@ -14,4 +14,8 @@ pub const MULTISAMPLE_STATE: wgpu::MultisampleState = wgpu::MultisampleState {
 
 pub const TEXTURE_FORMAT:       wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
 pub const CAST_TEXTURE_FORMAT:  wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Uint;
-pub const DEPTH_FORMAT:         wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
\ No newline at end of file
+pub const DEPTH_FORMAT:         wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// 加权混合顺序无关透明的累积与透射率目标
+pub const OIT_ACCUM_FORMAT:     wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+pub const OIT_REVEAL_FORMAT:    wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
\ No newline at end of file
@@ -53,15 +53,69 @@ pub fn read_from_csv(path: &Option<PathBuf>) -> Result<ExternalData, String> {
     })
 }
 
+// 导入前的统计，用于估算节点数和显存
+#[derive(Default, Clone)]
+pub struct CsvSummary {
+    pub row_count: usize,
+    // 每一列的最大非负整数，含有其他值的列为 None
+    pub column_max: Vec<Option<u32>>,
+}
+
+pub fn summarize_csv(path: &Option<PathBuf>) -> Result<CsvSummary, String> {
+    let path = path.as_deref().ok_or("Can't find file")?;
+    let err_fomatter = |err| format!("{}", err);
+
+    let mut rdr = csv::Reader::from_path(path).map_err(err_fomatter)?;
+    let column_count = rdr.byte_headers().map_err(err_fomatter)?.len();
+    let mut summary = CsvSummary {
+        row_count: 0,
+        column_max: vec![Some(0); column_count],
+    };
+
+    let mut record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record).map_err(err_fomatter)? {
+        summary.row_count += 1;
+        for (column_max, field) in summary.column_max.iter_mut().zip(record.iter()) {
+            if let Some(max) = column_max {
+                *column_max = std::str::from_utf8(field).ok()
+                    .and_then(|field| field.parse::<u32>().ok())
+                    .map(|value| value.max(*max));
+            }
+        }
+    }
+    Ok(summary)
+}
+
+// 以行号的哈希决定是否保留，同一比例下抽样结果固定
+fn is_sampled(index: usize, sample_ratio: f32) -> bool {
+    let mut hash = index as u64 ^ 0x9e37_79b9_7f4a_7c15;
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    ((hash >> 40) as f32 / (1u64 << 24) as f32) < sample_ratio
+}
+
+// edge_sample_ratio 小于 1 时按比例抽样边，用于超出显存预算的图
 pub fn load_data(
     node_file_path: &str,
     edge_file_path: &str,
     source_index: usize,
     target_index: usize,
+    edge_sample_ratio: f32,
 ) -> Result<ImportedData, String> {
     let node_data =
         read_from_csv(&Some(PathBuf::from(node_file_path))).unwrap_or(ExternalData::default());
-    let edge_data = read_from_csv(&Some(PathBuf::from(edge_file_path)))?;
+    let mut edge_data = read_from_csv(&Some(PathBuf::from(edge_file_path)))?;
+    if edge_sample_ratio < 1.0 {
+        edge_data.data = edge_data.data.into_iter()
+            .enumerate()
+            .filter(|(index, _)| is_sampled(*index, edge_sample_ratio))
+            .map(|(_, record)| record)
+            .collect();
+        if edge_data.data.is_empty() {
+            return Err(String::from("No edges left after sampling"));
+        }
+    }
     let err_mapper = |_| String::from("Source and target isn't uint");
     let source_target_list  = (0..edge_data.data.len() * 2).into_par_iter()
         .map::<_, Result<u32, String>>(|index| {
@@ -83,5 +137,71 @@ pub fn load_data(
         source_index,
         target_index,
         max_id,
+        edge_sample_ratio: edge_sample_ratio.min(1.0),
     })
 }
+
+// 只读取边文件中的起点和终点，用于监视文件变化
+pub fn read_edge_pairs(path: &PathBuf, source_index: usize, target_index: usize) -> Result<Vec<(u32, u32)>, String> {
+    let err_fomatter = |err| format!("{}", err);
+
+    let mut rdr = csv::Reader::from_path(path).map_err(err_fomatter)?;
+    let mut record = csv::StringRecord::new();
+    let mut pairs = vec![];
+    while rdr.read_record(&mut record).map_err(err_fomatter)? {
+        let parse = |index: usize| record.get(index)
+            .and_then(|field| field.parse::<u32>().ok())
+            .ok_or_else(|| String::from("Source and target isn't uint"));
+        pairs.push((parse(source_index)?, parse(target_index)?));
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_csv(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn summarizes_columns() {
+        let path = write_csv("graphpu_summary.csv", "source,target,weight,label\n3,4,1,a\n10,2,0.5,b\n7,8,2,c\n");
+        let summary = summarize_csv(&Some(path.clone())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(summary.row_count, 3);
+        // 出现非整数后该列为 None
+        assert_eq!(summary.column_max, vec![Some(10), Some(8), None, None]);
+    }
+
+    #[test]
+    fn samples_stable_fraction() {
+        let row_count = 100_000;
+        for ratio in [0.01, 0.1, 0.5, 0.9] {
+            let sampled: Vec<usize> = (0..row_count).filter(|&index| is_sampled(index, ratio)).collect();
+            let expected = ratio * row_count as f32;
+            assert!((sampled.len() as f32 - expected).abs() < expected * 0.05 + 50.0, "ratio {} kept {}", ratio, sampled.len());
+            assert_eq!(sampled, (0..row_count).filter(|&index| is_sampled(index, ratio)).collect::<Vec<usize>>());
+        }
+        assert!((0..1000).all(|index| is_sampled(index, 1.0)));
+        assert!((0..1000).all(|index| !is_sampled(index, 0.0)));
+        // 比例越小，保留的行是比例更大时的子集
+        assert!((0..row_count).all(|index| !is_sampled(index, 0.1) || is_sampled(index, 0.5)));
+    }
+
+    #[test]
+    fn rejects_empty_sample() {
+        let path = write_csv("graphpu_sample.csv", "source,target\n0,1\n1,2\n2,0\n");
+        let path_string = path.to_string_lossy().to_string();
+        let result = load_data("", &path_string, 0, 1, 0.0001);
+        let full = load_data("", &path_string, 0, 1, 1.0);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.err(), Some(String::from("No edges left after sampling")));
+        assert_eq!(full.unwrap().source_target_list, vec![0, 1, 1, 2, 2, 0]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use strum::Display;
+
+#[derive(Display, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    #[strum(serialize = "PCACHE")]
+    Pcache,
+    #[strum(serialize = "CSV")]
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Pcache => "pcache",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    // 按扩展名判断导出格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "pcache" => Some(ExportFormat::Pcache),
+            "csv" | "txt" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+pub enum ColumnValues {
+    Float(Vec<f32>),
+    Uint(Vec<u32>),
+}
+
+// 导出的一列，PCACHE 中为一个 property，CSV 中为一列
+pub struct ExportColumn {
+    pub name:   String,
+    pub values: ColumnValues,
+}
+
+impl ExportColumn {
+    pub fn float(name: &str, values: Vec<f32>) -> Self {
+        Self { name: String::from(name), values: ColumnValues::Float(values) }
+    }
+
+    pub fn uint(name: &str, values: Vec<u32>) -> Self {
+        Self { name: String::from(name), values: ColumnValues::Uint(values) }
+    }
+
+    // 长度为 count * 3 的 x / y / z 坐标拆分为三列
+    pub fn positions(positions: &[f32]) -> Vec<Self> {
+        ["position.x", "position.y", "position.z"].iter().enumerate()
+            .map(|(axis, name)| Self::float(name, positions.iter().skip(axis).step_by(3).copied().collect()))
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        match &self.values {
+            ColumnValues::Float(values) => values.len(),
+            ColumnValues::Uint(values) => values.len(),
+        }
+    }
+}
+
+// 所有列的长度须相同，comment 只写入 PCACHE
+pub fn write_columns(path: &Path, format: ExportFormat, comment: &str, columns: &[ExportColumn]) -> std::io::Result<()> {
+    let element_count = columns.first().map_or(0, ExportColumn::len);
+    if columns.iter().any(|column| column.len() != element_count) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Columns have different lengths"));
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    match format {
+        ExportFormat::Pcache => write_pcache(&mut file, comment, element_count, columns)?,
+        ExportFormat::Csv => write_csv(&mut file, element_count, columns)?,
+    }
+    file.flush()
+}
+
+// 文本头 + 按元素交错排列的小端二进制数据
+fn write_pcache(file: &mut impl Write, comment: &str, element_count: usize, columns: &[ExportColumn]) -> std::io::Result<()> {
+    file.write_all(b"pcache\n")?;
+    file.write_fmt(format_args!("comment {}\n", comment))?;
+    file.write_all(b"format binary 1.0\n")?;
+    file.write_fmt(format_args!("elements {}\n", element_count))?;
+    for column in columns {
+        let type_string = match column.values {
+            ColumnValues::Float(_) => "float",
+            ColumnValues::Uint(_) => "uint",
+        };
+        file.write_fmt(format_args!("property {} {}\n", type_string, column.name))?;
+    }
+    file.write_all(b"end_header\n")?;
+
+    for index in 0..element_count {
+        for column in columns {
+            match &column.values {
+                ColumnValues::Float(values) => file.write_all(&values[index].to_le_bytes())?,
+                ColumnValues::Uint(values) => file.write_all(&values[index].to_le_bytes())?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_csv(file: &mut impl Write, element_count: usize, columns: &[ExportColumn]) -> std::io::Result<()> {
+    let header: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+    file.write_fmt(format_args!("{}\n", header.join(",")))?;
+    for index in 0..element_count {
+        let row: Vec<String> = columns.iter()
+            .map(|column| match &column.values {
+                ColumnValues::Float(values) => values[index].to_string(),
+                ColumnValues::Uint(values) => values[index].to_string(),
+            })
+            .collect();
+        file.write_fmt(format_args!("{}\n", row.join(",")))?;
+    }
+    Ok(())
+}
@@ -18,6 +18,22 @@ pub fn pick_csv() -> Option<PathBuf> {
         .pick_file()
 }
 
+pub fn pick_session() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("GraphPU Session", &["gpsession"])
+        .pick_file()
+}
+
+pub fn save_session_file() -> Option<PathBuf> {
+    let dir = desktop_dir_or_empty();
+
+    rfd::FileDialog::new()
+        .set_directory(dir)
+        .add_filter("GraphPU Session", &["gpsession"])
+        .set_file_name("session.gpsession")
+        .save_file()
+}
+
 pub fn path_to_string(path: &Option<PathBuf>) -> Option<String> {
     path.as_ref().map(|path| path.display().to_string())
 }
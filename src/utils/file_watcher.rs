@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::models::graphics_model::GraphEdit;
+use crate::utils::csv_loader::read_edge_pairs;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// 轮询边文件的修改时间，文件变化后发送文件中的全部边，由主线程与当前的图比较
+// 在后台线程中读取文件，Drop 时停止
+pub struct EdgeFileWatcher {
+    receiver: Receiver<Result<Vec<(u32, u32)>, String>>,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl EdgeFileWatcher {
+
+    pub fn new(path: PathBuf, source_index: usize, target_index: usize) -> Self {
+
+        let (sender, receiver) = mpsc::channel();
+        let is_stopped = Arc::new(AtomicBool::new(false));
+        let is_thread_stopped = is_stopped.clone();
+
+        thread::spawn(move || {
+            let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+
+            let mut last_modified: Option<SystemTime> = modified(&path);
+
+            while !is_thread_stopped.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                let current_modified = modified(&path);
+                if current_modified.is_none() || current_modified == last_modified { continue; }
+                last_modified = current_modified;
+
+                let message = read_edge_pairs(&path, source_index, target_index);
+                if sender.send(message).is_err() { break; }
+            }
+        });
+
+        Self {
+            receiver,
+            is_stopped,
+        }
+    }
+
+    // 取出目前收到的全部读取结果，不阻塞
+    pub fn try_recv(&self) -> Vec<Result<Vec<(u32, u32)>, String>> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for EdgeFileWatcher {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+// 按多重集合比较两组边，先删除后添加
+pub fn diff_edges(old_edges: &[(u32, u32)], new_edges: &[(u32, u32)]) -> Vec<GraphEdit> {
+
+    let mut counts: HashMap<(u32, u32), i64> = HashMap::new();
+    for &edge in old_edges {
+        *counts.entry(edge).or_default() -= 1;
+    }
+    for &edge in new_edges {
+        *counts.entry(edge).or_default() += 1;
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().filter(|(_, count)| *count != 0).collect();
+    counts.sort();
+
+    let removed = counts.iter()
+        .filter(|(_, count)| *count < 0)
+        .flat_map(|&((source, target), count)| (0..-count).map(move |_| GraphEdit::RemoveEdge(source, target)));
+    let added = counts.iter()
+        .filter(|(_, count)| *count > 0)
+        .flat_map(|&((source, target), count)| (0..count).map(move |_| GraphEdit::AddEdge(source, target)));
+
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_reordering() {
+        let old_edges = [(0, 1), (1, 2), (2, 3)];
+        let new_edges = [(2, 3), (0, 1), (1, 2)];
+        assert_eq!(diff_edges(&old_edges, &new_edges), vec![]);
+    }
+
+    #[test]
+    fn diffs_duplicate_edges() {
+        let old_edges = [(0, 1), (0, 1), (0, 1), (1, 2)];
+        let new_edges = [(1, 2), (0, 1), (1, 2), (1, 0)];
+        // 方向不同的边不相同，重复的边按次数增删
+        assert_eq!(diff_edges(&old_edges, &new_edges), vec![
+            GraphEdit::RemoveEdge(0, 1),
+            GraphEdit::RemoveEdge(0, 1),
+            GraphEdit::AddEdge(1, 0),
+            GraphEdit::AddEdge(1, 2),
+        ]);
+    }
+
+    #[test]
+    fn removes_before_adding() {
+        let edits = diff_edges(&[(3, 4), (0, 1)], &[(5, 6), (2, 2)]);
+        assert_eq!(edits, vec![
+            GraphEdit::RemoveEdge(0, 1),
+            GraphEdit::RemoveEdge(3, 4),
+            GraphEdit::AddEdge(2, 2),
+            GraphEdit::AddEdge(5, 6),
+        ]);
+        assert_eq!(diff_edges(&[], &[]), vec![]);
+    }
+}
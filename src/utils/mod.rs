@@ -1,4 +1,7 @@
 pub mod csv_loader;
+pub mod export;
+pub mod file_watcher;
 pub mod file;
 pub mod message;
+pub mod session;
 pub mod constant;
\ No newline at end of file
@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::path::Path;
+
+// 布局会话，保存节点坐标和固定（Pinned）节点
+// 文件格式与 PCACHE 类似：文本头 + 二进制数据（小端 f32 坐标，随后为 u32 节点编号）
+pub struct Session {
+    pub node_file_path: String,
+    pub edge_file_path: String,
+    pub positions: Vec<f32>,
+    pub pinned_nodes: Vec<u32>,
+}
+
+impl Session {
+    pub fn node_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+}
+
+pub fn write_session(path: &Path, session: &Session) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(b"graphpu_session\n")?;
+    file.write_all(b"format binary 1.0\n")?;
+    file.write_fmt(format_args!("node_file {}\n", session.node_file_path))?;
+    file.write_fmt(format_args!("edge_file {}\n", session.edge_file_path))?;
+    file.write_fmt(format_args!("node_count {}\n", session.node_count()))?;
+    file.write_fmt(format_args!("pinned_count {}\n", session.pinned_nodes.len()))?;
+    file.write_all(b"end_header\n")?;
+    // 按小端写入，与 read_session 和平台的字节序无关
+    let positions: Vec<u8> = session.positions.iter().flat_map(|value| value.to_le_bytes()).collect();
+    let pinned_nodes: Vec<u8> = session.pinned_nodes.iter().flat_map(|value| value.to_le_bytes()).collect();
+    file.write_all(&positions)?;
+    file.write_all(&pinned_nodes)?;
+    Ok(())
+}
+
+pub fn read_session(path: &Path) -> std::io::Result<Session> {
+    let file = File::open(path)?;
+    let file_length = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut session = Session {
+        node_file_path: String::new(),
+        edge_file_path: String::new(),
+        positions: vec![],
+        pinned_nodes: vec![],
+    };
+    let mut node_count = 0usize;
+    let mut pinned_count = 0usize;
+
+    let mut line = String::new();
+    // 文本头的字节数，用于检查二进制数据的长度
+    let mut header_length = reader.read_line(&mut line)? as u64;
+    if line.trim_end() != "graphpu_session" {
+        return Err(invalid_data("Not a GraphPU session file"));
+    }
+
+    loop {
+        line.clear();
+        let line_length = reader.read_line(&mut line)?;
+        if line_length == 0 {
+            return Err(invalid_data("Missing end_header"));
+        }
+        header_length += line_length as u64;
+        let line = line.trim_end();
+        if line == "end_header" { break; }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "format" if value != "binary 1.0" => return Err(invalid_data("Unsupported session format")),
+            "node_file" => session.node_file_path = value.to_string(),
+            "edge_file" => session.edge_file_path = value.to_string(),
+            "node_count" => node_count = value.parse().map_err(|_| invalid_data("Invalid node_count"))?,
+            "pinned_count" => pinned_count = value.parse().map_err(|_| invalid_data("Invalid pinned_count"))?,
+            _ => {}
+        }
+    }
+
+    // 头中的数量来自文件，分配内存前先检查溢出与文件的剩余长度
+    let position_length = node_count.checked_mul(3 * 4).ok_or_else(|| invalid_data("Invalid node_count"))?;
+    let pinned_length = pinned_count.checked_mul(4).ok_or_else(|| invalid_data("Invalid pinned_count"))?;
+    let data_length = position_length.checked_add(pinned_length).ok_or_else(|| invalid_data("Invalid pinned_count"))?;
+    if data_length as u64 > file_length.saturating_sub(header_length) {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Session file is truncated"));
+    }
+
+    let mut bytes = vec![0u8; position_length];
+    reader.read_exact(&mut bytes)?;
+    session.positions = bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+
+    let mut bytes = vec![0u8; pinned_length];
+    reader.read_exact(&mut bytes)?;
+    session.pinned_nodes = bytes.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+
+    Ok(session)
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session {
+            node_file_path: String::from("nodes.csv"),
+            edge_file_path: String::from("edges.csv"),
+            positions: vec![0.0, 1.0, 2.0, -3.5, 4.25, f32::MAX],
+            pinned_nodes: vec![1],
+        }
+    }
+
+    #[test]
+    fn round_trips_session() {
+        let path = std::env::temp_dir().join("graphpu_round_trip.session");
+        write_session(&path, &session()).unwrap();
+        let read = read_session(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.node_file_path, "nodes.csv");
+        assert_eq!(read.edge_file_path, "edges.csv");
+        assert_eq!(read.positions, session().positions);
+        assert_eq!(read.pinned_nodes, vec![1]);
+    }
+
+    #[test]
+    fn writes_little_endian_data() {
+        let path = std::env::temp_dir().join("graphpu_little_endian.session");
+        write_session(&path, &session()).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // 最后依次为 f32::MAX 与节点编号 1
+        assert_eq!(bytes[bytes.len() - 8..], [0xff, 0xff, 0x7f, 0x7f, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_truncated_session() {
+        let path = std::env::temp_dir().join("graphpu_truncated.session");
+        write_session(&path, &session()).unwrap();
+        let length = std::fs::metadata(&path).unwrap().len();
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(length - 2).unwrap();
+        let error = read_session(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_oversized_counts() {
+        let path = std::env::temp_dir().join("graphpu_oversized.session");
+        let header = format!("graphpu_session\nnode_count {}\npinned_count 0\nend_header\n", usize::MAX / 4);
+        std::fs::write(&path, header).unwrap();
+        let error = read_session(&path).err().unwrap();
+        std::fs::write(&path, "graphpu_session\nnode_count 1000000000\nend_header\n").unwrap();
+        let truncated_error = read_session(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(truncated_error.kind(), ErrorKind::UnexpectedEof);
+    }
+}